@@ -0,0 +1,392 @@
+use std::cmp::Reverse;
+
+use crate::utils::{Character, STAT_LIST, Stat, Stats};
+
+/// Whether `a` is at least as good as `b` on every given stat and better on
+/// at least one.
+pub fn dominates(a: &Stats, b: &Stats, stats: &[Stat]) -> bool {
+    stats.iter().all(|stat| a.get(*stat) >= b.get(*stat))
+        && stats.iter().any(|stat| a.get(*stat) > b.get(*stat))
+}
+
+/// Numbers of the characters no other character dominates on the given stats,
+/// in the order they were given. Characters without stats are never part of
+/// the frontier.
+pub fn pareto_frontier<'a>(characters: impl IntoIterator<Item = &'a Character>, stats: &[Stat]) -> Vec<u16> {
+    let mut candidates: Vec<(u16, &Stats, u32)> = characters
+        .into_iter()
+        .filter_map(|character| {
+            let stats_of = character.stats.as_ref()?;
+            let total = stats.iter().map(|stat| stats_of.get(*stat) as u32).sum();
+            Some((character.number, stats_of, total))
+        })
+        .collect();
+
+    let order: Vec<u16> = candidates.iter().map(|(number, _, _)| *number).collect();
+
+    // A character can only be dominated by one with a higher total, and any
+    // dominated character is dominated by a member of the frontier, so
+    // comparing against the frontier built so far is enough.
+    candidates.sort_by_key(|(_, _, total)| Reverse(*total));
+
+    let mut frontier: Vec<(u16, &Stats)> = Vec::new();
+    for (number, candidate, _) in candidates {
+        if !frontier.iter().any(|(_, member)| dominates(member, candidate, stats)) {
+            frontier.push((number, candidate));
+        }
+    }
+
+    order
+        .into_iter()
+        .filter(|number| frontier.iter().any(|(member, _)| member == number))
+        .collect()
+}
+
+/// How the stat profiles of two characters are compared.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Similarity {
+    /// Angle between the stat vectors, comparing the shape of the profiles
+    /// regardless of their magnitude.
+    Cosine,
+    /// Distance between the stats once each is scaled by its standard
+    /// deviation over the characters, so that every stat weighs the same.
+    Euclidean,
+}
+
+impl Similarity {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Similarity::Cosine => "Cosine",
+            Similarity::Euclidean => "Normalized Euclidean",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimilarOptions {
+    pub similarity: Similarity,
+    /// Maximum number of characters returned.
+    pub count: usize,
+    pub same_element: bool,
+    pub same_position: bool,
+}
+
+impl Default for SimilarOptions {
+    fn default() -> Self {
+        SimilarOptions {
+            similarity: Similarity::Cosine,
+            count: 10,
+            same_element: false,
+            same_position: false,
+        }
+    }
+}
+
+/// The characters whose stat profile is closest to the target's, closest
+/// first, with their distance: `1 - cosine` for the cosine similarity, the
+/// normalized distance otherwise. The target itself and characters without
+/// stats are left out.
+pub fn similar_characters<'a>(target: &Character, characters: &'a [Character], options: &SimilarOptions) -> Vec<(&'a Character, f64)> {
+    let Some(target_stats) = &target.stats else {
+        return Vec::new();
+    };
+
+    let vector = |stats: &Stats| STAT_LIST.map(|stat| stats.get(stat) as f64);
+    let target_vector = vector(target_stats);
+
+    let scales = match options.similarity {
+        Similarity::Cosine => [1.0; 7],
+        Similarity::Euclidean => standard_deviations(characters),
+    };
+
+    let mut neighbors: Vec<(&Character, f64)> = characters
+        .iter()
+        .filter(|character| character.number != target.number)
+        .filter(|character| !options.same_element || character.element == target.element)
+        .filter(|character| !options.same_position || character.position == target.position)
+        .filter_map(|character| {
+            let other = vector(character.stats.as_ref()?);
+
+            let distance = match options.similarity {
+                Similarity::Cosine => {
+                    let dot: f64 = target_vector.iter().zip(other).map(|(a, b)| a * b).sum();
+                    let norms = norm(&target_vector) * norm(&other);
+                    if norms == 0.0 { 1.0 } else { 1.0 - dot / norms }
+                }
+                Similarity::Euclidean => target_vector
+                    .iter()
+                    .zip(other)
+                    .zip(scales)
+                    .map(|((a, b), scale)| ((a - b) / scale).powi(2))
+                    .sum::<f64>()
+                    .sqrt(),
+            };
+
+            Some((character, distance))
+        })
+        .collect();
+
+    neighbors.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.number.cmp(&b.0.number)));
+    neighbors.truncate(options.count);
+    neighbors
+}
+
+fn norm(vector: &[f64; 7]) -> f64 {
+    vector.iter().map(|value| value * value).sum::<f64>().sqrt()
+}
+
+/// Standard deviation of each stat over the characters, 1 when it is zero so
+/// it can be divided by.
+fn standard_deviations(characters: &[Character]) -> [f64; 7] {
+    let stats: Vec<&Stats> = characters.iter().filter_map(|character| character.stats.as_ref()).collect();
+    if stats.is_empty() {
+        return [1.0; 7];
+    }
+
+    let count = stats.len() as f64;
+
+    STAT_LIST.map(|stat| {
+        let mean = stats.iter().map(|stats| stats.get(stat) as f64).sum::<f64>() / count;
+        let variance = stats.iter().map(|stats| (stats.get(stat) as f64 - mean).powi(2)).sum::<f64>() / count;
+
+        if variance == 0.0 { 1.0 } else { variance.sqrt() }
+    })
+}
+
+/// Summary statistics of one stat over a set of characters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatSummary {
+    pub count: usize,
+    pub mean: f64,
+    pub min: u8,
+    pub max: u8,
+    pub p10: f64,
+    pub p25: f64,
+    pub median: f64,
+    pub p75: f64,
+    pub p90: f64,
+}
+
+/// Sorted values of every stat over a set of characters, answering
+/// percentile questions such as how common 85 Kick is.
+#[derive(Debug, Clone, Default)]
+pub struct StatDistribution {
+    /// Values of each stat in `STAT_LIST` order, sorted.
+    sorted: [Vec<u8>; 7],
+}
+
+impl StatDistribution {
+    /// Characters without stats are ignored.
+    pub fn new<'a>(characters: impl IntoIterator<Item = &'a Character>) -> StatDistribution {
+        let mut sorted: [Vec<u8>; 7] = Default::default();
+
+        for stats in characters.into_iter().filter_map(|character| character.stats.as_ref()) {
+            for (values, stat) in sorted.iter_mut().zip(STAT_LIST) {
+                values.push(stats.get(stat));
+            }
+        }
+
+        for values in sorted.iter_mut() {
+            values.sort_unstable();
+        }
+
+        StatDistribution { sorted }
+    }
+
+    pub fn count(&self) -> usize {
+        self.sorted[0].len()
+    }
+
+    pub fn values(&self, stat: Stat) -> &[u8] {
+        &self.sorted[stat.index()]
+    }
+
+    /// Value below which the given percentage of the values fall, linearly
+    /// interpolated between the closest values.
+    pub fn percentile(&self, stat: Stat, percent: f64) -> Option<f64> {
+        let values = self.values(stat);
+        if values.is_empty() {
+            return None;
+        }
+
+        let rank = percent.clamp(0.0, 100.0) / 100.0 * (values.len() - 1) as f64;
+        let lower = values[rank.floor() as usize] as f64;
+        let upper = values[rank.ceil() as usize] as f64;
+
+        Some(lower + (upper - lower) * rank.fract())
+    }
+
+    /// Percentage of the values below the given one, counting equal values
+    /// as half below.
+    pub fn percentile_rank(&self, stat: Stat, value: u8) -> Option<f64> {
+        let values = self.values(stat);
+        if values.is_empty() {
+            return None;
+        }
+
+        let below = values.partition_point(|other| *other < value);
+        let equal = values[below..].partition_point(|other| *other == value);
+
+        Some((below as f64 + equal as f64 / 2.0) / values.len() as f64 * 100.0)
+    }
+
+    pub fn summary(&self, stat: Stat) -> Option<StatSummary> {
+        let values = self.values(stat);
+        let (min, max) = (*values.first()?, *values.last()?);
+
+        Some(StatSummary {
+            count: values.len(),
+            mean: values.iter().map(|value| *value as f64).sum::<f64>() / values.len() as f64,
+            min,
+            max,
+            p10: self.percentile(stat, 10.0)?,
+            p25: self.percentile(stat, 25.0)?,
+            median: self.percentile(stat, 50.0)?,
+            p75: self.percentile(stat, 75.0)?,
+            p90: self.percentile(stat, 90.0)?,
+        })
+    }
+
+    /// Number of values in each bin of the given width, the first bin
+    /// starting at 0 and the last one holding the maximum.
+    pub fn histogram(&self, stat: Stat, bin_width: u8) -> Vec<usize> {
+        let values = self.values(stat);
+        let bin_width = bin_width.max(1) as usize;
+
+        let Some(max) = values.last() else {
+            return Vec::new();
+        };
+
+        let mut bins = vec![0; *max as usize / bin_width + 1];
+        for value in values {
+            bins[*value as usize / bin_width] += 1;
+        }
+        bins
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{Element, Position};
+
+    fn character(number: u16, values: [u8; 7]) -> Character {
+        let mut stats = Stats { kick: 0, control: 0, technique: 0, pressure: 0, physical: 0, agility: 0, intelligence: 0 };
+        for (stat, value) in STAT_LIST.into_iter().zip(values) {
+            stats.set(stat, value);
+        }
+
+        Character {
+            number,
+            name: format!("Player {number}"),
+            nickname: String::new(),
+            japanese_name: String::new(),
+            japanese_nickname: String::new(),
+            school: String::new(),
+            element: Element::FIRE,
+            position: Position::FW,
+            stats: Some(stats),
+            page_url: String::new(),
+            portrait_url: String::new(),
+            my_team_key: String::new(),
+        }
+    }
+
+    fn without_stats(number: u16) -> Character {
+        Character { stats: None, ..character(number, [0; 7]) }
+    }
+
+    fn stats(values: [u8; 7]) -> Stats {
+        character(0, values).stats.unwrap()
+    }
+
+    #[test]
+    fn domination_needs_one_strictly_better_stat() {
+        let kick_control = [Stat::Kick, Stat::Control];
+        let better = stats([80, 60, 0, 0, 0, 0, 0]);
+        let worse = stats([70, 60, 99, 0, 0, 0, 0]);
+
+        assert!(dominates(&better, &worse, &kick_control));
+        assert!(!dominates(&worse, &better, &kick_control));
+        // Equal stats don't dominate each other
+        assert!(!dominates(&better, &better, &kick_control));
+        // Only the given stats count
+        assert!(dominates(&worse, &better, &[Stat::Technique]));
+        assert!(!dominates(&better, &worse, &[]));
+    }
+
+    #[test]
+    fn frontier_keeps_ties_and_skips_missing_stats() {
+        let characters = [
+            character(1, [80, 50, 0, 0, 0, 0, 0]),
+            character(2, [50, 80, 0, 0, 0, 0, 0]),
+            character(3, [60, 60, 0, 0, 0, 0, 0]),
+            character(4, [50, 50, 0, 0, 0, 0, 0]),
+            // Same as 1, neither dominates the other
+            character(5, [80, 50, 0, 0, 0, 0, 0]),
+            without_stats(6),
+        ];
+
+        assert_eq!(pareto_frontier(&characters, &[Stat::Kick, Stat::Control]), vec![1, 2, 3, 5]);
+        assert_eq!(pareto_frontier(&characters, &[Stat::Kick]), vec![1, 5]);
+        assert!(pareto_frontier(&[without_stats(1)], &[Stat::Kick]).is_empty());
+    }
+
+    #[test]
+    fn similar_characters_by_profile_shape() {
+        let target = character(1, [80, 40, 20, 10, 10, 10, 10]);
+        let characters = [
+            target.clone(),
+            // Same shape, half the magnitude
+            character(2, [40, 20, 10, 5, 5, 5, 5]),
+            character(3, [10, 10, 10, 80, 80, 10, 10]),
+            character(4, [0; 7]),
+            without_stats(5),
+        ];
+
+        let cosine = similar_characters(&target, &characters, &SimilarOptions::default());
+        let numbers: Vec<u16> = cosine.iter().map(|(character, _)| character.number).collect();
+
+        assert_eq!(numbers, vec![2, 3, 4]);
+        assert!(cosine[0].1.abs() < 1e-9);
+        // A zero vector has no direction and is as far as can be
+        assert_eq!(cosine[2].1, 1.0);
+
+        let options = SimilarOptions { similarity: Similarity::Euclidean, count: 1, ..SimilarOptions::default() };
+        let euclidean = similar_characters(&target, &characters, &options);
+        assert_eq!(euclidean.len(), 1);
+        assert_eq!(euclidean[0].0.number, 2);
+
+        assert!(similar_characters(&without_stats(9), &characters, &SimilarOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn percentiles_interpolate_and_handle_single_characters() {
+        let characters: Vec<Character> = [10, 20, 30, 40, 50]
+            .into_iter()
+            .enumerate()
+            .map(|(index, kick)| character(index as u16, [kick, 0, 0, 0, 0, 0, 0]))
+            .chain([without_stats(9)])
+            .collect();
+        let distribution = StatDistribution::new(&characters);
+
+        assert_eq!(distribution.count(), 5);
+        assert_eq!(distribution.percentile(Stat::Kick, 0.0), Some(10.0));
+        assert_eq!(distribution.percentile(Stat::Kick, 50.0), Some(30.0));
+        assert_eq!(distribution.percentile(Stat::Kick, 10.0), Some(14.0));
+        assert_eq!(distribution.percentile(Stat::Kick, 150.0), Some(50.0));
+        assert_eq!(distribution.percentile_rank(Stat::Kick, 30), Some(50.0));
+        assert_eq!(distribution.percentile_rank(Stat::Kick, 5), Some(0.0));
+
+        let single = StatDistribution::new(&characters[..1]);
+        assert_eq!(single.percentile(Stat::Kick, 90.0), Some(10.0));
+        assert_eq!(single.percentile_rank(Stat::Kick, 10), Some(50.0));
+        let summary = single.summary(Stat::Kick).unwrap();
+        assert_eq!((summary.count, summary.min, summary.max, summary.median), (1, 10, 10, 10.0));
+
+        let empty = StatDistribution::new(&[without_stats(1)]);
+        assert_eq!(empty.percentile(Stat::Kick, 50.0), None);
+        assert_eq!(empty.summary(Stat::Kick), None);
+        assert!(empty.histogram(Stat::Kick, 10).is_empty());
+    }
+}
@@ -0,0 +1,54 @@
+use inazugle_scraper::{Database, DerivedColumn, Query, STAT_LIST};
+
+/// Runs a query against the local character cache and prints the matches.
+///
+/// Usage: `query [--db <path>] <query...>`
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1).peekable();
+    let mut db_path = "character_cache.sqlite".to_string();
+
+    if args.peek().map(String::as_str) == Some("--db") {
+        args.next();
+        db_path = args.next().ok_or("missing path after --db")?;
+    }
+
+    let text = args.collect::<Vec<_>>().join(" ");
+
+    let database = Database::connect(&db_path);
+    let derived = database.derived_columns().unwrap_or_else(DerivedColumn::defaults);
+
+    let query = match Query::parse_with(&text, &derived) {
+        Ok(query) => query,
+        Err(error) => {
+            eprintln!("{text}");
+            eprintln!("{}^", " ".repeat(error.position));
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+    };
+
+    let characters = database.query_characters(&query);
+
+    let stat_header: Vec<&str> = STAT_LIST.iter().map(|stat| &stat.to_str()[..3]).collect();
+    println!("{:>5}  {:<24} {:<8} {:<3} {}", "ID", "Name", "Element", "Pos", stat_header.join(" "));
+
+    for character in &characters {
+        let stats = match &character.stats {
+            Some(stats) => STAT_LIST.iter().map(|stat| format!("{:>3}", stats.get(*stat))).collect::<Vec<_>>(),
+            None => continue,
+        };
+
+        println!(
+            "{:>5}  {:<24} {:<8} {:<3} {}",
+            character.number,
+            character.name,
+            character.element.db_str(),
+            character.position.to_str(),
+            stats.join(" ")
+        );
+    }
+
+    println!("{} characters", characters.len());
+
+    Ok(())
+}
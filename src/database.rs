@@ -1,11 +1,33 @@
-use std::{fs::File, sync::{Arc, RwLock}};
+use std::{collections::HashSet, fs::File, sync::{Arc, RwLock, atomic::{AtomicI64, Ordering}}};
 
-use rusqlite::{params, Connection};
+use rusqlite::{functions::FunctionFlags, params, params_from_iter, Connection, Row};
 
-use crate::utils::{Character, Stats};
+use crate::{query::Query, request::Request, utils::{Character, Element, ELEMENT_LIST, Position, POSITION_LIST, STAT_LIST, Stats, now_timestamp}};
+
+mod annotations;
+mod character_profiles;
+mod details;
+mod identities;
+mod modifiers;
+mod my_team;
+mod portraits;
+mod profiles;
+mod roster;
+mod schools;
+mod searches;
+mod settings;
+mod teams;
+
+pub use annotations::Annotation;
+pub use details::CharacterDetails;
+pub use profiles::{DEFAULT_PROFILE, Profile};
+pub use schools::SCHOOL_NAME_SQL;
+pub use searches::{SavedSearch, SearchHistoryEntry, SearchSource};
 
 pub struct Database {
     conn: Arc<RwLock<Connection>>,
+    /// Profile owning the personal data, shared by every clone.
+    profile: Arc<AtomicI64>,
 }
 
 impl Database {
@@ -17,6 +39,16 @@ impl Database {
 
         let conn = Connection::open(path).expect("Unable to open database");
 
+        // LIKE and NOCASE only fold ASCII letters, queries lowercase both
+        // sides with this instead to match like they do in memory
+        conn.create_scalar_function(
+            "unicode_lower",
+            1,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| Ok(ctx.get::<Option<String>>(0)?.map(|text| text.to_lowercase())),
+        )
+        .expect("Failed to register unicode_lower");
+
         conn.execute(
             r#"
             CREATE TABLE IF NOT EXISTS characters (
@@ -31,16 +63,60 @@ impl Database {
                 pressure INTEGER,
                 physical INTEGER,
                 agility INTEGER,
-                intelligence INTEGER
+                intelligence INTEGER,
+                page_url TEXT,
+                fetched_at INTEGER,
+                portrait_url TEXT,
+                japanese_name TEXT,
+                japanese_nickname TEXT
             )
             "#,
             [],
         )
         .expect("Failed to create table");
 
-        Database {
+        // Caches created before the page URL, fetch time, portrait and
+        // Japanese names were stored lack the columns
+        let _ = conn.execute("ALTER TABLE characters ADD COLUMN page_url TEXT", []);
+        let _ = conn.execute("ALTER TABLE characters ADD COLUMN fetched_at INTEGER", []);
+        let _ = conn.execute("ALTER TABLE characters ADD COLUMN portrait_url TEXT", []);
+        let _ = conn.execute("ALTER TABLE characters ADD COLUMN japanese_name TEXT", []);
+        let _ = conn.execute("ALTER TABLE characters ADD COLUMN japanese_nickname TEXT", []);
+
+        // Characters the Japanese listing didn't name, not looked up again
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS japanese_name_misses (character_id INTEGER PRIMARY KEY)",
+            [],
+        )
+        .expect("Failed to create Japanese name misses table");
+
+        annotations::create_tables(&conn);
+        character_profiles::create_tables(&conn);
+        details::create_tables(&conn);
+        identities::create_tables(&conn);
+        modifiers::create_tables(&conn);
+        my_team::create_tables(&conn);
+        portraits::create_tables(&conn);
+        profiles::create_tables(&conn);
+        roster::create_tables(&conn);
+        schools::create_tables(&conn);
+        searches::create_tables(&conn);
+        settings::create_tables(&conn);
+        teams::create_tables(&conn);
+
+        let database = Database {
             conn: Arc::new(RwLock::new(conn)),
-        }
+            profile: Arc::new(AtomicI64::new(DEFAULT_PROFILE)),
+        };
+
+        database.restore_profile();
+        database.resolve_new_identities();
+        database
+    }
+
+    /// Profile the personal data is read from and written to.
+    pub fn profile_id(&self) -> i64 {
+        self.profile.load(Ordering::Relaxed)
     }
 
     pub fn populate_character_data(&self, character: &mut Character) -> bool {
@@ -54,7 +130,10 @@ impl Database {
                 pressure,
                 physical,
                 agility,
-                intelligence
+                intelligence,
+                japanese_name,
+                japanese_nickname,
+                (SELECT name FROM schools WHERE schools.id = school_id) AS school
             FROM characters
             WHERE id = ?
             "#,
@@ -66,7 +145,7 @@ impl Database {
         let result = stmt.query_row(
             params![character.number],
             |row| {
-                Ok(Stats {
+                let stats = Stats {
                     kick: row.get("kick")?,
                     control: row.get("control")?,
                     technique: row.get("technique")?,
@@ -74,22 +153,32 @@ impl Database {
                     physical: row.get("physical")?,
                     agility: row.get("agility")?,
                     intelligence: row.get("intelligence")?,
-                })
+                };
+                let japanese_name: Option<String> = row.get("japanese_name")?;
+                let japanese_nickname: Option<String> = row.get("japanese_nickname")?;
+                let school: Option<String> = row.get("school")?;
+
+                Ok((stats, japanese_name, japanese_nickname, school))
             },
         );
 
         match result {
-            Ok(stats) => {
+            Ok((stats, japanese_name, japanese_nickname, school)) => {
                 character.stats = Some(stats);
+                character.japanese_name = japanese_name.unwrap_or_default();
+                character.japanese_nickname = japanese_nickname.unwrap_or_default();
+                character.school = school.unwrap_or_default();
                 true
             }
             Err(_) => false,
         }
     }
 
+    /// Stores a character, updating it if it was already cached, and records
+    /// its stats in the stat history when they changed.
     pub fn store_character(&self, character: &Character) {
-        let write_lock = self.conn.write().unwrap();
         let stats = character.stats.as_ref().expect("Character has no stats");
+        let write_lock = self.conn.write().unwrap();
 
         write_lock
             .execute(
@@ -106,9 +195,31 @@ impl Database {
                     pressure,
                     physical,
                     agility,
-                    intelligence
+                    intelligence,
+                    page_url,
+                    fetched_at,
+                    portrait_url,
+                    japanese_name,
+                    japanese_nickname
                 )
-                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT(id) DO UPDATE SET
+                    name = excluded.name,
+                    nickname = excluded.nickname,
+                    element = excluded.element,
+                    position = excluded.position,
+                    kick = excluded.kick,
+                    control = excluded.control,
+                    technique = excluded.technique,
+                    pressure = excluded.pressure,
+                    physical = excluded.physical,
+                    agility = excluded.agility,
+                    intelligence = excluded.intelligence,
+                    page_url = excluded.page_url,
+                    fetched_at = excluded.fetched_at,
+                    portrait_url = COALESCE(NULLIF(excluded.portrait_url, ''), portrait_url),
+                    japanese_name = COALESCE(NULLIF(excluded.japanese_name, ''), japanese_name),
+                    japanese_nickname = COALESCE(NULLIF(excluded.japanese_nickname, ''), japanese_nickname)
                 "#,
                 params![
                     character.number,
@@ -122,17 +233,167 @@ impl Database {
                     stats.pressure,
                     stats.physical,
                     stats.agility,
-                    stats.intelligence
+                    stats.intelligence,
+                    character.page_url,
+                    now_timestamp(),
+                    character.portrait_url,
+                    character.japanese_name,
+                    character.japanese_nickname
                 ],
             )
             .expect("Failed to insert character");
+
+        details::record_stats(&write_lock, character.number, stats);
+    }
+
+    /// Stores the Japanese names of characters that are already cached.
+    pub fn store_japanese_names(&self, characters: &[Character]) {
+        let write_lock = self.conn.write().unwrap();
+
+        for character in characters.iter().filter(|character| !character.japanese_name.is_empty()) {
+            write_lock
+                .execute(
+                    "UPDATE characters SET japanese_name = ?, japanese_nickname = ? WHERE id = ?",
+                    params![character.japanese_name, character.japanese_nickname, character.number],
+                )
+                .expect("Failed to store Japanese names");
+        }
+    }
+
+    /// Characters whose Japanese name was looked up without success.
+    pub fn japanese_name_misses(&self) -> HashSet<u16> {
+        let read_lock = self.conn.read().unwrap();
+
+        read_lock
+            .prepare("SELECT character_id FROM japanese_name_misses")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get(0))
+                    .map(|rows| rows.filter_map(Result::ok).collect())
+            })
+            .unwrap_or_default()
     }
+
+    pub fn record_japanese_name_misses(&self, character_ids: &[u16]) {
+        let mut write_lock = self.conn.write().unwrap();
+        let transaction = write_lock.transaction().expect("Failed to start transaction");
+
+        for character_id in character_ids {
+            transaction
+                .execute(
+                    "INSERT OR IGNORE INTO japanese_name_misses (character_id) VALUES (?)",
+                    params![character_id],
+                )
+                .expect("Failed to record Japanese name miss");
+        }
+
+        transaction.commit().expect("Failed to commit Japanese name misses");
+    }
+
+    /// Runs a request, narrowed down by a query, against the cached characters
+    /// instead of the website. The cache doesn't know which games a character
+    /// appears in, so the game filter of the request is ignored.
+    pub fn search_characters(&self, request: &Request, query: &Query) -> Vec<Character> {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+
+        if !request.name.is_empty() {
+            conditions.push("(name LIKE ? OR nickname LIKE ? OR japanese_name LIKE ? OR japanese_nickname LIKE ?)".to_string());
+            let pattern = format!("%{}%", request.name);
+            values.extend(std::iter::repeat_n(pattern, 4));
+        }
+
+        let elements: Vec<&Element> = ELEMENT_LIST
+            .iter()
+            .filter(|element| request.has_element(element))
+            .collect();
+
+        if !elements.is_empty() {
+            conditions.push(format!("element IN ({})", vec!["?"; elements.len()].join(", ")));
+            values.extend(elements.iter().map(|element| element.db_str().to_string()));
+        }
+
+        let positions: Vec<&Position> = POSITION_LIST
+            .iter()
+            .filter(|position| request.has_position(position))
+            .collect();
+
+        if !positions.is_empty() {
+            conditions.push(format!("position IN ({})", vec!["?"; positions.len()].join(", ")));
+            values.extend(positions.iter().map(|position| position.to_str().to_string()));
+        }
+
+        for stat in STAT_LIST.iter().filter(|stat| request.stat_filters.is_active(**stat)) {
+            let (min, max) = request.stat_filters.range(*stat);
+            conditions.push(format!("{} BETWEEN {} AND {}", stat.db_column(), min, max));
+        }
+
+        let (condition, query_values) = query.to_sql();
+        conditions.push(condition);
+        values.extend(query_values);
+
+        self.select_characters(&conditions, values)
+    }
+
+    /// Returns the cached characters matching a query.
+    pub fn query_characters(&self, query: &Query) -> Vec<Character> {
+        let (condition, values) = query.to_sql();
+        self.select_characters(&[condition], values)
+    }
+
+    fn select_characters(&self, conditions: &[String], values: Vec<String>) -> Vec<Character> {
+        let mut query = format!("SELECT *, {SCHOOL_NAME_SQL} AS school FROM characters");
+        if !conditions.is_empty() {
+            query.push_str(" WHERE ");
+            query.push_str(&conditions.join(" AND "));
+        }
+
+        let read_lock = self.conn.read().unwrap();
+        let mut stmt = match read_lock.prepare(&query) {
+            Ok(stmt) => stmt,
+            Err(_) => return Vec::new(),
+        };
+
+        stmt.query_map(params_from_iter(values), character_from_row)
+            .map(|rows| rows.filter_map(Result::ok).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Builds a character from a full row of the `characters` table along with
+/// its school name.
+fn character_from_row(row: &Row) -> rusqlite::Result<Character> {
+    let element: String = row.get("element")?;
+    let position: String = row.get("position")?;
+
+    Ok(Character {
+        number: row.get("id")?,
+        name: row.get("name")?,
+        nickname: row.get("nickname")?,
+        japanese_name: row.get::<_, Option<String>>("japanese_name")?.unwrap_or_default(),
+        japanese_nickname: row.get::<_, Option<String>>("japanese_nickname")?.unwrap_or_default(),
+        school: row.get("school")?,
+        element: Element::from_db_str(&element),
+        position: Position::from_db_str(&position),
+        stats: Some(Stats {
+            kick: row.get("kick")?,
+            control: row.get("control")?,
+            technique: row.get("technique")?,
+            pressure: row.get("pressure")?,
+            physical: row.get("physical")?,
+            agility: row.get("agility")?,
+            intelligence: row.get("intelligence")?,
+        }),
+        page_url: row.get::<_, Option<String>>("page_url")?.unwrap_or_default(),
+        portrait_url: row.get::<_, Option<String>>("portrait_url")?.unwrap_or_default(),
+        my_team_key: String::new(),
+    })
 }
 
 impl Clone for Database {
     fn clone(&self) -> Self {
         Database {
             conn: self.conn.clone(),
+            profile: self.profile.clone(),
         }
     }
 }
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection};
+
+use crate::database::{Database, profiles::create_scoped_table};
+
+/// What the user noted about a character, kept apart from the scraped data
+/// so that fetching the character again never overwrites it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Annotation {
+    pub favorite: bool,
+    /// Free-form tags, sorted.
+    pub tags: Vec<String>,
+    /// Markdown text.
+    pub notes: String,
+}
+
+impl Annotation {
+    pub fn is_empty(&self) -> bool {
+        !self.favorite && self.tags.is_empty() && self.notes.trim().is_empty()
+    }
+}
+
+pub(super) fn create_tables(conn: &Connection) {
+    create_scoped_table(
+        conn,
+        "character_annotations",
+        r#"
+            profile_id INTEGER NOT NULL REFERENCES profiles(id) ON DELETE CASCADE,
+            character_id INTEGER NOT NULL,
+            favorite INTEGER NOT NULL,
+            notes TEXT NOT NULL,
+            PRIMARY KEY (profile_id, character_id)
+        "#,
+    );
+
+    create_scoped_table(
+        conn,
+        "character_tags",
+        r#"
+            profile_id INTEGER NOT NULL REFERENCES profiles(id) ON DELETE CASCADE,
+            character_id INTEGER NOT NULL,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (profile_id, character_id, tag)
+        "#,
+    );
+}
+
+impl Database {
+    /// Annotations of the current profile, by character number.
+    pub fn annotations(&self) -> HashMap<u16, Annotation> {
+        let profile_id = self.profile_id();
+        let read_lock = self.conn.read().unwrap();
+
+        let mut annotations: HashMap<u16, Annotation> = read_lock
+            .prepare("SELECT character_id, favorite, notes FROM character_annotations WHERE profile_id = ?")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![profile_id], |row| {
+                    Ok((row.get(0)?, Annotation { favorite: row.get(1)?, tags: Vec::new(), notes: row.get(2)? }))
+                })
+                .map(|rows| rows.filter_map(Result::ok).collect())
+            })
+            .unwrap_or_default();
+
+        let tags: Vec<(u16, String)> = read_lock
+            .prepare("SELECT character_id, tag FROM character_tags WHERE profile_id = ? ORDER BY tag")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![profile_id], |row| Ok((row.get(0)?, row.get(1)?)))
+                    .map(|rows| rows.filter_map(Result::ok).collect())
+            })
+            .unwrap_or_default();
+
+        for (character_id, tag) in tags {
+            annotations.entry(character_id).or_default().tags.push(tag);
+        }
+
+        annotations
+    }
+
+    /// Replaces the annotation of a character in the current profile,
+    /// removing it when empty.
+    pub fn save_annotation(&self, character_id: u16, annotation: &Annotation) {
+        let profile_id = self.profile_id();
+        let mut write_lock = self.conn.write().unwrap();
+        let transaction = write_lock.transaction().expect("Failed to start transaction");
+
+        transaction
+            .execute(
+                "DELETE FROM character_annotations WHERE profile_id = ? AND character_id = ?",
+                params![profile_id, character_id],
+            )
+            .expect("Failed to clear character annotation");
+
+        transaction
+            .execute(
+                "DELETE FROM character_tags WHERE profile_id = ? AND character_id = ?",
+                params![profile_id, character_id],
+            )
+            .expect("Failed to clear character tags");
+
+        if annotation.favorite || !annotation.notes.trim().is_empty() {
+            transaction
+                .execute(
+                    "INSERT INTO character_annotations (profile_id, character_id, favorite, notes) VALUES (?, ?, ?, ?)",
+                    params![profile_id, character_id, annotation.favorite, annotation.notes],
+                )
+                .expect("Failed to save character annotation");
+        }
+
+        for tag in &annotation.tags {
+            transaction
+                .execute(
+                    "INSERT OR IGNORE INTO character_tags (profile_id, character_id, tag) VALUES (?, ?, ?)",
+                    params![profile_id, character_id, tag],
+                )
+                .expect("Failed to save character tag");
+        }
+
+        transaction.commit().expect("Failed to commit character annotation");
+    }
+}
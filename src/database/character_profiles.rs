@@ -0,0 +1,168 @@
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{database::{Database, schools::assign_school}, utils::CharacterProfile};
+
+pub(super) fn create_tables(conn: &Connection) {
+    conn.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS character_profiles (
+            character_id INTEGER PRIMARY KEY,
+            affiliation TEXT NOT NULL,
+            gender TEXT NOT NULL,
+            grade TEXT NOT NULL,
+            height TEXT NOT NULL,
+            recruitment TEXT NOT NULL,
+            rarity TEXT NOT NULL,
+            description TEXT NOT NULL
+        )
+        "#,
+        [],
+    )
+    .expect("Failed to create character profiles table");
+
+    // Entries of the page that match none of the profile columns
+    conn.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS character_profile_fields (
+            character_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            label TEXT NOT NULL,
+            value TEXT NOT NULL,
+            PRIMARY KEY (character_id, position)
+        )
+        "#,
+        [],
+    )
+    .expect("Failed to create character profile fields table");
+
+    conn.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS character_techniques (
+            character_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            PRIMARY KEY (character_id, position)
+        )
+        "#,
+        [],
+    )
+    .expect("Failed to create character techniques table");
+}
+
+/// Profile of a character, if its page was fetched since profiles are
+/// stored.
+pub(super) fn load_profile(conn: &Connection, character_id: u16) -> Option<CharacterProfile> {
+    let mut profile = conn
+        .query_row(
+            "SELECT * FROM character_profiles WHERE character_id = ?",
+            params![character_id],
+            |row| {
+                Ok(CharacterProfile {
+                    affiliation: row.get("affiliation")?,
+                    gender: row.get("gender")?,
+                    grade: row.get("grade")?,
+                    height: row.get("height")?,
+                    recruitment: row.get("recruitment")?,
+                    rarity: row.get("rarity")?,
+                    description: row.get("description")?,
+                    techniques: Vec::new(),
+                    other: Vec::new(),
+                })
+            },
+        )
+        .optional()
+        .ok()
+        .flatten()?;
+
+    profile.other = conn
+        .prepare("SELECT label, value FROM character_profile_fields WHERE character_id = ? ORDER BY position")
+        .and_then(|mut stmt| {
+            stmt.query_map(params![character_id], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map(|rows| rows.filter_map(Result::ok).collect())
+        })
+        .unwrap_or_default();
+
+    profile.techniques = conn
+        .prepare("SELECT name FROM character_techniques WHERE character_id = ? ORDER BY position")
+        .and_then(|mut stmt| {
+            stmt.query_map(params![character_id], |row| row.get(0))
+                .map(|rows| rows.filter_map(Result::ok).collect())
+        })
+        .unwrap_or_default();
+
+    Some(profile)
+}
+
+impl Database {
+    pub fn character_profile(&self, character_id: u16) -> Option<CharacterProfile> {
+        let read_lock = self.conn.read().unwrap();
+        load_profile(&read_lock, character_id)
+    }
+
+    /// Replaces the stored profile of a character, linking it to the school
+    /// of its affiliation.
+    pub fn store_character_profile(&self, character_id: u16, profile: &CharacterProfile) {
+        let mut write_lock = self.conn.write().unwrap();
+        let transaction = write_lock.transaction().expect("Failed to start transaction");
+
+        transaction
+            .execute(
+                r#"
+                INSERT OR REPLACE INTO character_profiles (
+                    character_id,
+                    affiliation,
+                    gender,
+                    grade,
+                    height,
+                    recruitment,
+                    rarity,
+                    description
+                )
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+                params![
+                    character_id,
+                    profile.affiliation,
+                    profile.gender,
+                    profile.grade,
+                    profile.height,
+                    profile.recruitment,
+                    profile.rarity,
+                    profile.description
+                ],
+            )
+            .expect("Failed to store character profile");
+
+        transaction
+            .execute("DELETE FROM character_profile_fields WHERE character_id = ?", params![character_id])
+            .expect("Failed to clear character profile fields");
+
+        for (position, (label, value)) in profile.other.iter().enumerate() {
+            transaction
+                .execute(
+                    "INSERT INTO character_profile_fields (character_id, position, label, value) VALUES (?, ?, ?, ?)",
+                    params![character_id, position as i64, label, value],
+                )
+                .expect("Failed to store character profile field");
+        }
+
+        transaction
+            .execute("DELETE FROM character_techniques WHERE character_id = ?", params![character_id])
+            .expect("Failed to clear character techniques");
+
+        for (position, name) in profile.techniques.iter().enumerate() {
+            transaction
+                .execute(
+                    "INSERT INTO character_techniques (character_id, position, name) VALUES (?, ?, ?)",
+                    params![character_id, position as i64, name],
+                )
+                .expect("Failed to store character technique");
+        }
+
+        if !profile.affiliation.is_empty() {
+            assign_school(&transaction, character_id, &profile.affiliation);
+        }
+
+        transaction.commit().expect("Failed to commit character profile");
+    }
+}
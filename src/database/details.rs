@@ -0,0 +1,196 @@
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+
+use crate::{
+    database::{Database, character_profiles::load_profile},
+    utils::{CharacterProfile, GAME_LIST, Game, GameFlags, STAT_LIST, Stats, now_timestamp},
+};
+
+/// Information about a cached character beyond its current stats.
+#[derive(Debug, Clone, Default)]
+pub struct CharacterDetails {
+    /// When the stats were last fetched from the website, if known.
+    pub fetched_at: Option<i64>,
+    /// Every distinct set of stats fetched for the character, oldest first.
+    pub stat_history: Vec<(i64, Stats)>,
+    /// Games the character was found in through game-filtered searches.
+    pub games: Vec<Game>,
+    /// `None` when the page of the character wasn't fetched since profiles
+    /// are stored.
+    pub profile: Option<CharacterProfile>,
+}
+
+pub(super) fn create_tables(conn: &Connection) {
+    conn.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS stat_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            character_id INTEGER NOT NULL,
+            fetched_at INTEGER,
+            kick INTEGER,
+            control INTEGER,
+            technique INTEGER,
+            pressure INTEGER,
+            physical INTEGER,
+            agility INTEGER,
+            intelligence INTEGER
+        )
+        "#,
+        [],
+    )
+    .expect("Failed to create stat history table");
+
+    conn.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS character_games (
+            character_id INTEGER NOT NULL,
+            game TEXT NOT NULL,
+            PRIMARY KEY (character_id, game)
+        )
+        "#,
+        [],
+    )
+    .expect("Failed to create character games table");
+}
+
+/// Games the character was found in, in `GAME_LIST` order.
+pub(super) fn load_games(conn: &Connection, character_id: u16) -> Vec<Game> {
+    let games: Vec<String> = conn
+        .prepare("SELECT game FROM character_games WHERE character_id = ?")
+        .and_then(|mut stmt| {
+            stmt.query_map(params![character_id], |row| row.get(0))
+                .map(|rows| rows.filter_map(Result::ok).collect())
+        })
+        .unwrap_or_default();
+
+    GAME_LIST
+        .into_iter()
+        .filter(|game| games.iter().any(|name| name == game.req_str()))
+        .collect()
+}
+
+fn stats_from_row(row: &rusqlite::Row) -> rusqlite::Result<Stats> {
+    Ok(Stats {
+        kick: row.get("kick")?,
+        control: row.get("control")?,
+        technique: row.get("technique")?,
+        pressure: row.get("pressure")?,
+        physical: row.get("physical")?,
+        agility: row.get("agility")?,
+        intelligence: row.get("intelligence")?,
+    })
+}
+
+/// Adds the stats to the history of the character unless they are the same
+/// as the last recorded ones.
+pub(super) fn record_stats(conn: &Connection, character_id: u16, stats: &Stats) {
+    let last = conn
+        .query_row(
+            "SELECT * FROM stat_history WHERE character_id = ? ORDER BY id DESC LIMIT 1",
+            params![character_id],
+            stats_from_row,
+        )
+        .optional()
+        .unwrap_or_default();
+
+    let unchanged = last.is_some_and(|last| {
+        STAT_LIST.iter().all(|stat| last.get(*stat) == stats.get(*stat))
+    });
+
+    if unchanged {
+        return;
+    }
+
+    conn.execute(
+        r#"
+        INSERT INTO stat_history (
+            character_id, fetched_at, kick, control, technique, pressure, physical, agility, intelligence
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        params![
+            character_id,
+            now_timestamp(),
+            stats.kick,
+            stats.control,
+            stats.technique,
+            stats.pressure,
+            stats.physical,
+            stats.agility,
+            stats.intelligence
+        ],
+    )
+    .expect("Failed to record stat history");
+}
+
+impl Database {
+    pub fn character_details(&self, character_id: u16) -> CharacterDetails {
+        let read_lock = self.conn.read().unwrap();
+
+        let fetched_at = read_lock
+            .query_row(
+                "SELECT fetched_at FROM characters WHERE id = ?",
+                params![character_id],
+                |row| row.get::<_, Option<i64>>(0),
+            )
+            .ok()
+            .flatten();
+
+        let stat_history = read_lock
+            .prepare("SELECT * FROM stat_history WHERE character_id = ? ORDER BY id")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![character_id], |row| Ok((row.get("fetched_at")?, stats_from_row(row)?)))
+                    .map(|rows| rows.filter_map(Result::ok).collect())
+            })
+            .unwrap_or_default();
+
+        CharacterDetails {
+            fetched_at,
+            stat_history,
+            games: load_games(&read_lock, character_id),
+            profile: load_profile(&read_lock, character_id),
+        }
+    }
+
+    /// Remembers that the characters appear in the given game.
+    pub fn record_character_games(&self, character_ids: &[u16], game: Game) {
+        let mut write_lock = self.conn.write().unwrap();
+        let transaction = write_lock.transaction().expect("Failed to start transaction");
+
+        for id in character_ids {
+            transaction
+                .execute(
+                    "INSERT OR IGNORE INTO character_games (character_id, game) VALUES (?, ?)",
+                    params![id, game.req_str()],
+                )
+                .expect("Failed to record character game");
+        }
+
+        transaction.commit().expect("Failed to record character games");
+    }
+
+    /// Characters found in at least one of the games through game-filtered
+    /// searches.
+    pub fn characters_in_games(&self, games: &GameFlags) -> Vec<u16> {
+        let games: Vec<&str> = GAME_LIST
+            .iter()
+            .filter(|game| games.contains(game.flag()))
+            .map(|game| game.req_str())
+            .collect();
+
+        if games.is_empty() {
+            return Vec::new();
+        }
+
+        let placeholders = vec!["?"; games.len()].join(", ");
+        let query = format!("SELECT DISTINCT character_id FROM character_games WHERE game IN ({placeholders})");
+
+        let read_lock = self.conn.read().unwrap();
+        read_lock
+            .prepare(&query)
+            .and_then(|mut stmt| {
+                stmt.query_map(params_from_iter(games), |row| row.get(0))
+                    .map(|rows| rows.filter_map(Result::ok).collect())
+            })
+            .unwrap_or_default()
+    }
+}
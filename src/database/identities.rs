@@ -0,0 +1,307 @@
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, ToSql};
+
+use crate::{
+    database::{Database, details::load_games},
+    utils::{BASE_URL, Character, Game},
+};
+
+pub(super) fn create_tables(conn: &Connection) {
+    // Characters with the same identity are versions of one person from
+    // different games. The identity is the smallest number among them.
+    conn.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS character_identity (
+            character_id INTEGER PRIMARY KEY,
+            identity_id INTEGER NOT NULL
+        )
+        "#,
+        [],
+    )
+    .expect("Failed to create character identity table");
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS character_identity_by_identity ON character_identity (identity_id)",
+        [],
+    )
+    .expect("Failed to create character identity index");
+
+    // Keys each resolved character was linked by, so that a new character
+    // is matched against the cache without resolving it again
+    conn.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS identity_keys (
+            key TEXT NOT NULL,
+            character_id INTEGER NOT NULL,
+            PRIMARY KEY (key, character_id)
+        )
+        "#,
+        [],
+    )
+    .expect("Failed to create identity keys table");
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS identity_keys_by_character ON identity_keys (character_id)",
+        [],
+    )
+    .expect("Failed to create identity keys index");
+}
+
+/// Lowercase letters and digits of a name, so that spacing, punctuation and
+/// case differences between games don't matter.
+fn normalize(name: &str) -> String {
+    name.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// Path of a character page without the host and the language prefix, so
+/// that the English and Japanese links of a page are the same key.
+fn normalize_link(page_url: &str) -> String {
+    let path = page_url.strip_prefix(BASE_URL).unwrap_or(page_url);
+    let path = path.strip_prefix("/en/").map_or(path.to_string(), |rest| format!("/{rest}"));
+    path.trim_end_matches('/').to_lowercase()
+}
+
+/// Keys linking a character to its other versions: its page link first,
+/// then its normalized English and Japanese names with their nicknames.
+/// Secret characters, which have no name, get none.
+fn identity_keys(page_url: &str, name: &str, nickname: &str, japanese_name: &str, japanese_nickname: &str) -> Vec<String> {
+    if name.is_empty() {
+        return Vec::new();
+    }
+
+    let mut keys = Vec::new();
+
+    if !page_url.is_empty() {
+        keys.push(format!("page:{}", normalize_link(page_url)));
+    }
+
+    for (language, name, nickname) in [("en", name, nickname), ("ja", japanese_name, japanese_nickname)] {
+        let name = normalize(name);
+        if !name.is_empty() {
+            keys.push(format!("{language}:{name}|{}", normalize(nickname)));
+        }
+    }
+
+    keys
+}
+
+impl Database {
+    /// Links the given characters to the cached versions sharing one of
+    /// their keys, merging the groups they bridge. Characters whose keys
+    /// didn't change since they were resolved are skipped. Groups are never
+    /// split, so a character keeps its links when its names change.
+    pub fn resolve_identities(&self, numbers: &[u16]) {
+        let mut write_lock = self.conn.write().unwrap();
+        let transaction = write_lock.transaction().expect("Failed to start transaction");
+
+        for number in numbers {
+            let keys = transaction
+                .query_row(
+                    r#"
+                    SELECT IFNULL(page_url, ''), IFNULL(name, ''), IFNULL(nickname, ''),
+                        IFNULL(japanese_name, ''), IFNULL(japanese_nickname, '')
+                    FROM characters WHERE id = ?
+                    "#,
+                    params![number],
+                    |row| {
+                        let text = |index| row.get::<_, String>(index);
+                        Ok(identity_keys(&text(0)?, &text(1)?, &text(2)?, &text(3)?, &text(4)?))
+                    },
+                )
+                .optional()
+                .ok()
+                .flatten();
+
+            let Some(mut keys) = keys else {
+                continue;
+            };
+
+            // Characters already resolved with the same keys are left alone
+            let mut stored: Vec<String> = transaction
+                .prepare("SELECT key FROM identity_keys WHERE character_id = ?")
+                .and_then(|mut stmt| {
+                    stmt.query_map(params![number], |row| row.get(0))
+                        .map(|rows| rows.filter_map(Result::ok).collect())
+                })
+                .unwrap_or_default();
+
+            keys.sort();
+            stored.sort();
+            if !keys.is_empty() && keys == stored {
+                continue;
+            }
+
+            transaction
+                .execute("DELETE FROM identity_keys WHERE character_id = ?", params![number])
+                .expect("Failed to clear identity keys");
+
+            if keys.is_empty() {
+                continue;
+            }
+
+            let placeholders = vec!["?"; keys.len()].join(", ");
+            let linked: Vec<u16> = transaction
+                .prepare(&format!(
+                    r#"
+                    SELECT DISTINCT character_identity.identity_id
+                    FROM identity_keys
+                    JOIN character_identity ON character_identity.character_id = identity_keys.character_id
+                    WHERE identity_keys.key IN ({placeholders}) AND identity_keys.character_id != ?
+                    "#
+                ))
+                .and_then(|mut stmt| {
+                    stmt.query_map(params_from_iter(keys.iter().map(|key| key as &dyn ToSql).chain([number as &dyn ToSql])), |row| row.get(0))
+                        .map(|rows| rows.filter_map(Result::ok).collect())
+                })
+                .unwrap_or_default();
+
+            let current: Option<u16> = transaction
+                .query_row("SELECT identity_id FROM character_identity WHERE character_id = ?", params![number], |row| row.get(0))
+                .optional()
+                .ok()
+                .flatten();
+
+            let merged: Vec<u16> = linked.into_iter().chain(current).collect();
+            let identity = merged.iter().copied().chain([*number]).min().unwrap_or(*number);
+
+            for other in merged {
+                transaction
+                    .execute(
+                        "UPDATE character_identity SET identity_id = ? WHERE identity_id = ?",
+                        params![identity, other],
+                    )
+                    .expect("Failed to merge character identities");
+            }
+
+            transaction
+                .execute(
+                    "INSERT OR REPLACE INTO character_identity (character_id, identity_id) VALUES (?, ?)",
+                    params![number, identity],
+                )
+                .expect("Failed to store character identity");
+
+            for key in &keys {
+                transaction
+                    .execute(
+                        "INSERT OR IGNORE INTO identity_keys (key, character_id) VALUES (?, ?)",
+                        params![key, number],
+                    )
+                    .expect("Failed to store identity key");
+            }
+        }
+
+        transaction.commit().expect("Failed to commit character identities");
+    }
+
+    /// Resolves the cached characters that never were, e.g. in a cache made
+    /// before identities were keyed.
+    pub(super) fn resolve_new_identities(&self) {
+        let numbers: Vec<u16> = {
+            let read_lock = self.conn.read().unwrap();
+
+            read_lock
+                .prepare(
+                    r#"
+                    SELECT id FROM characters
+                    WHERE IFNULL(name, '') != ''
+                        AND id NOT IN (SELECT character_id FROM identity_keys)
+                    ORDER BY id
+                    "#,
+                )
+                .and_then(|mut stmt| {
+                    stmt.query_map([], |row| row.get(0))
+                        .map(|rows| rows.filter_map(Result::ok).collect())
+                })
+                .unwrap_or_default()
+        };
+
+        if !numbers.is_empty() {
+            self.resolve_identities(&numbers);
+        }
+    }
+
+    /// Every cached version of a character, itself included, with the games
+    /// each was found in, ordered by number.
+    pub fn character_versions(&self, character_id: u16) -> Vec<(Character, Vec<Game>)> {
+        let condition = format!(
+            r#"
+            id IN (
+                SELECT character_id FROM character_identity
+                WHERE identity_id = (SELECT identity_id FROM character_identity WHERE character_id = {character_id})
+            )
+            "#
+        );
+
+        let mut versions = self.select_characters(&[condition], Vec::new());
+        versions.sort_by_key(|character| character.number);
+
+        let read_lock = self.conn.read().unwrap();
+        versions
+            .into_iter()
+            .map(|character| {
+                let games = load_games(&read_lock, character.number);
+                (character, games)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{Element, Position, Stats};
+
+    fn character(number: u16, name: &str, page: &str) -> Character {
+        Character {
+            number,
+            name: name.to_string(),
+            nickname: String::new(),
+            japanese_name: String::new(),
+            japanese_nickname: String::new(),
+            school: String::new(),
+            element: Element::FIRE,
+            position: Position::FW,
+            stats: Some(Stats { kick: 50, control: 50, technique: 50, pressure: 50, physical: 50, agility: 50, intelligence: 50 }),
+            page_url: format!("{BASE_URL}{page}"),
+            portrait_url: String::new(),
+            my_team_key: String::new(),
+        }
+    }
+
+    fn versions(database: &Database, number: u16) -> Vec<u16> {
+        database.character_versions(number).into_iter().map(|(character, _)| character.number).collect()
+    }
+
+    #[test]
+    fn versions_are_linked_by_page_and_name() {
+        let path = std::env::temp_dir().join(format!("inazugle_identities_{}.sqlite", std::process::id()));
+        let database = Database::connect(path.to_str().unwrap());
+
+        for character in [
+            character(1, "Mark Evans", "/en/chara/1"),
+            character(2, "Mark  evans", "/en/chara/2"),
+            character(3, "Jude Sharp", "/en/chara/3"),
+            character(5, "Shawn Froste", "/en/chara/shawn"),
+            character(6, "Aiden Froste", "/chara/shawn/"),
+        ] {
+            database.store_character(&character);
+        }
+        database.resolve_identities(&[1, 2, 3, 5, 6]);
+
+        assert_eq!(versions(&database, 2), vec![1, 2]);
+        assert_eq!(versions(&database, 3), vec![3]);
+        assert_eq!(versions(&database, 6), vec![5, 6]);
+
+        // A new character is matched against the resolved ones
+        database.store_character(&character(7, "Jude Sharp", "/en/chara/7"));
+        database.resolve_new_identities();
+        assert_eq!(versions(&database, 3), vec![3, 7]);
+
+        // One sharing a name with a group and a page with another merges them
+        database.store_character(&character(8, "Mark Evans", "/en/chara/shawn"));
+        database.resolve_identities(&[8]);
+        assert_eq!(versions(&database, 6), vec![1, 2, 5, 6, 8]);
+
+        drop(database);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection};
+
+use crate::{
+    database::Database,
+    modifiers::{Modifier, ModifierScope, ModifierSet},
+};
+
+pub(super) fn create_tables(conn: &Connection) {
+    conn.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS modifiers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            profile_id INTEGER NOT NULL REFERENCES profiles(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            boosts TEXT NOT NULL,
+            UNIQUE (profile_id, name)
+        )
+        "#,
+        [],
+    )
+    .expect("Failed to create modifiers table");
+
+    // The scope is a `ModifierScope` key
+    let definition = r#"
+        profile_id INTEGER NOT NULL REFERENCES profiles(id) ON DELETE CASCADE,
+        character_id INTEGER NOT NULL,
+        modifier_id INTEGER NOT NULL REFERENCES modifiers(id) ON DELETE CASCADE,
+        scope TEXT NOT NULL,
+        PRIMARY KEY (profile_id, character_id, modifier_id, scope)
+    "#;
+
+    conn.execute(&format!("CREATE TABLE IF NOT EXISTS character_modifiers ({definition})"), [])
+        .expect("Failed to create character modifiers table");
+
+    // Modifiers attached before scopes existed apply in the roster
+    if conn.prepare("SELECT scope FROM character_modifiers LIMIT 0").is_err() {
+        conn.execute_batch(&format!(
+            r#"
+            BEGIN;
+            CREATE TABLE character_modifiers_scoped ({definition});
+            INSERT INTO character_modifiers_scoped (profile_id, character_id, modifier_id, scope)
+                SELECT profile_id, character_id, modifier_id, 'roster' FROM character_modifiers;
+            DROP TABLE character_modifiers;
+            ALTER TABLE character_modifiers_scoped RENAME TO character_modifiers;
+            COMMIT;
+            "#
+        ))
+        .expect("Failed to add scopes to character modifiers");
+    }
+}
+
+impl Database {
+    /// Modifiers of the current profile and the characters they are attached
+    /// to.
+    pub fn modifier_set(&self) -> ModifierSet {
+        let profile_id = self.profile_id();
+        let read_lock = self.conn.read().unwrap();
+
+        let modifiers = read_lock
+            .prepare("SELECT id, name, boosts FROM modifiers WHERE profile_id = ? ORDER BY name")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![profile_id], |row| {
+                    let boosts: String = row.get("boosts")?;
+
+                    Ok(Modifier {
+                        id: Some(row.get("id")?),
+                        name: row.get("name")?,
+                        boosts: Modifier::boosts_from_spec(&boosts),
+                    })
+                })
+                .map(|rows| rows.filter_map(Result::ok).collect())
+            })
+            .unwrap_or_default();
+
+        let mut attached: HashMap<u16, Vec<(ModifierScope, i64)>> = HashMap::new();
+
+        let links: Vec<(u16, i64, String)> = read_lock
+            .prepare("SELECT character_id, modifier_id, scope FROM character_modifiers WHERE profile_id = ?")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![profile_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+                    .map(|rows| rows.filter_map(Result::ok).collect())
+            })
+            .unwrap_or_default();
+
+        for (character_id, modifier_id, scope) in links {
+            if let Some(scope) = ModifierScope::from_key(&scope) {
+                attached.entry(character_id).or_default().push((scope, modifier_id));
+            }
+        }
+
+        ModifierSet { modifiers, attached }
+    }
+
+    /// Saves a modifier, replacing the one with the same id. Returns the id
+    /// of the modifier, or `None` if another modifier of the profile already
+    /// has its name or the modifier is no longer in the profile.
+    pub fn save_modifier(&self, modifier: &Modifier) -> Option<i64> {
+        let profile_id = self.profile_id();
+        let write_lock = self.conn.write().unwrap();
+
+        // The name is unique within the profile, so both statements fail on a
+        // name that is already used
+        match modifier.id {
+            Some(id) => {
+                let updated = write_lock.execute(
+                    "UPDATE modifiers SET name = ?, boosts = ? WHERE id = ? AND profile_id = ?",
+                    params![modifier.name, modifier.to_spec(), id, profile_id],
+                );
+
+                // Nothing is updated for a modifier that was deleted
+                matches!(updated, Ok(1)).then_some(id)
+            }
+            None => write_lock
+                .query_row(
+                    "INSERT INTO modifiers (profile_id, name, boosts) VALUES (?, ?, ?) RETURNING id",
+                    params![profile_id, modifier.name, modifier.to_spec()],
+                    |row| row.get(0),
+                )
+                .ok(),
+        }
+    }
+
+    pub fn delete_modifier(&self, id: i64) {
+        let write_lock = self.conn.write().unwrap();
+
+        write_lock
+            .execute("DELETE FROM character_modifiers WHERE modifier_id = ?", params![id])
+            .expect("Failed to detach modifier");
+        write_lock
+            .execute("DELETE FROM modifiers WHERE id = ?", params![id])
+            .expect("Failed to delete modifier");
+    }
+
+    /// Attaches a modifier to characters in a scope or detaches it from
+    /// them.
+    pub fn set_modifier_attached(&self, modifier_id: i64, scope: ModifierScope, character_ids: &[u16], attached: bool) {
+        let profile_id = self.profile_id();
+        let mut write_lock = self.conn.write().unwrap();
+        let transaction = write_lock.transaction().expect("Failed to start transaction");
+
+        for character_id in character_ids {
+            let statement = if attached {
+                "INSERT OR IGNORE INTO character_modifiers (profile_id, character_id, modifier_id, scope) VALUES (?, ?, ?, ?)"
+            } else {
+                "DELETE FROM character_modifiers WHERE profile_id = ? AND character_id = ? AND modifier_id = ? AND scope = ?"
+            };
+
+            transaction
+                .execute(statement, params![profile_id, character_id, modifier_id, scope.to_key()])
+                .expect("Failed to attach modifier");
+        }
+
+        transaction.commit().expect("Failed to commit modifier attachments");
+    }
+
+    /// Detaches a modifier from every character, in every scope.
+    pub fn detach_modifier(&self, modifier_id: i64) {
+        let write_lock = self.conn.write().unwrap();
+
+        write_lock
+            .execute(
+                "DELETE FROM character_modifiers WHERE profile_id = ? AND modifier_id = ?",
+                params![self.profile_id(), modifier_id],
+            )
+            .expect("Failed to detach modifier");
+    }
+}
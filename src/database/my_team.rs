@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection};
+
+use crate::{
+    database::{Database, profiles::create_scoped_table},
+    utils::{Character, now_timestamp},
+};
+
+pub(super) fn create_tables(conn: &Connection) {
+    create_scoped_table(
+        conn,
+        "my_team",
+        r#"
+            profile_id INTEGER NOT NULL,
+            character_id INTEGER NOT NULL,
+            added_at INTEGER,
+            PRIMARY KEY (profile_id, character_id)
+        "#,
+    );
+
+    // Read from the website, shared by every profile
+    conn.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS my_team_keys (
+            character_id INTEGER PRIMARY KEY,
+            key TEXT NOT NULL
+        )
+        "#,
+        [],
+    )
+    .expect("Failed to create my team keys table");
+}
+
+impl Database {
+    /// Numbers of the characters in My Team, in the order they were added.
+    pub fn my_team(&self) -> Vec<u16> {
+        let read_lock = self.conn.read().unwrap();
+
+        read_lock
+            .prepare("SELECT character_id FROM my_team WHERE profile_id = ? ORDER BY added_at, character_id")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![self.profile_id()], |row| row.get(0))
+                    .map(|rows| rows.filter_map(Result::ok).collect())
+            })
+            .unwrap_or_default()
+    }
+
+    /// The cached characters of My Team.
+    pub fn my_team_characters(&self) -> Vec<Character> {
+        let condition = format!("id IN (SELECT character_id FROM my_team WHERE profile_id = {})", self.profile_id());
+        self.select_characters(&[condition], Vec::new())
+    }
+
+    pub fn set_in_my_team(&self, character_id: u16, in_team: bool) {
+        let profile_id = self.profile_id();
+        let write_lock = self.conn.write().unwrap();
+
+        if in_team {
+            write_lock
+                .execute(
+                    "INSERT OR IGNORE INTO my_team (profile_id, character_id, added_at) VALUES (?, ?, ?)",
+                    params![profile_id, character_id, now_timestamp()],
+                )
+                .expect("Failed to add character to my team");
+        } else {
+            write_lock
+                .execute(
+                    "DELETE FROM my_team WHERE profile_id = ? AND character_id = ?",
+                    params![profile_id, character_id],
+                )
+                .expect("Failed to remove character from my team");
+        }
+    }
+
+    /// Remembers the My Team keys of the website for search results.
+    pub fn store_my_team_keys(&self, characters: &[Character]) {
+        let mut write_lock = self.conn.write().unwrap();
+        let transaction = write_lock.transaction().expect("Failed to start transaction");
+
+        for character in characters.iter().filter(|character| !character.my_team_key.is_empty()) {
+            transaction
+                .execute(
+                    "INSERT OR REPLACE INTO my_team_keys (character_id, key) VALUES (?, ?)",
+                    params![character.number, character.my_team_key],
+                )
+                .expect("Failed to store my team key");
+        }
+
+        transaction.commit().expect("Failed to commit my team keys");
+    }
+
+    /// My Team keys of the website, by character number.
+    pub fn my_team_keys(&self) -> HashMap<u16, String> {
+        let read_lock = self.conn.read().unwrap();
+
+        read_lock
+            .prepare("SELECT character_id, key FROM my_team_keys")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                    .map(|rows| rows.filter_map(Result::ok).collect())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Replaces the whole of My Team, keeping the given order.
+    pub fn replace_my_team(&self, character_ids: &[u16]) {
+        let profile_id = self.profile_id();
+        let mut write_lock = self.conn.write().unwrap();
+        let transaction = write_lock.transaction().expect("Failed to start transaction");
+
+        transaction
+            .execute("DELETE FROM my_team WHERE profile_id = ?", params![profile_id])
+            .expect("Failed to clear my team");
+
+        let now = now_timestamp();
+        for (offset, id) in character_ids.iter().enumerate() {
+            transaction
+                .execute(
+                    "INSERT OR IGNORE INTO my_team (profile_id, character_id, added_at) VALUES (?, ?, ?)",
+                    params![profile_id, id, now + offset as i64],
+                )
+                .expect("Failed to add character to my team");
+        }
+
+        transaction.commit().expect("Failed to commit my team");
+    }
+}
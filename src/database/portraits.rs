@@ -0,0 +1,99 @@
+use std::collections::HashSet;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{database::Database, utils::now_timestamp};
+
+pub(super) fn create_tables(conn: &Connection) {
+    conn.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS portraits (
+            character_id INTEGER PRIMARY KEY,
+            url TEXT NOT NULL,
+            data BLOB NOT NULL,
+            fetched_at INTEGER
+        )
+        "#,
+        [],
+    )
+    .expect("Failed to create portraits table");
+}
+
+impl Database {
+    /// Image data of the portrait of a character, if it was downloaded.
+    pub fn portrait(&self, character_id: u16) -> Option<Vec<u8>> {
+        let read_lock = self.conn.read().unwrap();
+
+        read_lock
+            .query_row(
+                "SELECT data FROM portraits WHERE character_id = ?",
+                params![character_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .ok()
+            .flatten()
+    }
+
+    /// Whether the portrait at this URL was already downloaded for the
+    /// character.
+    pub fn has_portrait(&self, character_id: u16, url: &str) -> bool {
+        let read_lock = self.conn.read().unwrap();
+
+        read_lock
+            .query_row(
+                "SELECT 1 FROM portraits WHERE character_id = ? AND url = ?",
+                params![character_id, url],
+                |_| Ok(()),
+            )
+            .optional()
+            .is_ok_and(|found| found.is_some())
+    }
+
+    /// Portrait URLs of more than one cached character.
+    pub fn shared_portrait_urls(&self) -> HashSet<String> {
+        let read_lock = self.conn.read().unwrap();
+
+        read_lock
+            .prepare(
+                r#"
+                SELECT portrait_url FROM characters
+                WHERE portrait_url <> ''
+                GROUP BY portrait_url
+                HAVING COUNT(*) > 1
+                "#,
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get(0))
+                    .map(|rows| rows.filter_map(Result::ok).collect())
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn delete_portraits_at(&self, urls: &HashSet<String>) {
+        let write_lock = self.conn.write().unwrap();
+
+        for url in urls {
+            write_lock
+                .execute("DELETE FROM portraits WHERE url = ?", params![url])
+                .expect("Failed to delete portraits");
+        }
+    }
+
+    pub fn store_portrait(&self, character_id: u16, url: &str, data: &[u8]) {
+        let write_lock = self.conn.write().unwrap();
+
+        write_lock
+            .execute(
+                r#"
+                INSERT INTO portraits (character_id, url, data, fetched_at) VALUES (?, ?, ?, ?)
+                ON CONFLICT(character_id) DO UPDATE SET
+                    url = excluded.url,
+                    data = excluded.data,
+                    fetched_at = excluded.fetched_at
+                "#,
+                params![character_id, url, data, now_timestamp()],
+            )
+            .expect("Failed to store portrait");
+    }
+}
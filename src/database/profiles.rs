@@ -0,0 +1,189 @@
+use std::sync::atomic::Ordering;
+
+use rusqlite::{params, Connection};
+
+use crate::database::Database;
+
+/// Profile every installation starts with, owning the data created before
+/// profiles existed.
+pub const DEFAULT_PROFILE: i64 = 1;
+
+/// Global setting holding the profile in use when the app was closed.
+const CURRENT_PROFILE_SETTING: &str = "current_profile";
+
+/// Tables holding personal data, every row belonging to a profile.
+const PROFILE_TABLES: [&str; 11] = [
+    "settings",
+    "derived_columns",
+    "saved_searches",
+    "search_history",
+    "teams",
+    "my_team",
+    "roster",
+    "character_annotations",
+    "character_tags",
+    "character_modifiers",
+    "modifiers",
+];
+
+/// A named set of personal data: settings, searches, teams, roster and
+/// annotations. The scraped characters are shared by every profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub id: i64,
+    pub name: String,
+}
+
+pub(super) fn create_tables(conn: &Connection) {
+    conn.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS profiles (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT UNIQUE NOT NULL
+        )
+        "#,
+        [],
+    )
+    .expect("Failed to create profiles table");
+
+    conn.execute(
+        "INSERT OR IGNORE INTO profiles (id, name) VALUES (?, 'Default')",
+        [DEFAULT_PROFILE],
+    )
+    .expect("Failed to create default profile");
+}
+
+/// Creates a table holding personal data. A table created before profiles
+/// existed is rebuilt with the given definition, which must have a
+/// `profile_id` column, and its rows go to the default profile.
+pub(super) fn create_scoped_table(conn: &Connection, table: &str, definition: &str) {
+    conn.execute(&format!("CREATE TABLE IF NOT EXISTS {table} ({definition})"), [])
+        .unwrap_or_else(|error| panic!("Failed to create {table} table: {error}"));
+
+    if conn.prepare(&format!("SELECT profile_id FROM {table} LIMIT 0")).is_ok() {
+        return;
+    }
+
+    let columns = |table: &str| -> Vec<String> {
+        conn.prepare(&format!("PRAGMA table_info({table})"))
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get("name"))
+                    .map(|rows| rows.filter_map(Result::ok).collect())
+            })
+            .unwrap_or_default()
+    };
+
+    let scoped = format!("{table}_scoped");
+    conn.execute(&format!("CREATE TABLE IF NOT EXISTS {scoped} ({definition})"), [])
+        .unwrap_or_else(|error| panic!("Failed to create {table} table: {error}"));
+
+    // Columns added to the definition since the old table was created are
+    // left empty
+    let old_columns = columns(table);
+    let copied = columns(&scoped)
+        .into_iter()
+        .filter(|column| old_columns.contains(column))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    conn.execute_batch(&format!(
+        r#"
+        BEGIN;
+        INSERT INTO {scoped} (profile_id, {copied}) SELECT {DEFAULT_PROFILE}, {copied} FROM {table};
+        DROP TABLE {table};
+        ALTER TABLE {scoped} RENAME TO {table};
+        COMMIT;
+        "#
+    ))
+    .unwrap_or_else(|error| panic!("Failed to scope {table} table to profiles: {error}"));
+}
+
+impl Database {
+    /// Switches to the profile stored as current when the app was last
+    /// closed, if it still exists.
+    pub(super) fn restore_profile(&self) {
+        let stored = self
+            .global_setting(CURRENT_PROFILE_SETTING)
+            .and_then(|id| id.parse().ok())
+            .filter(|id| self.profiles().iter().any(|profile| profile.id == *id));
+
+        if let Some(id) = stored {
+            self.profile.store(id, Ordering::Relaxed);
+        }
+    }
+
+    pub fn profiles(&self) -> Vec<Profile> {
+        let read_lock = self.conn.read().unwrap();
+
+        read_lock
+            .prepare("SELECT id, name FROM profiles ORDER BY id")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| Ok(Profile { id: row.get(0)?, name: row.get(1)? }))
+                    .map(|rows| rows.filter_map(Result::ok).collect())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Makes every clone of the database read and write the personal data of
+    /// the given profile, and remembers it for the next launch.
+    pub fn switch_profile(&self, id: i64) {
+        self.profile.store(id, Ordering::Relaxed);
+        self.set_global_setting(CURRENT_PROFILE_SETTING, &id.to_string());
+    }
+
+    /// Creates a profile, or finds the one with the same name. Returns its id.
+    pub fn create_profile(&self, name: &str) -> i64 {
+        let write_lock = self.conn.write().unwrap();
+
+        write_lock
+            .query_row(
+                r#"
+                INSERT INTO profiles (name) VALUES (?)
+                ON CONFLICT(name) DO UPDATE SET name = excluded.name
+                RETURNING id
+                "#,
+                params![name],
+                |row| row.get(0),
+            )
+            .expect("Failed to create profile")
+    }
+
+    /// Renames a profile, returning false if the name is already used.
+    pub fn rename_profile(&self, id: i64, name: &str) -> bool {
+        let write_lock = self.conn.write().unwrap();
+
+        write_lock
+            .execute("UPDATE profiles SET name = ? WHERE id = ?", params![name, id])
+            .is_ok()
+    }
+
+    /// Deletes a profile and all of its personal data. The default profile
+    /// can't be deleted.
+    pub fn delete_profile(&self, id: i64) {
+        if id == DEFAULT_PROFILE {
+            return;
+        }
+
+        let mut write_lock = self.conn.write().unwrap();
+        let transaction = write_lock.transaction().expect("Failed to start transaction");
+
+        transaction
+            .execute(
+                "DELETE FROM team_members WHERE team_id IN (SELECT id FROM teams WHERE profile_id = ?)",
+                params![id],
+            )
+            .expect("Failed to delete profile teams");
+
+        for table in PROFILE_TABLES {
+            transaction
+                .execute(&format!("DELETE FROM {table} WHERE profile_id = ?"), params![id])
+                .expect("Failed to delete profile data");
+        }
+
+        transaction
+            .execute("DELETE FROM profiles WHERE id = ?", params![id])
+            .expect("Failed to delete profile");
+
+        transaction.commit().expect("Failed to commit profile deletion");
+    }
+}
@@ -0,0 +1,77 @@
+use rusqlite::{params, Connection};
+
+use crate::{database::{Database, profiles::create_scoped_table}, roster::RosterEntry};
+
+pub(super) fn create_tables(conn: &Connection) {
+    create_scoped_table(
+        conn,
+        "roster",
+        r#"
+            profile_id INTEGER NOT NULL REFERENCES profiles(id) ON DELETE CASCADE,
+            character_id INTEGER NOT NULL,
+            recruited_on TEXT NOT NULL,
+            level INTEGER,
+            notes TEXT NOT NULL,
+            PRIMARY KEY (profile_id, character_id)
+        "#,
+    );
+}
+
+impl Database {
+    /// Characters recruited in the current profile, by number.
+    pub fn roster(&self) -> Vec<RosterEntry> {
+        let read_lock = self.conn.read().unwrap();
+
+        read_lock
+            .prepare("SELECT * FROM roster WHERE profile_id = ? ORDER BY character_id")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![self.profile_id()], |row| {
+                    Ok(RosterEntry {
+                        character_id: row.get("character_id")?,
+                        recruited_on: row.get("recruited_on")?,
+                        level: row.get("level")?,
+                        notes: row.get("notes")?,
+                    })
+                })
+                .map(|rows| rows.filter_map(Result::ok).collect())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Numbers of the characters recruited in the current profile.
+    pub fn owned_characters(&self) -> Vec<u16> {
+        self.roster().into_iter().map(|entry| entry.character_id).collect()
+    }
+
+    /// Adds or updates characters of the roster of the current profile.
+    pub fn save_roster_entries(&self, entries: &[RosterEntry]) {
+        let profile_id = self.profile_id();
+        let mut write_lock = self.conn.write().unwrap();
+        let transaction = write_lock.transaction().expect("Failed to start transaction");
+
+        for entry in entries {
+            transaction
+                .execute(
+                    r#"
+                    INSERT OR REPLACE INTO roster (profile_id, character_id, recruited_on, level, notes)
+                    VALUES (?, ?, ?, ?, ?)
+                    "#,
+                    params![profile_id, entry.character_id, entry.recruited_on, entry.level, entry.notes],
+                )
+                .expect("Failed to save roster entry");
+        }
+
+        transaction.commit().expect("Failed to commit roster");
+    }
+
+    pub fn remove_from_roster(&self, character_id: u16) {
+        let write_lock = self.conn.write().unwrap();
+
+        write_lock
+            .execute(
+                "DELETE FROM roster WHERE profile_id = ? AND character_id = ?",
+                params![self.profile_id(), character_id],
+            )
+            .expect("Failed to remove roster entry");
+    }
+}
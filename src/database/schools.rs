@@ -0,0 +1,79 @@
+use rusqlite::{params, Connection};
+
+use crate::database::Database;
+
+/// Expression giving the school name of a row of the `characters` table,
+/// empty when unknown.
+pub const SCHOOL_NAME_SQL: &str = "IFNULL((SELECT name FROM schools WHERE schools.id = characters.school_id), '')";
+
+pub(super) fn create_tables(conn: &Connection) {
+    conn.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS schools (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT UNIQUE NOT NULL
+        )
+        "#,
+        [],
+    )
+    .expect("Failed to create schools table");
+
+    // Caches created before schools were stored lack the column
+    let _ = conn.execute("ALTER TABLE characters ADD COLUMN school_id INTEGER REFERENCES schools(id)", []);
+
+    // Profiles stored before schools were only known by their affiliation
+    conn.execute(
+        r#"
+        INSERT OR IGNORE INTO schools (name)
+        SELECT DISTINCT affiliation FROM character_profiles WHERE affiliation != ''
+        "#,
+        [],
+    )
+    .expect("Failed to fill schools table");
+
+    conn.execute(
+        r#"
+        UPDATE characters SET school_id = (
+            SELECT schools.id FROM character_profiles
+            JOIN schools ON schools.name = character_profiles.affiliation
+            WHERE character_profiles.character_id = characters.id
+        )
+        WHERE school_id IS NULL
+        "#,
+        [],
+    )
+    .expect("Failed to link characters to their school");
+}
+
+/// Links a character to the school of the given name, creating it if needed.
+pub(super) fn assign_school(conn: &Connection, character_id: u16, name: &str) {
+    conn.execute("INSERT OR IGNORE INTO schools (name) VALUES (?)", params![name])
+        .expect("Failed to store school");
+
+    conn.execute(
+        "UPDATE characters SET school_id = (SELECT id FROM schools WHERE name = ?) WHERE id = ?",
+        params![name, character_id],
+    )
+    .expect("Failed to link character to its school");
+}
+
+impl Database {
+    /// Names of the schools with at least one cached character, sorted.
+    pub fn schools(&self) -> Vec<String> {
+        let read_lock = self.conn.read().unwrap();
+
+        read_lock
+            .prepare(
+                r#"
+                SELECT name FROM schools
+                WHERE EXISTS (SELECT 1 FROM characters WHERE characters.school_id = schools.id)
+                ORDER BY name
+                "#,
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get(0))
+                    .map(|rows| rows.filter_map(Result::ok).collect())
+            })
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,243 @@
+use rusqlite::{params, Connection, Row};
+
+use crate::{
+    database::{Database, profiles::create_scoped_table},
+    request::Request,
+    utils::{ElementFlags, GameFlags, PositionFlags, StatFilters, now_timestamp},
+};
+
+/// Number of entries kept in the search history.
+const HISTORY_LENGTH: usize = 50;
+
+/// A named request stored in the database, along with the sort that was
+/// active when it was saved.
+#[derive(Debug, Clone)]
+pub struct SavedSearch {
+    pub id: i64,
+    pub name: String,
+    pub request: Request,
+    pub query: String,
+    pub sort: String,
+}
+
+/// A request that was sent at some point, online or against the cache.
+#[derive(Debug, Clone)]
+pub struct SearchHistoryEntry {
+    pub sent_at: i64,
+    pub request: Request,
+    pub query: String,
+    pub source: SearchSource,
+    pub result_count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchSource {
+    Online,
+    Cache,
+}
+
+impl SearchSource {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            SearchSource::Online => "Online",
+            SearchSource::Cache => "Cache",
+        }
+    }
+
+    pub fn from_db_str(text: &str) -> SearchSource {
+        match text {
+            "Cache" => SearchSource::Cache,
+            _ => SearchSource::Online,
+        }
+    }
+}
+
+pub(super) fn create_tables(conn: &Connection) {
+    create_scoped_table(
+        conn,
+        "saved_searches",
+        r#"
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            profile_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            query_name TEXT,
+            elements INTEGER,
+            positions INTEGER,
+            games INTEGER,
+            stat_filters TEXT,
+            query TEXT,
+            sort TEXT,
+            UNIQUE (profile_id, name)
+        "#,
+    );
+
+    create_scoped_table(
+        conn,
+        "search_history",
+        r#"
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            profile_id INTEGER NOT NULL,
+            sent_at INTEGER,
+            query_name TEXT,
+            elements INTEGER,
+            positions INTEGER,
+            games INTEGER,
+            stat_filters TEXT,
+            query TEXT,
+            source TEXT,
+            result_count INTEGER
+        "#,
+    );
+
+    // Histories recorded before the query was stored lack the column
+    let _ = conn.execute("ALTER TABLE search_history ADD COLUMN query TEXT", []);
+}
+
+/// Rebuilds the request stored in the `query_name`, `elements`, `positions`,
+/// `games` and `stat_filters` columns of a row.
+fn request_from_row(row: &Row) -> rusqlite::Result<Request> {
+    let stat_filters: Option<String> = row.get("stat_filters")?;
+
+    Ok(Request::with_filters(
+        row.get("query_name")?,
+        ElementFlags::from_bits_truncate(row.get("elements")?),
+        PositionFlags::from_bits_truncate(row.get("positions")?),
+        GameFlags::from_bits_truncate(row.get("games")?),
+        StatFilters::from_spec(&stat_filters.unwrap_or_default()),
+    ))
+}
+
+impl Database {
+    /// Saves a request and its query under the given name, replacing any
+    /// saved search that already uses it.
+    pub fn save_search(&self, name: &str, request: &Request, query: &str, sort: &str) {
+        let write_lock = self.conn.write().unwrap();
+
+        write_lock
+            .execute(
+                r#"
+                INSERT INTO saved_searches (profile_id, name, query_name, elements, positions, games, stat_filters, query, sort)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT(profile_id, name) DO UPDATE SET
+                    query_name = excluded.query_name,
+                    elements = excluded.elements,
+                    positions = excluded.positions,
+                    games = excluded.games,
+                    stat_filters = excluded.stat_filters,
+                    query = excluded.query,
+                    sort = excluded.sort
+                "#,
+                params![
+                    self.profile_id(),
+                    name,
+                    request.name,
+                    request.elements().bits(),
+                    request.positions().bits(),
+                    request.games().bits(),
+                    request.stat_filters.to_spec(),
+                    query,
+                    sort
+                ],
+            )
+            .expect("Failed to save search");
+    }
+
+    pub fn saved_searches(&self) -> Vec<SavedSearch> {
+        let read_lock = self.conn.read().unwrap();
+        let mut stmt = match read_lock.prepare("SELECT * FROM saved_searches WHERE profile_id = ? ORDER BY name") {
+            Ok(stmt) => stmt,
+            Err(_) => return Vec::new(),
+        };
+
+        stmt.query_map(params![self.profile_id()], |row| {
+            Ok(SavedSearch {
+                id: row.get("id")?,
+                name: row.get("name")?,
+                request: request_from_row(row)?,
+                query: row.get::<_, Option<String>>("query")?.unwrap_or_default(),
+                sort: row.get::<_, Option<String>>("sort")?.unwrap_or_default(),
+            })
+        })
+        .map(|rows| rows.filter_map(Result::ok).collect())
+        .unwrap_or_default()
+    }
+
+    pub fn delete_saved_search(&self, id: i64) {
+        let write_lock = self.conn.write().unwrap();
+
+        write_lock
+            .execute("DELETE FROM saved_searches WHERE id = ?", params![id])
+            .expect("Failed to delete saved search");
+    }
+
+    /// Adds a request to the search history, dropping the oldest entries
+    /// once the history is full.
+    pub fn record_search(&self, request: &Request, query: &str, source: SearchSource, result_count: usize) {
+        let profile_id = self.profile_id();
+        let write_lock = self.conn.write().unwrap();
+
+        write_lock
+            .execute(
+                r#"
+                INSERT INTO search_history (profile_id, sent_at, query_name, elements, positions, games, stat_filters, query, source, result_count)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+                params![
+                    profile_id,
+                    now_timestamp(),
+                    request.name,
+                    request.elements().bits(),
+                    request.positions().bits(),
+                    request.games().bits(),
+                    request.stat_filters.to_spec(),
+                    query,
+                    source.to_str(),
+                    result_count as i64
+                ],
+            )
+            .expect("Failed to record search");
+
+        write_lock
+            .execute(
+                r#"
+                DELETE FROM search_history
+                WHERE profile_id = ?1
+                AND id NOT IN (SELECT id FROM search_history WHERE profile_id = ?1 ORDER BY id DESC LIMIT ?2)
+                "#,
+                params![profile_id, HISTORY_LENGTH as i64],
+            )
+            .expect("Failed to trim search history");
+    }
+
+    /// Returns the search history, most recent first.
+    pub fn search_history(&self) -> Vec<SearchHistoryEntry> {
+        let read_lock = self.conn.read().unwrap();
+        let mut stmt = match read_lock.prepare("SELECT * FROM search_history WHERE profile_id = ? ORDER BY id DESC") {
+            Ok(stmt) => stmt,
+            Err(_) => return Vec::new(),
+        };
+
+        stmt.query_map(params![self.profile_id()], |row| {
+            let source: String = row.get("source")?;
+            let result_count: i64 = row.get("result_count")?;
+
+            Ok(SearchHistoryEntry {
+                sent_at: row.get("sent_at")?,
+                request: request_from_row(row)?,
+                query: row.get::<_, Option<String>>("query")?.unwrap_or_default(),
+                source: SearchSource::from_db_str(&source),
+                result_count: result_count as usize,
+            })
+        })
+        .map(|rows| rows.filter_map(Result::ok).collect())
+        .unwrap_or_default()
+    }
+
+    pub fn clear_search_history(&self) {
+        let write_lock = self.conn.write().unwrap();
+
+        write_lock
+            .execute("DELETE FROM search_history WHERE profile_id = ?", params![self.profile_id()])
+            .expect("Failed to clear search history");
+    }
+}
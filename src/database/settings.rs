@@ -0,0 +1,122 @@
+use rusqlite::{params, Connection};
+
+use crate::{
+    database::{Database, profiles::create_scoped_table},
+    query::DerivedColumn,
+};
+
+/// Settings shared by every profile are stored under this profile id.
+const GLOBAL_SETTINGS: i64 = 0;
+
+pub(super) fn create_tables(conn: &Connection) {
+    create_scoped_table(
+        conn,
+        "settings",
+        r#"
+            profile_id INTEGER NOT NULL,
+            key TEXT NOT NULL,
+            value TEXT,
+            PRIMARY KEY (profile_id, key)
+        "#,
+    );
+
+    create_scoped_table(
+        conn,
+        "derived_columns",
+        r#"
+            profile_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            formula TEXT NOT NULL,
+            PRIMARY KEY (profile_id, position)
+        "#,
+    );
+}
+
+impl Database {
+    /// Setting of the current profile.
+    pub fn setting(&self, key: &str) -> Option<String> {
+        self.profile_setting(self.profile_id(), key)
+    }
+
+    pub fn set_setting(&self, key: &str, value: &str) {
+        self.set_profile_setting(self.profile_id(), key, value);
+    }
+
+    /// Setting shared by every profile.
+    pub(super) fn global_setting(&self, key: &str) -> Option<String> {
+        self.profile_setting(GLOBAL_SETTINGS, key)
+    }
+
+    pub(super) fn set_global_setting(&self, key: &str, value: &str) {
+        self.set_profile_setting(GLOBAL_SETTINGS, key, value);
+    }
+
+    fn profile_setting(&self, profile_id: i64, key: &str) -> Option<String> {
+        let read_lock = self.conn.read().unwrap();
+
+        read_lock
+            .query_row(
+                "SELECT value FROM settings WHERE profile_id = ? AND key = ?",
+                params![profile_id, key],
+                |row| row.get(0),
+            )
+            .ok()
+    }
+
+    fn set_profile_setting(&self, profile_id: i64, key: &str, value: &str) {
+        let write_lock = self.conn.write().unwrap();
+
+        write_lock
+            .execute(
+                "INSERT OR REPLACE INTO settings (profile_id, key, value) VALUES (?, ?, ?)",
+                params![profile_id, key, value],
+            )
+            .expect("Failed to store setting");
+    }
+
+    /// Returns the stored derived columns, or `None` if they were never saved.
+    pub fn derived_columns(&self) -> Option<Vec<DerivedColumn>> {
+        self.setting("derived_columns_saved")?;
+
+        let read_lock = self.conn.read().unwrap();
+        let mut stmt = read_lock
+            .prepare("SELECT name, formula FROM derived_columns WHERE profile_id = ? ORDER BY position")
+            .ok()?;
+
+        stmt.query_map(params![self.profile_id()], |row| {
+            let name: String = row.get("name")?;
+            let formula: String = row.get("formula")?;
+            Ok(DerivedColumn::new(&name, &formula))
+        })
+        .map(|rows| rows.filter_map(Result::ok).collect())
+        .ok()
+    }
+
+    pub fn store_derived_columns(&self, columns: &[DerivedColumn]) {
+        let profile_id = self.profile_id();
+
+        {
+            let mut write_lock = self.conn.write().unwrap();
+            let transaction = write_lock.transaction().expect("Failed to start transaction");
+
+            transaction
+                .execute("DELETE FROM derived_columns WHERE profile_id = ?", params![profile_id])
+                .expect("Failed to clear derived columns");
+
+            for (position, column) in columns.iter().enumerate() {
+                transaction
+                    .execute(
+                        "INSERT INTO derived_columns (profile_id, position, name, formula) VALUES (?, ?, ?, ?)",
+                        params![profile_id, position as i64, column.name, column.formula()],
+                    )
+                    .expect("Failed to store derived column");
+            }
+
+            transaction.commit().expect("Failed to store derived columns");
+        }
+
+        // Distinguishes "no derived columns" from "never configured"
+        self.set_setting("derived_columns_saved", "1");
+    }
+}
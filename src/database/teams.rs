@@ -0,0 +1,150 @@
+use rusqlite::{params, Connection};
+
+use crate::{
+    database::{Database, profiles::create_scoped_table},
+    modifiers::ModifierScope,
+    team::{FORMATION_LIST, Formation, TEAM_SIZE, Team},
+};
+
+pub(super) fn create_tables(conn: &Connection) {
+    create_scoped_table(
+        conn,
+        "teams",
+        r#"
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            profile_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            formation TEXT NOT NULL,
+            UNIQUE (profile_id, name)
+        "#,
+    );
+
+    // Slots below TEAM_SIZE are on the pitch, the following ones on the bench
+    conn.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS team_members (
+            team_id INTEGER NOT NULL REFERENCES teams(id) ON DELETE CASCADE,
+            slot INTEGER NOT NULL,
+            character_id INTEGER NOT NULL,
+            PRIMARY KEY (team_id, slot)
+        )
+        "#,
+        [],
+    )
+    .expect("Failed to create team members table");
+}
+
+impl Database {
+    /// Saves a team, replacing the team with the same id. Returns the id of
+    /// the team, or `None` if another team of the profile already has its
+    /// name or the team is no longer in the profile.
+    pub fn save_team(&self, team: &Team) -> Option<i64> {
+        let profile_id = self.profile_id();
+        let mut write_lock = self.conn.write().unwrap();
+        let transaction = write_lock.transaction().expect("Failed to start transaction");
+
+        let id: i64 = match team.id {
+            Some(id) => {
+                let updated = transaction.execute(
+                    "UPDATE teams SET name = ?, formation = ? WHERE id = ? AND profile_id = ?",
+                    params![team.name, team.formation.name, id, profile_id],
+                );
+
+                // The name is unique within the profile, and nothing is
+                // updated for a team that was deleted
+                if !matches!(updated, Ok(1)) {
+                    return None;
+                }
+                id
+            }
+            None => transaction
+                .query_row(
+                    "INSERT INTO teams (profile_id, name, formation) VALUES (?, ?, ?) RETURNING id",
+                    params![profile_id, team.name, team.formation.name],
+                    |row| row.get(0),
+                )
+                .ok()?,
+        };
+
+        transaction
+            .execute("DELETE FROM team_members WHERE team_id = ?", params![id])
+            .expect("Failed to clear team members");
+
+        let starters = team.players
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, player)| player.map(|number| (slot, number)));
+        let bench = team.bench
+            .iter()
+            .enumerate()
+            .map(|(index, number)| (TEAM_SIZE + index, *number));
+
+        for (slot, number) in starters.chain(bench) {
+            transaction
+                .execute(
+                    "INSERT INTO team_members (team_id, slot, character_id) VALUES (?, ?, ?)",
+                    params![id, slot as i64, number],
+                )
+                .expect("Failed to store team member");
+        }
+
+        transaction.commit().expect("Failed to save team");
+        Some(id)
+    }
+
+    pub fn teams(&self) -> Vec<Team> {
+        let read_lock = self.conn.read().unwrap();
+
+        let mut teams: Vec<Team> = read_lock
+            .prepare("SELECT id, name, formation FROM teams WHERE profile_id = ? ORDER BY name")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![self.profile_id()], |row| {
+                    let name: String = row.get("name")?;
+                    let formation: String = row.get("formation")?;
+
+                    let mut team = Team::new(&name);
+                    team.id = Some(row.get("id")?);
+                    team.formation = Formation::from_name(&formation).unwrap_or(FORMATION_LIST[0]);
+                    Ok(team)
+                })
+                .map(|rows| rows.filter_map(Result::ok).collect())
+            })
+            .unwrap_or_default();
+
+        let mut stmt = match read_lock.prepare("SELECT slot, character_id FROM team_members WHERE team_id = ? ORDER BY slot") {
+            Ok(stmt) => stmt,
+            Err(_) => return teams,
+        };
+
+        for team in teams.iter_mut() {
+            let members = stmt
+                .query_map(params![team.id], |row| Ok((row.get::<_, i64>(0)? as usize, row.get::<_, u16>(1)?)))
+                .map(|rows| rows.filter_map(Result::ok).collect::<Vec<_>>())
+                .unwrap_or_default();
+
+            for (slot, number) in members {
+                if slot < TEAM_SIZE {
+                    team.players[slot] = Some(number);
+                } else {
+                    team.bench.push(number);
+                }
+            }
+        }
+
+        teams
+    }
+
+    pub fn delete_team(&self, id: i64) {
+        let write_lock = self.conn.write().unwrap();
+
+        write_lock
+            .execute("DELETE FROM team_members WHERE team_id = ?", params![id])
+            .expect("Failed to delete team members");
+        write_lock
+            .execute("DELETE FROM character_modifiers WHERE scope = ?", params![ModifierScope::Team(id).to_key()])
+            .expect("Failed to detach team modifiers");
+        write_lock
+            .execute("DELETE FROM teams WHERE id = ?", params![id])
+            .expect("Failed to delete team");
+    }
+}
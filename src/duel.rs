@@ -0,0 +1,203 @@
+use crate::{elements::AffinityTable, query::DerivedColumn, utils::{Character, Stat}};
+
+/// Margin, in formula points, at which the attacker wins about 73 % of the
+/// time by default.
+pub const DEFAULT_SCALE: f64 = 20.0;
+
+/// A one-on-one encounter of a match.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encounter {
+    Shot,
+    Dribble,
+    Pass,
+}
+
+pub const ENCOUNTER_LIST: [Encounter; 3] = [Encounter::Shot, Encounter::Dribble, Encounter::Pass];
+
+impl Encounter {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Encounter::Shot => "Shoot vs keeper",
+            Encounter::Dribble => "Dribble vs block",
+            Encounter::Pass => "Pass vs intercept",
+        }
+    }
+
+    /// Names of the attacking and defending sides.
+    pub fn sides(self) -> (&'static str, &'static str) {
+        match self {
+            Encounter::Shot => ("Shooter", "Keeper"),
+            Encounter::Dribble => ("Dribbler", "Blocker"),
+            Encounter::Pass => ("Passer", "Interceptor"),
+        }
+    }
+
+    /// Key of the settings storing the formulas of the encounter.
+    pub fn setting_key(self) -> &'static str {
+        match self {
+            Encounter::Shot => "duel_shot",
+            Encounter::Dribble => "duel_dribble",
+            Encounter::Pass => "duel_pass",
+        }
+    }
+
+    /// Stats of the attacking and defending sides, added up by the default
+    /// formulas.
+    pub fn stats(self) -> ([Stat; 2], [Stat; 2]) {
+        match self {
+            Encounter::Shot => ([Stat::Kick, Stat::Technique], [Stat::Physical, Stat::Pressure]),
+            Encounter::Dribble => ([Stat::Control, Stat::Agility], [Stat::Physical, Stat::Pressure]),
+            Encounter::Pass => ([Stat::Control, Stat::Intelligence], [Stat::Agility, Stat::Intelligence]),
+        }
+    }
+
+    pub fn default_formula(self) -> DuelFormula {
+        let formula = |stats: [Stat; 2]| stats.map(|stat| stat.to_str().to_lowercase()).join(" + ");
+        let (attacker, defender) = self.stats();
+
+        DuelFormula {
+            attacker: DerivedColumn::new("attacker", &formula(attacker)),
+            defender: DerivedColumn::new("defender", &formula(defender)),
+        }
+    }
+}
+
+/// Formulas scoring each side of an encounter, over the stats of the
+/// character.
+#[derive(Debug, Clone)]
+pub struct DuelFormula {
+    pub attacker: DerivedColumn,
+    pub defender: DerivedColumn,
+}
+
+impl DuelFormula {
+    /// Serializes the formulas as `attacker|defender`.
+    pub fn to_spec(&self) -> String {
+        format!("{}|{}", self.attacker.formula(), self.defender.formula())
+    }
+
+    /// Parses formulas produced by `to_spec`, `None` if malformed.
+    pub fn from_spec(spec: &str) -> Option<DuelFormula> {
+        let (attacker, defender) = spec.split_once('|')?;
+
+        Some(DuelFormula {
+            attacker: DerivedColumn::new("attacker", attacker),
+            defender: DerivedColumn::new("defender", defender),
+        })
+    }
+}
+
+/// A character taking part in a duel, with the power of the technique it
+/// uses, 0 without technique.
+#[derive(Debug, Clone, Copy)]
+pub struct DuelSide<'a> {
+    pub character: &'a Character,
+    pub power: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuelEstimate {
+    pub attacker_score: f64,
+    pub defender_score: f64,
+    /// Probability that the attacker wins, between 0 and 1.
+    pub probability: f64,
+}
+
+impl DuelEstimate {
+    /// Positive when the attacker has the upper hand.
+    pub fn margin(&self) -> f64 {
+        self.attacker_score - self.defender_score
+    }
+}
+
+/// Scores both sides of a duel: the formula of the side plus the power of its
+/// technique, multiplied by its element affinity against the other side when
+/// a table is given. The margin is turned into a win probability with a
+/// logistic curve, `scale` being the margin at which the attacker wins 73 %
+/// of the time. `None` when a formula can't be evaluated, e.g. without stats.
+pub fn estimate(
+    formula: &DuelFormula,
+    attacker: DuelSide,
+    defender: DuelSide,
+    affinity: Option<&AffinityTable>,
+    scale: f64,
+) -> Option<DuelEstimate> {
+    let multiplier = |side: &DuelSide, other: &DuelSide| {
+        affinity.map_or(1.0, |table| table.get(side.character.element, other.character.element))
+    };
+
+    let attacker_score = (formula.attacker.eval(attacker.character)? + attacker.power) * multiplier(&attacker, &defender);
+    let defender_score = (formula.defender.eval(defender.character)? + defender.power) * multiplier(&defender, &attacker);
+
+    let margin = attacker_score - defender_score;
+    let probability = 1.0 / (1.0 + (-margin / scale.max(f64::EPSILON)).exp());
+
+    Some(DuelEstimate { attacker_score, defender_score, probability })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        elements::{DEFAULT_ADVANTAGE, DEFAULT_DISADVANTAGE},
+        utils::{Element, Position, Stats},
+    };
+
+    fn character(number: u16, element: Element) -> Character {
+        Character {
+            number,
+            name: format!("Player {number}"),
+            nickname: String::new(),
+            japanese_name: String::new(),
+            japanese_nickname: String::new(),
+            school: String::new(),
+            element,
+            position: Position::FW,
+            stats: Some(Stats { kick: 50, control: 50, technique: 50, pressure: 50, physical: 50, agility: 50, intelligence: 50 }),
+            page_url: String::new(),
+            portrait_url: String::new(),
+            my_team_key: String::new(),
+        }
+    }
+
+    #[test]
+    fn probability_follows_a_logistic_curve_of_the_margin() {
+        let formula = Encounter::Shot.default_formula();
+        let (fire, forest) = (character(1, Element::FIRE), character(2, Element::FOREST));
+        let side = |character, power| DuelSide { character, power };
+
+        let even = estimate(&formula, side(&fire, 0.0), side(&forest, 0.0), None, DEFAULT_SCALE).unwrap();
+        assert_eq!((even.attacker_score, even.defender_score), (100.0, 100.0));
+        assert!((even.probability - 0.5).abs() < 1e-9);
+
+        // 1 / (1 + e^-1) at a margin of `scale`
+        let ahead = estimate(&formula, side(&fire, DEFAULT_SCALE), side(&forest, 0.0), None, DEFAULT_SCALE).unwrap();
+        assert_eq!(ahead.margin(), DEFAULT_SCALE);
+        assert!((ahead.probability - 0.731_058_578_6).abs() < 1e-9);
+
+        let behind = estimate(&formula, side(&fire, 0.0), side(&forest, DEFAULT_SCALE), None, DEFAULT_SCALE).unwrap();
+        assert!((behind.probability - (1.0 - ahead.probability)).abs() < 1e-9);
+
+        let table = AffinityTable::default();
+        let affinity = estimate(&formula, side(&fire, 0.0), side(&forest, 0.0), Some(&table), DEFAULT_SCALE).unwrap();
+        assert!((affinity.margin() - 100.0 * (DEFAULT_ADVANTAGE - DEFAULT_DISADVANTAGE)).abs() < 1e-9);
+
+        let no_stats = Character { stats: None, ..character(3, Element::FIRE) };
+        assert_eq!(estimate(&formula, side(&no_stats, 0.0), side(&forest, 0.0), None, DEFAULT_SCALE), None);
+    }
+
+    #[test]
+    fn formulas_round_trip_through_their_spec() {
+        for encounter in ENCOUNTER_LIST {
+            let formula = encounter.default_formula();
+            let parsed = DuelFormula::from_spec(&formula.to_spec()).unwrap();
+
+            assert_eq!(parsed.to_spec(), formula.to_spec());
+            assert_eq!(parsed.attacker.formula(), formula.attacker.formula());
+            assert_eq!(parsed.defender.formula(), formula.defender.formula());
+        }
+
+        assert_eq!(Encounter::Pass.default_formula().to_spec(), "control + intelligence|agility + intelligence");
+        assert!(DuelFormula::from_spec("kick + technique").is_none());
+    }
+}
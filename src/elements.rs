@@ -0,0 +1,332 @@
+use crate::{
+    duel::{ENCOUNTER_LIST, Encounter},
+    utils::{Character, ELEMENT_LIST, Element, Position, Stat, Stats},
+};
+
+/// Multiplier applied by default when an element beats another.
+pub const DEFAULT_ADVANTAGE: f64 = 1.2;
+/// Multiplier applied by default when an element is beaten by another.
+pub const DEFAULT_DISADVANTAGE: f64 = 0.8;
+
+/// The element each element beats: Fire over Forest, Forest over Wind, Wind
+/// over Mountain and Mountain over Fire. This cycle is written from memory of
+/// the games and hasn't been checked against them, the multipliers can be
+/// edited for that reason.
+const BEATS: [(Element, Element); 4] = [
+    (Element::FIRE, Element::FOREST),
+    (Element::FOREST, Element::WIND),
+    (Element::WIND, Element::MOUNTAIN),
+    (Element::MOUNTAIN, Element::FIRE),
+];
+
+/// Position of an element in `ELEMENT_LIST`, `None` for unknown elements.
+fn element_index(element: Element) -> Option<usize> {
+    ELEMENT_LIST.iter().position(|other| *other == element)
+}
+
+/// How much the stats of a character of one element count against a
+/// character of another, indexed in `ELEMENT_LIST` order. A character of an
+/// unknown element is always neutral.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AffinityTable {
+    multipliers: [[f64; 4]; 4],
+}
+
+impl Default for AffinityTable {
+    fn default() -> Self {
+        let mut table = AffinityTable { multipliers: [[1.0; 4]; 4] };
+
+        for (winner, loser) in BEATS {
+            table.set(winner, loser, DEFAULT_ADVANTAGE);
+            table.set(loser, winner, DEFAULT_DISADVANTAGE);
+        }
+
+        table
+    }
+}
+
+impl AffinityTable {
+    /// Multiplier of the stats of an attacker of the given element against a
+    /// defender of the other.
+    pub fn get(&self, attacker: Element, defender: Element) -> f64 {
+        match (element_index(attacker), element_index(defender)) {
+            (Some(attacker), Some(defender)) => self.multipliers[attacker][defender],
+            _ => 1.0,
+        }
+    }
+
+    /// Does nothing for unknown elements.
+    pub fn set(&mut self, attacker: Element, defender: Element, multiplier: f64) {
+        if let (Some(attacker), Some(defender)) = (element_index(attacker), element_index(defender)) {
+            self.multipliers[attacker][defender] = multiplier;
+        }
+    }
+
+    pub fn get_mut(&mut self, attacker: Element, defender: Element) -> Option<&mut f64> {
+        let (attacker, defender) = (element_index(attacker)?, element_index(defender)?);
+        Some(&mut self.multipliers[attacker][defender])
+    }
+
+    pub fn affinity(&self, attacker: Element, defender: Element) -> Affinity {
+        let multiplier = self.get(attacker, defender);
+
+        if multiplier > 1.0 {
+            Affinity::Advantage
+        } else if multiplier < 1.0 {
+            Affinity::Disadvantage
+        } else {
+            Affinity::Neutral
+        }
+    }
+
+    /// Serializes the multipliers row by row, e.g. `1,1.2,1,0.8;...`.
+    pub fn to_spec(&self) -> String {
+        self.multipliers
+            .iter()
+            .map(|row| row.iter().map(f64::to_string).collect::<Vec<_>>().join(","))
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    /// Parses multipliers produced by `to_spec`, `None` if malformed.
+    pub fn from_spec(spec: &str) -> Option<AffinityTable> {
+        let mut multipliers = [[1.0; 4]; 4];
+
+        let rows: Vec<&str> = spec.split(';').collect();
+        if rows.len() != 4 {
+            return None;
+        }
+
+        for (row, text) in multipliers.iter_mut().zip(rows) {
+            let values: Vec<f64> = text.split(',').map(|value| value.trim().parse().ok()).collect::<Option<_>>()?;
+            *row = values.try_into().ok()?;
+        }
+
+        Some(AffinityTable { multipliers })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Affinity {
+    Advantage,
+    Neutral,
+    Disadvantage,
+}
+
+impl Affinity {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Affinity::Advantage => "Advantage",
+            Affinity::Neutral => "Neutral",
+            Affinity::Disadvantage => "Disadvantage",
+        }
+    }
+}
+
+/// An encounter between two characters: the attacking stats of one against
+/// the defending stats of the other, before and after the element
+/// multipliers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncounterMatchup {
+    pub encounter: Encounter,
+    pub attacker: u16,
+    pub defender: u16,
+    pub adjusted_attacker: f64,
+    pub adjusted_defender: f64,
+}
+
+impl EncounterMatchup {
+    /// Adjusted difference, positive when the attacker has the upper hand.
+    pub fn margin(&self) -> f64 {
+        self.adjusted_attacker - self.adjusted_defender
+    }
+}
+
+/// Compares two characters in every encounter, pairing the stats of the
+/// attacking side with their defensive counterparts as the default duel
+/// formulas do. Each side is multiplied by the affinity of its element against
+/// the other's. Empty when either has no stats.
+pub fn matchup(attacker: &Character, defender: &Character, table: &AffinityTable) -> Vec<EncounterMatchup> {
+    let (Some(attacker_stats), Some(defender_stats)) = (&attacker.stats, &defender.stats) else {
+        return Vec::new();
+    };
+
+    let attacker_multiplier = table.get(attacker.element, defender.element);
+    let defender_multiplier = table.get(defender.element, attacker.element);
+
+    let total = |stats: &Stats, pair: [Stat; 2]| pair.iter().map(|stat| stats.get(*stat) as u16).sum::<u16>();
+
+    ENCOUNTER_LIST
+        .iter()
+        .map(|encounter| {
+            let (attacking, defending) = encounter.stats();
+            let (attacker, defender) = (total(attacker_stats, attacking), total(defender_stats, defending));
+
+            EncounterMatchup {
+                encounter: *encounter,
+                attacker,
+                defender,
+                adjusted_attacker: attacker as f64 * attacker_multiplier,
+                adjusted_defender: defender as f64 * defender_multiplier,
+            }
+        })
+        .collect()
+}
+
+/// How the players of a line of a team fare against one element.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineCoverage {
+    /// Players beating the element.
+    pub strong: usize,
+    /// Players beaten by the element.
+    pub weak: usize,
+    pub total: usize,
+}
+
+impl LineCoverage {
+    /// More players are beaten by the element than beat it.
+    pub fn is_weak(&self) -> bool {
+        self.weak > self.strong
+    }
+}
+
+/// How the defense and the attack of a team fare against one element.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementCoverage {
+    pub element: Element,
+    /// Goalkeepers and defenders, facing the opposing forwards.
+    pub defense: LineCoverage,
+    /// Midfielders, contesting the ball.
+    pub midfield: LineCoverage,
+    /// Forwards, shooting at the opposing keeper.
+    pub attack: LineCoverage,
+}
+
+/// Coverage of the team against each element, in `ELEMENT_LIST` order. A
+/// player counts in the line of the position they play.
+pub fn coverage(players: &[(&Character, Position)], table: &AffinityTable) -> Vec<ElementCoverage> {
+    let line = |element: Element, positions: &[Position]| {
+        let members: Vec<&Character> = players
+            .iter()
+            .filter(|(_, position)| positions.contains(position))
+            .map(|(character, _)| *character)
+            .collect();
+
+        LineCoverage {
+            strong: members.iter().filter(|member| table.affinity(member.element, element) == Affinity::Advantage).count(),
+            weak: members.iter().filter(|member| table.affinity(element, member.element) == Affinity::Advantage).count(),
+            total: members.len(),
+        }
+    };
+
+    ELEMENT_LIST
+        .iter()
+        .map(|element| ElementCoverage {
+            element: *element,
+            defense: line(*element, &[Position::GK, Position::DF]),
+            midfield: line(*element, &[Position::MF]),
+            attack: line(*element, &[Position::FW]),
+        })
+        .collect()
+}
+
+/// Sentences summarizing the weaknesses of a team, such as "Your defense is
+/// weak to Wind".
+pub fn coverage_warnings(coverage: &[ElementCoverage]) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    for entry in coverage {
+        let element = entry.element.db_str();
+
+        if entry.defense.is_weak() {
+            warnings.push(format!("Your defense is weak to {element} ({} of {} beaten)", entry.defense.weak, entry.defense.total));
+        }
+        if entry.midfield.is_weak() {
+            warnings.push(format!("Your midfield is weak to {element} ({} of {} beaten)", entry.midfield.weak, entry.midfield.total));
+        }
+        if entry.attack.total > 0 && entry.attack.strong == 0 {
+            warnings.push(format!("No forward has the advantage against {element}"));
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn character(number: u16, element: Element, position: Position) -> Character {
+        Character {
+            number,
+            name: format!("Player {number}"),
+            nickname: String::new(),
+            japanese_name: String::new(),
+            japanese_nickname: String::new(),
+            school: String::new(),
+            element,
+            position,
+            stats: Some(Stats { kick: 50, control: 50, technique: 50, pressure: 50, physical: 50, agility: 50, intelligence: 50 }),
+            page_url: String::new(),
+            portrait_url: String::new(),
+            my_team_key: String::new(),
+        }
+    }
+
+    #[test]
+    fn matchups_apply_the_affinity_of_each_side() {
+        let table = AffinityTable::default();
+        let fire = character(1, Element::FIRE, Position::FW);
+        let forest = character(2, Element::FOREST, Position::GK);
+
+        let matchups = matchup(&fire, &forest, &table);
+        assert_eq!(matchups.len(), ENCOUNTER_LIST.len());
+        for entry in &matchups {
+            assert_eq!((entry.attacker, entry.defender), (100, 100));
+            assert!((entry.adjusted_attacker - 100.0 * DEFAULT_ADVANTAGE).abs() < 1e-9);
+            assert!((entry.adjusted_defender - 100.0 * DEFAULT_DISADVANTAGE).abs() < 1e-9);
+            assert!(entry.margin() > 0.0);
+        }
+
+        assert!(matchup(&forest, &fire, &table).iter().all(|entry| entry.margin() < 0.0));
+
+        let unknown = character(3, Element::NONE, Position::GK);
+        assert!(matchup(&fire, &unknown, &table).iter().all(|entry| entry.margin() == 0.0));
+
+        let no_stats = Character { stats: None, ..character(4, Element::FIRE, Position::GK) };
+        assert!(matchup(&fire, &no_stats, &table).is_empty());
+
+        assert_eq!(AffinityTable::from_spec(&table.to_spec()), Some(table));
+        assert_eq!(AffinityTable::from_spec("1,1,1,1;1,1,1,1"), None);
+    }
+
+    #[test]
+    fn coverage_warns_about_weak_lines_and_forwards() {
+        let players = [
+            character(1, Element::FIRE, Position::GK),
+            character(2, Element::FIRE, Position::DF),
+            character(3, Element::WIND, Position::DF),
+            character(4, Element::FOREST, Position::MF),
+            character(5, Element::MOUNTAIN, Position::FW),
+        ];
+        let lineup: Vec<(&Character, Position)> = players.iter().map(|player| (player, player.position)).collect();
+
+        let coverage = coverage(&lineup, &AffinityTable::default());
+
+        let mountain = &coverage[2];
+        assert_eq!(mountain.element, Element::MOUNTAIN);
+        assert_eq!(mountain.defense, LineCoverage { strong: 1, weak: 2, total: 3 });
+        assert_eq!(mountain.attack, LineCoverage { strong: 0, weak: 0, total: 1 });
+
+        assert_eq!(
+            coverage_warnings(&coverage),
+            [
+                "Your midfield is weak to Fire (1 of 1 beaten)",
+                "No forward has the advantage against Forest",
+                "Your defense is weak to Mountain (2 of 3 beaten)",
+                "No forward has the advantage against Mountain",
+                "No forward has the advantage against Wind",
+            ]
+        );
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use eframe::egui::{self, FontData, FontDefinitions, FontFamily};
+
+/// Fonts covering Japanese shipped with common systems, tried in order.
+const CJK_FONT_PATHS: [&str; 9] = [
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/fonts-japanese-gothic.ttf",
+    "C:\\Windows\\Fonts\\YuGothM.ttc",
+    "C:\\Windows\\Fonts\\meiryo.ttc",
+    "C:\\Windows\\Fonts\\msgothic.ttc",
+    "/System/Library/Fonts/ヒラギノ角ゴシック W3.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+];
+
+/// Adds the first system font found that covers Japanese as a fallback of the
+/// default fonts, which don't. Japanese names show as boxes when none is
+/// found.
+pub fn install_cjk_fallback(ctx: &egui::Context) {
+    let Some(data) = CJK_FONT_PATHS.iter().find_map(|path| std::fs::read(path).ok()) else {
+        eprintln!("No Japanese font found, Japanese names won't display");
+        return;
+    };
+
+    let mut fonts = FontDefinitions::default();
+    fonts.font_data.insert("cjk".to_owned(), Arc::new(FontData::from_owned(data)));
+
+    for family in [FontFamily::Proportional, FontFamily::Monospace] {
+        fonts.families.entry(family).or_default().push("cjk".to_owned());
+    }
+
+    ctx.set_fonts(fonts);
+}
@@ -1,53 +1,150 @@
 use eframe::egui;
 
+mod analysis;
 mod database;
+mod duel;
+mod elements;
+mod fonts;
+mod modifiers;
+mod query;
 mod request;
+mod roster;
+mod team;
 mod utils;
 mod pages;
 
+pub use analysis::{
+    SimilarOptions, Similarity, StatDistribution, StatSummary, dominates, pareto_frontier, similar_characters,
+};
+pub use database::Database;
+pub use duel::{DuelEstimate, DuelFormula, DuelSide, ENCOUNTER_LIST, Encounter, estimate};
+pub use elements::{
+    Affinity, AffinityTable, ElementCoverage, EncounterMatchup, LineCoverage, coverage, coverage_warnings, matchup,
+};
+pub use team::{
+    BENCH_SIZE, FORMATION_LIST, Formation, Lineup, OptimizerConfig, OptimizerResult, StatWeights, TEAM_SIZE, Team,
+    TeamSummary, eligible_characters, optimize, optimize_cached,
+};
+pub use modifiers::{BoostKind, Modifier, ModifierScope, ModifierSet, StatBoost, effective_stats};
+pub use roster::{CsvRow, Ownership, RosterEntry, export_csv, parse_csv};
+pub use query::{BinaryOp, CompareOp, DerivedColumn, Expr, ParseError, Query, TextField, TextOp};
+pub use utils::{Character, Element, Game, Position, Stat, Stats, GAME_LIST, STAT_LIST};
+
 use pages::{
-    CharactersPage, SettingsPage,
+    AnalyticsPage, CharactersPage, ComparePage, DuelPage, ElementsPage, OptimizerPage, ProfileSelector, SchoolsPage, SettingsPage, TeamPage,
 };
 
+const DATABASE_PATH: &str = "character_cache.sqlite";
+
 pub struct InazugleScraper {
+    database: Database,
     active_tab: Tab,
+    profile_selector: ProfileSelector,
 
     characters_page: CharactersPage,
+    compare_page: ComparePage,
+    team_page: TeamPage,
+    optimizer_page: OptimizerPage,
+    analytics_page: AnalyticsPage,
+    schools_page: SchoolsPage,
+    elements_page: ElementsPage,
+    duel_page: DuelPage,
     settings: SettingsPage,
 }
 
 impl InazugleScraper {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         // Customize egui here with cc.egui_ctx.set_fonts and cc.egui_ctx.set_visuals.
         // Restore app state using cc.storage (requires the "persistence" feature).
         // Use the cc.gl (a glow::Context) to create graphics shaders and buffers that you can use
         // for e.g. egui::PaintCallback.
 
+        // Portraits are decoded by the image loaders
+        egui_extras::install_image_loaders(&cc.egui_ctx);
+        // Japanese names need a font the default ones lack
+        fonts::install_cjk_fallback(&cc.egui_ctx);
+
+        let database = Database::connect(DATABASE_PATH);
+
         InazugleScraper { 
             active_tab: Tab::Characters, 
-            characters_page: CharactersPage::new(),
-            settings: SettingsPage::default(),
+            profile_selector: ProfileSelector::new(database.clone()),
+            characters_page: CharactersPage::new(database.clone()),
+            compare_page: ComparePage::new(),
+            team_page: TeamPage::new(database.clone()),
+            optimizer_page: OptimizerPage::new(database.clone()),
+            analytics_page: AnalyticsPage::new(database.clone()),
+            schools_page: SchoolsPage::new(database.clone()),
+            elements_page: ElementsPage::new(database.clone()),
+            duel_page: DuelPage::new(database.clone()),
+            settings: SettingsPage::new(database.clone()),
+            database,
         }
     }
+
+    /// Recreates the pages holding personal data once another profile is
+    /// current. The pinned characters of the Compare page are kept. The
+    /// profile can't be switched while a search or an optimization runs, as
+    /// recreating their pages would drop them.
+    fn reload_profile(&mut self) {
+        self.characters_page = CharactersPage::new(self.database.clone());
+        self.team_page = TeamPage::new(self.database.clone());
+        self.optimizer_page = OptimizerPage::new(self.database.clone());
+        self.analytics_page = AnalyticsPage::new(self.database.clone());
+        self.schools_page = SchoolsPage::new(self.database.clone());
+        self.elements_page = ElementsPage::new(self.database.clone());
+        self.duel_page = DuelPage::new(self.database.clone());
+        self.settings = SettingsPage::new(self.database.clone());
+    }
 }
 
 impl eframe::App for InazugleScraper {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         if self.active_tab == Tab::Characters {
-            self.characters_page.receive_char();
+            self.characters_page.sync_settings(&self.settings);
         }
 
+        // Received on every tab, the profile selector waits for them
+        self.characters_page.receive_char();
+        self.optimizer_page.receive_result();
+
+        let mut profile_switched = false;
+
         egui::TopBottomPanel::top("tabs").show(ctx, |ui|{
             ui.horizontal(|ui| {
+                let busy = self.characters_page.is_busy() || self.optimizer_page.is_busy();
+                let selector = ui.add_enabled_ui(!busy, |ui| self.profile_selector.render(ui));
+                selector.response.on_disabled_hover_text("Wait for the search or the optimization to finish");
+                profile_switched = selector.inner;
+                ui.separator();
+
                 ui.selectable_value(&mut self.active_tab, Tab::Characters, "Characters");
+                ui.selectable_value(&mut self.active_tab, Tab::Compare, "Compare");
+                ui.selectable_value(&mut self.active_tab, Tab::Teams, "Teams");
+                ui.selectable_value(&mut self.active_tab, Tab::Optimizer, "Optimizer");
+                ui.selectable_value(&mut self.active_tab, Tab::Analytics, "Analytics");
+                ui.selectable_value(&mut self.active_tab, Tab::Schools, "Schools");
+                ui.selectable_value(&mut self.active_tab, Tab::Elements, "Elements");
+                ui.selectable_value(&mut self.active_tab, Tab::Duels, "Duels");
                 ui.selectable_value(&mut self.active_tab, Tab::Techniques, "Techniques");
                 ui.selectable_value(&mut self.active_tab, Tab::Settings, "Settings");
             });
         });
 
+        if profile_switched {
+            self.reload_profile();
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             match self.active_tab {
-                Tab::Characters => self.characters_page.render(&self.settings, ui),
+                Tab::Characters => self.characters_page.render(&self.settings, &mut self.compare_page, ui),
+                Tab::Compare => self.compare_page.render(&self.settings, self.characters_page.modifiers(), ui),
+                Tab::Teams => self.team_page.render(self.characters_page.modifiers(), ui),
+                Tab::Optimizer => self.optimizer_page.render(&mut self.team_page, ui),
+                Tab::Analytics => self.analytics_page.render(&self.characters_page, ui),
+                Tab::Schools => self.schools_page.render(&self.settings, ui),
+                Tab::Elements => self.elements_page.render(self.characters_page.modifiers(), ui),
+                Tab::Duels => self.duel_page.render(self.characters_page.modifiers(), self.elements_page.affinity(), ui),
                 Tab::Techniques => (),
                 Tab::Settings => self.settings.render(ui),
             }
@@ -60,6 +157,13 @@ impl eframe::App for InazugleScraper {
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum Tab {
     Characters,
+    Compare,
+    Teams,
+    Optimizer,
+    Analytics,
+    Schools,
+    Elements,
+    Duels,
     Techniques,
     Settings,
 }
\ No newline at end of file
@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+
+use crate::utils::{Character, STAT_LIST, Stat, Stats};
+
+/// How a boost changes a stat.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoostKind {
+    /// Points added to the stat.
+    Flat,
+    /// Percentage of the stat added to it, once the flat boosts are applied.
+    Percent,
+}
+
+impl BoostKind {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            BoostKind::Flat => "points",
+            BoostKind::Percent => "%",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatBoost {
+    pub stat: Stat,
+    pub kind: BoostKind,
+    /// Negative for maluses.
+    pub amount: i16,
+}
+
+/// A piece of equipment or a training that changes some stats of the
+/// characters it is attached to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Modifier {
+    /// `None` until the modifier is saved.
+    pub id: Option<i64>,
+    pub name: String,
+    pub boosts: Vec<StatBoost>,
+}
+
+impl Modifier {
+    pub fn new(name: &str) -> Modifier {
+        Modifier {
+            id: None,
+            name: name.to_string(),
+            boosts: Vec::new(),
+        }
+    }
+
+    /// Serializes the boosts as `Kick:+10,Agility:+5%`.
+    pub fn to_spec(&self) -> String {
+        self.boosts
+            .iter()
+            .map(|boost| {
+                let suffix = if boost.kind == BoostKind::Percent { "%" } else { "" };
+                format!("{}:{:+}{}", boost.stat.to_str(), boost.amount, suffix)
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Parses boosts produced by `to_spec`, ignoring malformed entries.
+    pub fn boosts_from_spec(spec: &str) -> Vec<StatBoost> {
+        spec.split(',')
+            .filter_map(|entry| {
+                let (name, amount) = entry.split_once(':')?;
+                let stat = Stat::from_name(name.trim())?;
+
+                let amount = amount.trim();
+                let (amount, kind) = match amount.strip_suffix('%') {
+                    Some(amount) => (amount, BoostKind::Percent),
+                    None => (amount, BoostKind::Flat),
+                };
+
+                Some(StatBoost { stat, kind, amount: amount.parse().ok()? })
+            })
+            .collect()
+    }
+
+    /// Short description such as `Kick +10, Agility +5 %`.
+    pub fn describe(&self) -> String {
+        if self.boosts.is_empty() {
+            return "No effect".to_string();
+        }
+
+        self.boosts
+            .iter()
+            .map(|boost| match boost.kind {
+                BoostKind::Flat => format!("{} {:+}", boost.stat.to_str(), boost.amount),
+                BoostKind::Percent => format!("{} {:+} %", boost.stat.to_str(), boost.amount),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Stats once the modifiers are applied. The flat boosts of every modifier
+/// are added first, then the summed percentages are applied to the result,
+/// which is rounded and kept within the range of a stat.
+pub fn effective_stats<'a>(base: &Stats, modifiers: impl IntoIterator<Item = &'a Modifier>) -> Stats {
+    let mut flat = [0.0; 7];
+    let mut percent = [0.0; 7];
+
+    for boost in modifiers.into_iter().flat_map(|modifier| &modifier.boosts) {
+        match boost.kind {
+            BoostKind::Flat => flat[boost.stat.index()] += boost.amount as f64,
+            BoostKind::Percent => percent[boost.stat.index()] += boost.amount as f64,
+        }
+    }
+
+    let mut stats = base.clone();
+    for stat in STAT_LIST {
+        let index = stat.index();
+        let value = (base.get(stat) as f64 + flat[index]) * (1.0 + percent[index] / 100.0);
+        stats.set(stat, value.round().clamp(u8::MIN as f64, u8::MAX as f64) as u8);
+    }
+    stats
+}
+
+/// Where a modifier is attached to a character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModifierScope {
+    /// The character as recruited, wherever it is shown.
+    Roster,
+    /// The character while it plays in a saved team, by id. Added to the
+    /// modifiers of the roster.
+    Team(i64),
+}
+
+impl ModifierScope {
+    /// Value stored in the database, `roster` or `team:<id>`.
+    pub fn to_key(self) -> String {
+        match self {
+            ModifierScope::Roster => "roster".to_string(),
+            ModifierScope::Team(id) => format!("team:{id}"),
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<ModifierScope> {
+        match key.split_once(':') {
+            None if key == "roster" => Some(ModifierScope::Roster),
+            Some(("team", id)) => id.parse().ok().map(ModifierScope::Team),
+            _ => None,
+        }
+    }
+
+    /// Scope of the characters of a team, the roster for a team that isn't
+    /// saved.
+    pub fn of_team(team_id: Option<i64>) -> ModifierScope {
+        team_id.map_or(ModifierScope::Roster, ModifierScope::Team)
+    }
+}
+
+/// The modifiers of a profile and the characters they are attached to.
+#[derive(Debug, Clone, Default)]
+pub struct ModifierSet {
+    pub modifiers: Vec<Modifier>,
+    /// Scope and id of the modifiers attached to each character, by number.
+    pub attached: HashMap<u16, Vec<(ModifierScope, i64)>>,
+}
+
+impl ModifierSet {
+    pub fn is_attached(&self, number: u16, scope: ModifierScope, modifier_id: i64) -> bool {
+        self.attached.get(&number).is_some_and(|links| links.contains(&(scope, modifier_id)))
+    }
+
+    /// Number of characters a modifier is attached to, in any scope.
+    pub fn attached_count(&self, modifier_id: i64) -> usize {
+        self.attached
+            .values()
+            .filter(|links| links.iter().any(|(_, id)| *id == modifier_id))
+            .count()
+    }
+
+    /// Modifiers attached to a character in the roster.
+    pub fn of(&self, number: u16) -> Vec<&Modifier> {
+        self.of_in(number, ModifierScope::Roster)
+    }
+
+    /// Modifiers applying to a character in a scope: those of the roster and
+    /// those of the scope itself.
+    pub fn of_in(&self, number: u16, scope: ModifierScope) -> Vec<&Modifier> {
+        self.modifiers
+            .iter()
+            .filter(|modifier| {
+                modifier.id.is_some_and(|id| {
+                    self.is_attached(number, ModifierScope::Roster, id) || self.is_attached(number, scope, id)
+                })
+            })
+            .collect()
+    }
+
+    /// Effective stats of a character in the roster, `None` when it has no
+    /// stats or no modifier attached.
+    pub fn effective(&self, character: &Character) -> Option<Stats> {
+        self.effective_in(character, ModifierScope::Roster)
+    }
+
+    /// Effective stats of a character in a scope, `None` when it has no stats
+    /// or no modifier applies.
+    pub fn effective_in(&self, character: &Character, scope: ModifierScope) -> Option<Stats> {
+        let modifiers = self.of_in(character.number, scope);
+        if modifiers.is_empty() {
+            return None;
+        }
+
+        character.stats.as_ref().map(|stats| effective_stats(stats, modifiers))
+    }
+
+    /// The character with its effective stats in the roster in place of the
+    /// base ones.
+    pub fn apply(&self, character: &Character) -> Character {
+        self.apply_in(character, ModifierScope::Roster)
+    }
+
+    /// The character with its effective stats in a scope in place of the base
+    /// ones.
+    pub fn apply_in(&self, character: &Character, scope: ModifierScope) -> Character {
+        let mut character = character.clone();
+        if let Some(stats) = self.effective_in(&character, scope) {
+            character.stats = Some(stats);
+        }
+        character
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(value: u8) -> Stats {
+        Stats {
+            kick: value,
+            control: value,
+            technique: value,
+            pressure: value,
+            physical: value,
+            agility: value,
+            intelligence: value,
+        }
+    }
+
+    fn modifier(id: i64, spec: &str) -> Modifier {
+        Modifier {
+            id: Some(id),
+            name: format!("modifier {id}"),
+            boosts: Modifier::boosts_from_spec(spec),
+        }
+    }
+
+    #[test]
+    fn flat_boosts_come_before_percentages_and_are_clamped() {
+        let boots = modifier(1, "Kick:+10,Agility:+50%");
+        let training = modifier(2, "Kick:+10%,Agility:-30,Control:-200,Technique:+200");
+
+        let effective = effective_stats(&stats(100), [&boots, &training]);
+
+        // (100 + 10) * 1.1 and (100 - 30) * 1.5
+        assert_eq!(effective.kick, 121);
+        assert_eq!(effective.agility, 105);
+        assert_eq!(effective.control, 0);
+        assert_eq!(effective.technique, 255);
+        assert_eq!(effective.physical, 100);
+    }
+
+    #[test]
+    fn boosts_round_trip_through_their_spec() {
+        let boots = modifier(1, "Kick:+10,Agility:-5%");
+        assert_eq!(boots.to_spec(), "Kick:+10,Agility:-5%");
+        assert_eq!(Modifier::boosts_from_spec(&boots.to_spec()), boots.boosts);
+
+        assert_eq!(Modifier::boosts_from_spec("Kick:+10,Speed:+5,Control:lots"), modifier(1, "Kick:+10").boosts);
+    }
+
+    #[test]
+    fn team_modifiers_add_to_those_of_the_roster() {
+        for scope in [ModifierScope::Roster, ModifierScope::Team(7)] {
+            assert_eq!(ModifierScope::from_key(&scope.to_key()), Some(scope));
+        }
+        assert_eq!(ModifierScope::from_key("team:"), None);
+
+        let set = ModifierSet {
+            modifiers: vec![modifier(1, "Kick:+10"), modifier(2, "Kick:+5"), modifier(3, "Kick:+1")],
+            attached: HashMap::from([(
+                4,
+                vec![(ModifierScope::Roster, 1), (ModifierScope::Team(7), 2), (ModifierScope::Team(8), 3)],
+            )]),
+        };
+
+        let ids = |modifiers: Vec<&Modifier>| modifiers.iter().filter_map(|modifier| modifier.id).collect::<Vec<_>>();
+        assert_eq!(ids(set.of(4)), [1]);
+        assert_eq!(ids(set.of_in(4, ModifierScope::Team(7))), [1, 2]);
+        assert_eq!(ids(set.of_in(5, ModifierScope::Team(7))), Vec::<i64>::new());
+        assert_eq!(set.attached_count(3), 1);
+    }
+}
@@ -1,90 +1,949 @@
 use tokio::{runtime::Runtime, sync::mpsc};
 
-use crate::{database::Database, request::Request, utils::{Character, Progress}};
+use std::{cmp::Ordering, collections::HashMap};
 
+use crate::{analysis::StatDistribution, duel::{DEFAULT_SCALE, DuelFormula, ENCOUNTER_LIST, Encounter}, elements::AffinityTable, database::{Annotation, CharacterDetails, Database, Profile, SavedSearch, SearchHistoryEntry, SearchSource}, modifiers::ModifierSet, query::{DerivedColumn, ParseError, Query}, request::{Request, refresh_character}, roster::{Ownership, RosterEntry}, team::{OptimizerConfig, OptimizerResult, Team, eligible_characters, optimize}, utils::{Character, Position, Progress, Stat}};
+
+mod character_panel;
+mod analytics_page;
+mod annotations_view;
 mod characters_page;
+mod compare_page;
+mod duel_page;
+mod elements_page;
+mod modifiers_view;
+mod my_team_view;
+mod optimizer_page;
+mod pareto_view;
+mod portraits;
+mod profile_selector;
+mod roster_view;
+mod schools_page;
 mod settings_page;
+mod similar_view;
+mod team_page;
+mod versions_view;
 
-use characters_page::SortColumn;
+use analytics_page::{AnalyticsSource, PercentileLookup};
+use characters_page::{GroupBy, SortKey, TableColumn};
+use elements_page::CoverageSource;
+use modifiers_view::ModifierEditor;
+use pareto_view::{ParetoMode, ParetoView};
+use portraits::PortraitCache;
+use similar_view::SimilarView;
+use versions_view::VersionsView;
 
 pub struct CharactersPage {
     runtime: Runtime,
     character_cache: Database,
     request: Request,
 
+    query_text: String,
+    query: Query,
+    /// Text of `query`, which differs from `query_text` while it doesn't parse.
+    applied_query_text: String,
+    query_error: Option<ParseError>,
+
+    derived_columns: Vec<DerivedColumn>,
+    japanese_names: bool,
+    settings_revision: u64,
+
     characters: Vec<Character>,
+    /// Distribution of the stats of `characters`, for the percentile columns.
+    distribution: StatDistribution,
+    portraits: PortraitCache,
     sender: mpsc::UnboundedSender<Vec<Character>>,
     receiver: mpsc::UnboundedReceiver<Vec<Character>>,
+    /// Signals that the portraits of the last results were downloaded.
+    portrait_sender: mpsc::UnboundedSender<()>,
+    portrait_receiver: mpsc::UnboundedReceiver<()>,
     progress: Option<Progress>,
+    /// A search of the website is running, until its portraits are downloaded.
+    searching: bool,
+
+    /// Character shown in the detail panel, by number.
+    selected: Option<u16>,
+    details: CharacterDetails,
+    refresh_sender: mpsc::UnboundedSender<Character>,
+    refresh_receiver: mpsc::UnboundedReceiver<Character>,
+    refreshing: bool,
+
+    /// Sort keys by priority, later keys breaking ties of earlier ones.
+    sort: Vec<SortKey>,
+
+    /// Columns of the table in display order, with their visibility.
+    columns: Vec<(TableColumn, bool)>,
+    group_by: Option<GroupBy>,
+
+    /// Schools of the cached characters, for the school filter.
+    schools: Vec<String>,
+    /// Only characters of this school are shown when set.
+    school_filter: Option<String>,
+
+    /// Numbers of the characters in My Team, kept locally.
+    my_team: Vec<u16>,
+    my_team_only: bool,
+    my_team_import: String,
+    /// Outcome of the last export.
+    my_team_message: Option<String>,
 
-    sort_column: SortColumn,
-    sort_ascending: bool,
+    /// Characters recruited in the current profile.
+    roster: Vec<RosterEntry>,
+    ownership: Ownership,
+    roster_import: String,
+    roster_message: Option<String>,
+
+    /// Favorites, tags and notes of the current profile, by character number.
+    annotations: HashMap<u16, Annotation>,
+    favorites_only: bool,
+    tag_filter: Option<String>,
+    new_tag: String,
+    notes_preview: bool,
+
+    /// Equipment and training of the current profile, and the characters
+    /// they are attached to.
+    modifiers: ModifierSet,
+    modifier_editor: Option<ModifierEditor>,
+
+    pareto: ParetoView,
+    similar: Option<SimilarView>,
+    versions: Option<VersionsView>,
+
+    saved_searches: Vec<SavedSearch>,
+    search_history: Vec<SearchHistoryEntry>,
+    saved_search_name: String,
 }
 
 impl CharactersPage {
-    pub fn new() -> CharactersPage {
+    pub fn new(character_cache: Database) -> CharactersPage {
         let runtime = tokio::runtime::Runtime::new().unwrap();
-        let character_cache = Database::connect("character_cache.sqlite");
 
         let (sender, receiver) = mpsc::unbounded_channel();
+        let (refresh_sender, refresh_receiver) = mpsc::unbounded_channel();
+        let (portrait_sender, portrait_receiver) = mpsc::unbounded_channel();
 
         CharactersPage {
             runtime,
-            character_cache,
             request: Request::new(),
 
+            query_text: String::new(),
+            query: Query::All,
+            applied_query_text: String::new(),
+            query_error: None,
+
+            derived_columns: Vec::new(),
+            japanese_names: false,
+            settings_revision: 0,
+
             characters: Vec::new(),
+            distribution: StatDistribution::default(),
+            portraits: PortraitCache::new(),
             sender,
             receiver,
+            portrait_sender,
+            portrait_receiver,
             progress: None,
+            searching: false,
+
+            selected: None,
+            details: CharacterDetails::default(),
+            refresh_sender,
+            refresh_receiver,
+            refreshing: false,
 
-            sort_column: SortColumn::ID,
-            sort_ascending: true,
+            sort: vec![SortKey { column: TableColumn::ID, ascending: true }],
+
+            columns: load_column_layout(&character_cache),
+            group_by: None,
+
+            schools: character_cache.schools(),
+            school_filter: None,
+
+            my_team: character_cache.my_team(),
+            my_team_only: false,
+            my_team_import: String::new(),
+            my_team_message: None,
+
+            roster: character_cache.roster(),
+            ownership: Ownership::Any,
+            roster_import: String::new(),
+            roster_message: None,
+
+            annotations: character_cache.annotations(),
+            favorites_only: false,
+            tag_filter: None,
+            new_tag: String::new(),
+            notes_preview: false,
+
+            modifiers: character_cache.modifier_set(),
+            modifier_editor: None,
+
+            pareto: ParetoView::new(),
+            similar: None,
+            versions: None,
+
+            saved_searches: character_cache.saved_searches(),
+            search_history: character_cache.search_history(),
+            saved_search_name: String::new(),
+
+            character_cache,
         }
     }
 
+    /// Picks up the derived columns and the name language from the settings
+    /// when they changed, as the query and the sort may depend on them.
+    pub fn sync_settings(&mut self, settings: &SettingsPage) {
+        if self.settings_revision == settings.revision {
+            return;
+        }
+
+        self.settings_revision = settings.revision;
+        self.derived_columns = settings.derived_columns.clone();
+        self.japanese_names = settings.japanese_names;
+        self.reconcile_columns();
+        self.set_query(self.query_text.clone());
+        self.sort_characters();
+    }
+
     pub fn receive_char(&mut self) {
         if let Ok(characters) = self.receiver.try_recv() {
             // We update the stored characters
             self.characters = characters;
-            self.sort_characters();
+            self.characters_changed();
+            self.search_history = self.character_cache.search_history();
+            self.reload_details();
+        }
+
+        if self.portrait_receiver.try_recv().is_ok() {
+            self.searching = false;
+            self.portraits.clear();
+        }
+
+        if let Ok(character) = self.refresh_receiver.try_recv() {
+            self.refreshing = false;
+
+            if let Some(existing) = self.characters.iter_mut().find(|other| other.number == character.number) {
+                *existing = character;
+            }
+
+            self.characters_changed();
+            self.reload_details();
         }
     }
 
+    /// Whether background work would be lost by recreating the page.
+    pub fn is_busy(&self) -> bool {
+        self.searching || self.refreshing
+    }
+
+    fn select_character(&mut self, number: Option<u16>) {
+        self.selected = number;
+        self.reload_details();
+    }
+
+    fn reload_details(&mut self) {
+        self.details = match self.selected {
+            Some(number) => self.character_cache.character_details(number),
+            None => CharacterDetails::default(),
+        };
+    }
+
+    fn selected_character(&self) -> Option<&Character> {
+        let number = self.selected?;
+        self.characters.iter().find(|character| character.number == number)
+    }
+
+    /// Fetches the selected character again from the website in the background.
+    /// The result is picked up by `receive_char`.
+    fn refresh_selected(&mut self) {
+        let Some(character) = self.selected_character().cloned() else {
+            return;
+        };
+
+        let mut db = self.character_cache.clone();
+        let sender = self.refresh_sender.clone();
+        self.refreshing = true;
+
+        self.runtime.spawn(async move {
+            let character = refresh_character(&mut db, character).await;
+            let _ = sender.send(character);
+        });
+    }
+
+    /// Sends the current request to the website in the background. The results
+    /// are picked up by `receive_char`.
+    fn send_request(&mut self, max_parallelism: usize) {
+        let request = self.request.clone();
+        let query = self.query.clone();
+        let query_text = self.applied_query_text.clone();
+        let mut db = self.character_cache.clone();
+        let sender = self.sender.clone();
+        let portrait_sender = self.portrait_sender.clone();
+
+        let progress = Progress::new();
+        self.progress = Some(progress.clone());
+        self.searching = true;
+
+        self.runtime.spawn(async move {
+            let characters = request.send(&mut db, max_parallelism, progress).await;
+
+            let characters: Vec<Character> = characters
+                .into_iter()
+                .filter(|char| char.stats.is_some())
+                .collect();
+
+            let shown = characters
+                .iter()
+                .filter(|char| request.matches_stats(char) && query.matches(char))
+                .count();
+            db.record_search(&request, &query_text, SearchSource::Online, shown);
+            let _ = sender.send(characters.clone());
+
+            // The results show without portraits until they are downloaded
+            request.fetch_portraits(&mut db, &characters, max_parallelism).await;
+            let _ = portrait_sender.send(());
+        });
+    }
+
+    /// Runs the current request against the local cache only.
+    fn search_cache(&mut self) {
+        self.characters = self.character_cache.search_characters(&self.request, &self.query);
+        self.progress = None;
+        self.characters_changed();
+
+        self.character_cache.record_search(&self.request, &self.applied_query_text, SearchSource::Cache, self.characters.len());
+        self.search_history = self.character_cache.search_history();
+    }
+
+    fn run_search(&mut self, request: Request, source: SearchSource, max_parallelism: usize) {
+        self.request = request;
+
+        match source {
+            SearchSource::Online => self.send_request(max_parallelism),
+            SearchSource::Cache => self.search_cache(),
+        }
+    }
+
+    fn save_current_search(&mut self) {
+        // A query that doesn't parse would be saved without being applied
+        let name = self.saved_search_name.trim();
+        if name.is_empty() || self.query_error.is_some() {
+            return;
+        }
+
+        self.character_cache.save_search(name, &self.request, &self.query_text, &self.sort_spec());
+        self.saved_searches = self.character_cache.saved_searches();
+        self.saved_search_name.clear();
+    }
+
+    fn load_saved_search(&mut self, search: &SavedSearch, source: SearchSource, max_parallelism: usize) {
+        self.apply_sort_spec(&search.sort);
+        self.set_query(search.query.clone());
+        self.run_search(search.request.clone(), source, max_parallelism);
+    }
+
+    /// Updates what is computed from the characters after they were replaced
+    /// or one of them changed.
+    fn characters_changed(&mut self) {
+        self.distribution = StatDistribution::new(&self.characters);
+        self.portraits.clear();
+        self.pareto.invalidate();
+        self.schools = self.character_cache.schools();
+        self.sort_characters();
+    }
+
+    /// Characters shown in the table, in table order.
+    pub fn shown_characters(&self) -> Vec<&Character> {
+        self.characters
+            .iter()
+            .filter(|character| self.is_shown(character))
+            .collect()
+    }
+
+    /// Whether a character passes the client-side filters: the stat filters of
+    /// the request, the query, and the school, My Team, roster, favorite and
+    /// tag filters.
+    fn matches_filters(&self, character: &Character) -> bool {
+        self.request.matches_stats(character)
+            && self.query.matches(character)
+            && self.school_filter.as_ref().is_none_or(|school| character.school == *school)
+            && (!self.my_team_only || self.is_in_my_team(character.number))
+            && self.ownership.matches(self.roster_entry(character.number).is_some())
+            && self.matches_annotations(character.number)
+    }
+
+    /// Whether a character is shown in the table, which also hides dominated
+    /// characters when filtering on the Pareto frontier.
+    fn is_shown(&self, character: &Character) -> bool {
+        self.matches_filters(character)
+            && !(self.pareto.mode == ParetoMode::Filter && self.pareto.is_dominated(character.number))
+    }
+
+    /// Replaces the query text, keeping the previous query active when the
+    /// new one doesn't parse.
+    fn set_query(&mut self, text: String) {
+        self.query_text = text;
+
+        match Query::parse_with(&self.query_text, &self.derived_columns) {
+            Ok(query) => {
+                self.query = query;
+                self.applied_query_text = self.query_text.clone();
+                self.query_error = None;
+            }
+            Err(error) => self.query_error = Some(error),
+        }
+    }
+
+    fn delete_saved_search(&mut self, id: i64) {
+        self.character_cache.delete_saved_search(id);
+        self.saved_searches = self.character_cache.saved_searches();
+    }
+
+    fn clear_search_history(&mut self) {
+        self.character_cache.clear_search_history();
+        self.search_history.clear();
+    }
+
+    /// Makes the column layout match the available columns: derived columns
+    /// that were removed are dropped and new ones are appended as visible.
+    fn reconcile_columns(&mut self) {
+        let available = TableColumn::default_layout(&self.derived_columns);
+
+        self.columns.retain(|(column, _)| available.iter().any(|(other, _)| other == column));
+
+        for (column, visible) in available {
+            if !self.columns.iter().any(|(other, _)| *other == column) {
+                let index = match column {
+                    TableColumn::Portrait | TableColumn::MyTeam | TableColumn::Owned | TableColumn::Favorite => 0,
+                    TableColumn::Tags | TableColumn::Notes => self.columns
+                        .iter()
+                        .position(|(other, _)| *other == TableColumn::Link)
+                        .unwrap_or(self.columns.len()),
+                    TableColumn::School => self.columns
+                        .iter()
+                        .position(|(other, _)| *other == TableColumn::Position)
+                        .map_or(self.columns.len(), |index| index + 1),
+                    // Keep the link last when adding new columns
+                    TableColumn::Derived(_) | TableColumn::Percentile(_) => self.columns
+                        .iter()
+                        .position(|(other, _)| *other == TableColumn::Link)
+                        .unwrap_or(self.columns.len()),
+                    _ => self.columns.len(),
+                };
+
+                self.columns.insert(index, (column, visible));
+            }
+        }
+    }
+
+    /// Stores the column layout as `ID,Name,-Nickname,...`, hidden columns
+    /// being prefixed with `-`.
+    fn store_column_layout(&self) {
+        let spec = self.columns
+            .iter()
+            .map(|(column, visible)| format!("{}{}", if *visible { "" } else { "-" }, column.to_str()))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        self.character_cache.set_setting("table_columns", &spec);
+    }
+
+    /// Serializes the sort stack as `Column:asc,Column:desc`.
+    fn sort_spec(&self) -> String {
+        self.sort
+            .iter()
+            .map(|key| format!("{}:{}", key.column.to_str(), if key.ascending { "asc" } else { "desc" }))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    fn apply_sort_spec(&mut self, spec: &str) {
+        let sort: Vec<SortKey> = spec
+            .split(',')
+            .filter(|key| !key.is_empty())
+            .map(|key| {
+                let (column, direction) = key.split_once(':').unwrap_or((key, "asc"));
+                SortKey {
+                    column: TableColumn::from_name(column),
+                    ascending: direction != "desc",
+                }
+            })
+            .collect();
+
+        if !sort.is_empty() {
+            self.sort = sort;
+        }
+    }
+
+    /// Sorts the characters by every key of the sort stack in turn. The sort
+    /// is stable and ties left by all keys are broken by ID.
     fn sort_characters(&mut self) {
-        let column = self.sort_column;
+        let sort = &self.sort;
+        let derived = &self.derived_columns;
+        let japanese = self.japanese_names;
 
         self.characters.sort_by(|a, b| {
-            let a_stats = a.stats.as_ref().unwrap();
-            let b_stats = b.stats.as_ref().unwrap();
-
-            match column {
-                SortColumn::ID => a.number.cmp(&b.number),
-                SortColumn::Name => a.name.cmp(&b.name),
-                SortColumn::Kick => a_stats.kick.cmp(&b_stats.kick),
-                SortColumn::Control => a_stats.control.cmp(&b_stats.control),
-                SortColumn::Technique => a_stats.technique.cmp(&b_stats.technique),
-                SortColumn::Pressure => a_stats.pressure.cmp(&b_stats.pressure),
-                SortColumn::Physical => a_stats.physical.cmp(&b_stats.physical),
-                SortColumn::Agility => a_stats.agility.cmp(&b_stats.agility),
-                SortColumn::Intelligence => a_stats.intelligence.cmp(&b_stats.intelligence),
+            sort.iter()
+                .map(|key| {
+                    let ordering = compare_characters(a, b, &key.column, derived, japanese);
+                    if key.ascending { ordering } else { ordering.reverse() }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| a.number.cmp(&b.number))
+        });
+    }
+}
+
+fn load_column_layout(database: &Database) -> Vec<(TableColumn, bool)> {
+    match database.setting("table_columns") {
+        Some(spec) => spec
+            .split(',')
+            .filter(|name| !name.is_empty())
+            .map(|name| match name.strip_prefix('-') {
+                Some(name) => (TableColumn::from_name(name), false),
+                None => (TableColumn::from_name(name), true),
+            })
+            .collect(),
+        None => TableColumn::default_layout(&[]),
+    }
+}
+
+fn compare_characters(a: &Character, b: &Character, column: &TableColumn, derived: &[DerivedColumn], japanese: bool) -> Ordering {
+    match column {
+        TableColumn::ID => a.number.cmp(&b.number),
+        TableColumn::Name => a.display_name(japanese).cmp(b.display_name(japanese)),
+        TableColumn::Nickname => a.display_nickname(japanese).cmp(b.display_nickname(japanese)),
+        TableColumn::Element => (a.element as u8).cmp(&(b.element as u8)),
+        TableColumn::Position => (a.position as u8).cmp(&(b.position as u8)),
+        TableColumn::School => a.school.cmp(&b.school),
+        TableColumn::Portrait | TableColumn::MyTeam | TableColumn::Owned | TableColumn::Favorite | TableColumn::Tags | TableColumn::Notes | TableColumn::Link => Ordering::Equal,
+        // Percentile ranks follow the order of the stats
+        TableColumn::Stat(stat) | TableColumn::Percentile(stat) => {
+            let a_stat = a.stats.as_ref().map(|stats| stats.get(*stat));
+            let b_stat = b.stats.as_ref().map(|stats| stats.get(*stat));
+            a_stat.cmp(&b_stat)
+        }
+        TableColumn::Derived(name) => {
+            let column = derived.iter().find(|column| &column.name == name);
+            let a_value = column.and_then(|column| column.eval(a));
+            let b_value = column.and_then(|column| column.eval(b));
+
+            match (a_value, b_value) {
+                (Some(a_value), Some(b_value)) => a_value.total_cmp(&b_value),
+                (a_value, b_value) => a_value.is_some().cmp(&b_value.is_some()),
             }
+        }
+    }
+}
+
+/// Maximum number of characters that can be compared at once.
+pub const MAX_PINNED: usize = 6;
+
+pub struct ComparePage {
+    /// Characters pinned from the Characters page, in pinning order.
+    pinned: Vec<Character>,
+    /// Compare the stats once the modifiers are applied.
+    effective: bool,
+}
+
+impl ComparePage {
+    pub fn new() -> ComparePage {
+        ComparePage {
+            pinned: Vec::new(),
+            effective: true,
+        }
+    }
+
+    pub fn is_pinned(&self, number: u16) -> bool {
+        self.pinned.iter().any(|character| character.number == number)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.pinned.len() >= MAX_PINNED
+    }
+
+    /// Pins the character, or unpins it if it already is. Nothing happens
+    /// when pinning while the comparison is full.
+    pub fn toggle_pin(&mut self, character: &Character) {
+        if self.is_pinned(character.number) {
+            self.unpin(character.number);
+        } else if !self.is_full() {
+            self.pinned.push(character.clone());
+        }
+    }
+
+    pub fn unpin(&mut self, number: u16) {
+        self.pinned.retain(|character| character.number != number);
+    }
+}
+
+pub struct TeamPage {
+    database: Database,
+
+    team: Team,
+    /// Every cached character, the pool the team is built from.
+    characters: Vec<Character>,
+    filter: String,
+    /// Characters recruited in the current profile.
+    owned: Vec<u16>,
+    ownership: Ownership,
+
+    saved_teams: Vec<Team>,
+    import_text: String,
+    message: Option<String>,
+}
+
+impl TeamPage {
+    pub fn new(database: Database) -> TeamPage {
+        let characters = database.query_characters(&Query::All);
+        let saved_teams = database.teams();
+
+        let owned = database.owned_characters();
+
+        TeamPage {
+            database,
+
+            team: Team::new("New team"),
+            characters,
+            filter: String::new(),
+            owned,
+            ownership: Ownership::Any,
+
+            saved_teams,
+            import_text: String::new(),
+            message: None,
+        }
+    }
+
+    pub fn reload_characters(&mut self) {
+        self.characters = self.database.query_characters(&Query::All);
+        self.owned = self.database.owned_characters();
+    }
+
+    /// Replaces the team being built, e.g. by a lineup of the optimizer.
+    pub fn open_team(&mut self, team: Team) {
+        self.reload_characters();
+        self.message = Some(format!("Opened \"{}\"", team.name));
+        self.team = team;
+    }
+
+    fn save_team(&mut self) {
+        let Some(id) = self.database.save_team(&self.team) else {
+            self.message = Some(format!("Not saved: another team is named \"{}\", or this team was deleted", self.team.name));
+            return;
+        };
+
+        self.team.id = Some(id);
+        self.saved_teams = self.database.teams();
+        self.message = Some(format!("Saved \"{}\"", self.team.name));
+    }
+
+    fn delete_team(&mut self, id: i64) {
+        self.database.delete_team(id);
+        self.saved_teams = self.database.teams();
+
+        if self.team.id == Some(id) {
+            self.team.id = None;
+        }
+    }
+
+    fn import_team(&mut self) {
+        match Team::import(&self.import_text) {
+            Ok(team) => {
+                let missing = team.members()
+                    .into_iter()
+                    .filter(|number| crate::team::find(&self.characters, *number).is_none())
+                    .count();
+
+                self.message = Some(match missing {
+                    0 => format!("Imported \"{}\"", team.name),
+                    _ => format!("Imported \"{}\", {missing} players aren't in the cache", team.name),
+                });
+                self.team = team;
+                self.import_text.clear();
+            }
+            Err(error) => self.message = Some(format!("Import failed: {error}")),
+        }
+    }
+}
+
+pub struct OptimizerPage {
+    database: Database,
+
+    config: OptimizerConfig,
+    /// Cached characters, used to name the picked players and find the ones
+    /// to ban.
+    characters: Vec<Character>,
+    ban_filter: String,
+    /// Restricts the candidates to the roster of the current profile.
+    ownership: Ownership,
+
+    result: Option<OptimizerResult>,
+    sender: mpsc::UnboundedSender<OptimizerResult>,
+    receiver: mpsc::UnboundedReceiver<OptimizerResult>,
+    running: bool,
+}
+
+impl OptimizerPage {
+    pub fn new(database: Database) -> OptimizerPage {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let characters = database.query_characters(&Query::All);
+
+        OptimizerPage {
+            database,
+
+            config: OptimizerConfig::default(),
+            characters,
+            ban_filter: String::new(),
+            ownership: Ownership::Any,
+
+            result: None,
+            sender,
+            receiver,
+            running: false,
+        }
+    }
+
+    /// Runs the optimizer on a separate thread, the result being picked up by
+    /// `receive_result`.
+    fn run(&mut self) {
+        self.characters = self.database.query_characters(&Query::All);
+
+        let owned = self.database.owned_characters();
+        let mut characters = eligible_characters(&self.database, &self.config.games);
+        characters.retain(|character| self.ownership.matches(owned.contains(&character.number)));
+
+        let config = self.config.clone();
+        let sender = self.sender.clone();
+
+        self.running = true;
+        std::thread::spawn(move || {
+            let _ = sender.send(optimize(&characters, &config));
+        });
+    }
+
+    pub fn receive_result(&mut self) {
+        while let Ok(result) = self.receiver.try_recv() {
+            self.result = Some(result);
+            self.running = false;
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.running
+    }
+}
+
+pub struct AnalyticsPage {
+    database: Database,
+
+    source: AnalyticsSource,
+    /// Every cached character, loaded the first time the cache is analyzed.
+    cache: Option<Vec<Character>>,
+
+    breakdown: GroupBy,
+    lookup: PercentileLookup,
+}
+
+impl AnalyticsPage {
+    pub fn new(database: Database) -> AnalyticsPage {
+        AnalyticsPage {
+            database,
+
+            source: AnalyticsSource::Results,
+            cache: None,
+
+            breakdown: GroupBy::Position,
+            lookup: PercentileLookup {
+                stat: Stat::Kick,
+                value: 85,
+                position: Position::FW,
+            },
+        }
+    }
+
+    fn reload_cache(&mut self) {
+        self.cache = Some(self.database.query_characters(&Query::All));
+    }
+}
+
+pub struct SchoolsPage {
+    database: Database,
+
+    /// Every cached character, loaded the first time the page is shown.
+    characters: Option<Vec<Character>>,
+    filter: String,
+    /// Stat whose mean orders the schools, the total when `None`.
+    sort: Option<Stat>,
+    /// Schools whose rosters are shown, in the order they were picked.
+    rosters: Vec<String>,
+}
+
+impl SchoolsPage {
+    pub fn new(database: Database) -> SchoolsPage {
+        SchoolsPage {
+            database,
+
+            characters: None,
+            filter: String::new(),
+            sort: None,
+            rosters: Vec::new(),
+        }
+    }
+}
+
+pub struct ProfileSelector {
+    database: Database,
+
+    profiles: Vec<Profile>,
+    /// Name typed for a new profile or a renamed one.
+    name: String,
+    /// The current profile is being deleted, waiting for confirmation.
+    confirm_delete: bool,
+}
+
+impl ProfileSelector {
+    pub fn new(database: Database) -> ProfileSelector {
+        ProfileSelector {
+            profiles: database.profiles(),
+            database,
+
+            name: String::new(),
+            confirm_delete: false,
+        }
+    }
+}
+
+pub struct ElementsPage {
+    database: Database,
+
+    affinity: AffinityTable,
+    /// Every cached character, loaded the first time the page is shown.
+    characters: Option<Vec<Character>>,
+
+    attacker: Option<u16>,
+    attacker_filter: String,
+    defender: Option<u16>,
+    defender_filter: String,
+
+    coverage_source: CoverageSource,
+    teams: Vec<Team>,
+    my_team: Vec<u16>,
+}
+
+impl ElementsPage {
+    pub fn new(database: Database) -> ElementsPage {
+        let affinity = database
+            .setting("element_affinity")
+            .and_then(|spec| AffinityTable::from_spec(&spec))
+            .unwrap_or_default();
+
+        ElementsPage {
+            affinity,
+            characters: None,
+
+            attacker: None,
+            attacker_filter: String::new(),
+            defender: None,
+            defender_filter: String::new(),
+
+            coverage_source: CoverageSource::MyTeam,
+            teams: database.teams(),
+            my_team: database.my_team(),
+
+            database,
+        }
+    }
+}
+
+pub struct DuelPage {
+    database: Database,
+
+    /// Every cached character, loaded the first time the page is shown.
+    characters: Option<Vec<Character>>,
+    encounter: Encounter,
+
+    attacker: Option<u16>,
+    attacker_filter: String,
+    attacker_power: f64,
+    defender: Option<u16>,
+    defender_filter: String,
+    defender_power: f64,
+    use_elements: bool,
+
+    /// Formulas of each encounter, in `ENCOUNTER_LIST` order.
+    formulas: [DuelFormula; 3],
+    /// Margin at which the attacker wins 73 % of the time.
+    scale: f64,
+}
+
+impl DuelPage {
+    pub fn new(database: Database) -> DuelPage {
+        let formulas = ENCOUNTER_LIST.map(|encounter| {
+            database
+                .setting(encounter.setting_key())
+                .and_then(|spec| DuelFormula::from_spec(&spec))
+                .unwrap_or_else(|| encounter.default_formula())
         });
 
-        if !self.sort_ascending {
-            self.characters.reverse();
+        let scale = database
+            .setting("duel_scale")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_SCALE);
+
+        DuelPage {
+            database,
+
+            characters: None,
+            encounter: Encounter::Shot,
+
+            attacker: None,
+            attacker_filter: String::new(),
+            attacker_power: 0.0,
+            defender: None,
+            defender_filter: String::new(),
+            defender_power: 0.0,
+            use_elements: true,
+
+            formulas,
+            scale,
         }
     }
 }
 
 pub struct SettingsPage {
+    database: Database,
+
     max_parallelism: usize,
+    derived_columns: Vec<DerivedColumn>,
+    /// Show the Japanese names of the characters instead of the English ones.
+    japanese_names: bool,
+
+    /// Bumped every time the derived columns or the name language change.
+    revision: u64,
 }
 
 impl SettingsPage {
-    pub fn default() -> SettingsPage {
-        SettingsPage { 
-            max_parallelism: 20
+    pub fn new(database: Database) -> SettingsPage {
+        let max_parallelism = database
+            .setting("max_parallelism")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(20);
+
+        let derived_columns = database
+            .derived_columns()
+            .unwrap_or_else(DerivedColumn::defaults);
+
+        let japanese_names = database.setting("name_language").as_deref() == Some("ja");
+
+        SettingsPage {
+            database,
+
+            max_parallelism,
+            derived_columns,
+            japanese_names,
+
+            revision: 1,
         }
     }
 }
\ No newline at end of file
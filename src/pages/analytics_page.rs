@@ -0,0 +1,253 @@
+use eframe::egui::{self, Align2, DragValue, FontId, Rect, RichText, Sense, Stroke, Vec2};
+
+use crate::{
+    analysis::StatDistribution,
+    pages::{AnalyticsPage, CharactersPage, characters_page::{GroupBy, format_value}},
+    utils::{Character, POSITION_LIST, Position, STAT_LIST, Stat},
+};
+
+/// Width of the histogram bins, in stat points.
+const BIN_WIDTH: u8 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnalyticsSource {
+    /// The characters shown on the Characters page.
+    Results,
+    /// Every cached character.
+    Cache,
+}
+
+impl AnalyticsPage {
+    pub fn render(&mut self, characters_page: &CharactersPage, ui: &mut egui::Ui) {
+        ui.heading("Analytics");
+
+        ui.separator();
+
+        let results = characters_page.shown_characters();
+
+        ui.horizontal(|ui| {
+            ui.label("Characters");
+            ui.radio_value(&mut self.source, AnalyticsSource::Results, format!("Current results ({})", results.len()));
+            ui.radio_value(&mut self.source, AnalyticsSource::Cache, "Whole cache");
+
+            if self.source == AnalyticsSource::Cache && ui.button("Reload").clicked() {
+                self.reload_cache();
+            }
+        });
+
+        if self.source == AnalyticsSource::Cache && self.cache.is_none() {
+            self.reload_cache();
+        }
+
+        let characters: Vec<&Character> = match (self.source, &self.cache) {
+            (AnalyticsSource::Cache, Some(cache)) => cache.iter().collect(),
+            _ => results,
+        };
+
+        let distribution = StatDistribution::new(characters.iter().copied());
+
+        if distribution.count() == 0 {
+            ui.label("No character with stats to analyze.");
+            return;
+        }
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            self.lookup.render(ui, &characters);
+
+            ui.separator();
+
+            ui.heading(format!("Summary of {} characters", distribution.count()));
+            summary_grid(ui, &distribution);
+
+            ui.separator();
+
+            breakdown(ui, &mut self.breakdown, &characters);
+
+            ui.separator();
+
+            ui.heading("Distributions");
+            ui.horizontal_wrapped(|ui| {
+                for stat in STAT_LIST {
+                    ui.vertical(|ui| {
+                        ui.label(RichText::new(stat.to_str()).strong());
+                        histogram(ui, &distribution, stat);
+                    });
+                }
+            });
+        });
+    }
+}
+
+/// Percentile rank of a value among the characters of a position, answering
+/// questions such as "is 85 Kick good for a FW?".
+pub struct PercentileLookup {
+    pub stat: Stat,
+    pub value: u8,
+    /// `Position::NONE` compares with every position.
+    pub position: Position,
+}
+
+impl PercentileLookup {
+    fn render(&mut self, ui: &mut egui::Ui, characters: &[&Character]) {
+        ui.horizontal(|ui| {
+            ui.label("How good is");
+            ui.add(DragValue::new(&mut self.value));
+
+            egui::ComboBox::from_id_salt("lookup_stat")
+                .selected_text(self.stat.to_str())
+                .show_ui(ui, |ui| {
+                    for stat in STAT_LIST {
+                        ui.selectable_value(&mut self.stat, stat, stat.to_str());
+                    }
+                });
+
+            ui.label("for");
+
+            let label = if self.position == Position::NONE { "any position" } else { self.position.to_str() };
+            egui::ComboBox::from_id_salt("lookup_position")
+                .selected_text(label)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.position, Position::NONE, "any position");
+                    for position in POSITION_LIST {
+                        ui.selectable_value(&mut self.position, position, position.to_str());
+                    }
+                });
+        });
+
+        let peers = characters
+            .iter()
+            .copied()
+            .filter(|character| self.position == Position::NONE || character.position == self.position);
+        let distribution = StatDistribution::new(peers);
+
+        let peers = match self.position {
+            Position::NONE => "characters".to_string(),
+            position => position.to_str().to_string(),
+        };
+
+        match distribution.percentile_rank(self.stat, self.value) {
+            Some(rank) => ui.label(format!(
+                "{} {} is better than {:.1} % of the {} {}.",
+                self.value,
+                self.stat.to_str(),
+                rank,
+                distribution.count(),
+                peers,
+            )),
+            None => ui.label(format!("No {peers} to compare with.")),
+        };
+    }
+}
+
+/// Mean and median of each stat per element or position.
+fn breakdown(ui: &mut egui::Ui, group_by: &mut GroupBy, characters: &[&Character]) {
+    ui.horizontal(|ui| {
+        ui.heading("Breakdown by");
+
+        egui::ComboBox::from_id_salt("analytics_breakdown")
+            .selected_text(group_by.to_str())
+            .show_ui(ui, |ui| {
+                ui.selectable_value(group_by, GroupBy::Element, GroupBy::Element.to_str());
+                ui.selectable_value(group_by, GroupBy::Position, GroupBy::Position.to_str());
+            });
+    });
+
+    ui.label("Mean (median) of each stat");
+
+    egui::Grid::new("analytics_breakdown_grid").striped(true).spacing([16.0, 4.0]).show(ui, |ui| {
+        ui.label("");
+        ui.label(RichText::new("Count").strong());
+        for stat in STAT_LIST {
+            ui.label(RichText::new(stat.to_str()).strong());
+        }
+        ui.end_row();
+
+        for (label, group) in group_by.split(characters) {
+            let distribution = StatDistribution::new(group.iter().copied());
+
+            ui.label(label);
+            ui.label(distribution.count().to_string());
+
+            for stat in STAT_LIST {
+                match distribution.summary(stat) {
+                    Some(summary) => ui.label(format!("{:.1} ({})", summary.mean, format_value(summary.median))),
+                    None => ui.label("-"),
+                };
+            }
+            ui.end_row();
+        }
+    });
+}
+
+fn summary_grid(ui: &mut egui::Ui, distribution: &StatDistribution) {
+    egui::Grid::new("analytics_summary").striped(true).spacing([16.0, 4.0]).show(ui, |ui| {
+        for header in ["", "Mean", "Min", "10 %", "25 %", "Median", "75 %", "90 %", "Max"] {
+            ui.label(RichText::new(header).strong());
+        }
+        ui.end_row();
+
+        for stat in STAT_LIST {
+            let Some(summary) = distribution.summary(stat) else {
+                continue;
+            };
+
+            ui.label(stat.to_str());
+            ui.label(format!("{:.1}", summary.mean));
+            ui.label(summary.min.to_string());
+            for value in [summary.p10, summary.p25, summary.median, summary.p75, summary.p90] {
+                ui.label(format_value((value * 10.0).round() / 10.0));
+            }
+            ui.label(summary.max.to_string());
+            ui.end_row();
+        }
+    });
+}
+
+/// Bar chart of the number of characters per range of values.
+fn histogram(ui: &mut egui::Ui, distribution: &StatDistribution, stat: Stat) {
+    let (response, painter) = ui.allocate_painter(Vec2::new(260.0, 120.0), Sense::hover());
+
+    let bins = distribution.histogram(stat, BIN_WIDTH);
+    let Some(highest) = bins.iter().max().copied().filter(|highest| *highest > 0) else {
+        return;
+    };
+
+    let area = Rect::from_min_max(response.rect.min, response.rect.max - Vec2::new(0.0, 14.0));
+    let bar_width = area.width() / bins.len() as f32;
+    let fill = ui.visuals().selection.bg_fill;
+    let font = FontId::proportional(10.0);
+    let text_color = ui.visuals().weak_text_color();
+
+    let mut hovered = None;
+
+    for (index, count) in bins.iter().enumerate() {
+        let left = area.left() + index as f32 * bar_width;
+        let height = *count as f32 / highest as f32 * area.height();
+        let bar = Rect::from_min_max(
+            egui::pos2(left + 1.0, area.bottom() - height),
+            egui::pos2(left + bar_width - 1.0, area.bottom()),
+        );
+
+        painter.rect_filled(bar, 0.0, fill);
+
+        let column = Rect::from_x_y_ranges(bar.x_range(), area.y_range());
+        if response.hover_pos().is_some_and(|pointer| column.contains(pointer)) {
+            hovered = Some((index, *count));
+        }
+    }
+
+    painter.line_segment([area.left_bottom(), area.right_bottom()], Stroke::new(1.0, text_color));
+    painter.text(area.left_bottom(), Align2::LEFT_TOP, "0", font.clone(), text_color);
+    painter.text(
+        area.right_bottom(),
+        Align2::RIGHT_TOP,
+        (bins.len() * BIN_WIDTH as usize).to_string(),
+        font,
+        text_color,
+    );
+
+    if let Some((index, count)) = hovered {
+        let start = index * BIN_WIDTH as usize;
+        response.on_hover_text(format!("{} to {}: {} characters", start, start + BIN_WIDTH as usize - 1, count));
+    }
+}
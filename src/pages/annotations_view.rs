@@ -0,0 +1,148 @@
+use eframe::egui::{self, RichText};
+
+use crate::{database::Annotation, pages::CharactersPage};
+
+impl CharactersPage {
+    pub fn annotation(&self, number: u16) -> Option<&Annotation> {
+        self.annotations.get(&number)
+    }
+
+    pub fn is_favorite(&self, number: u16) -> bool {
+        self.annotation(number).is_some_and(|annotation| annotation.favorite)
+    }
+
+    /// Applies a change to the annotation of a character and saves it.
+    pub fn edit_annotation(&mut self, number: u16, edit: impl FnOnce(&mut Annotation)) {
+        let mut annotation = self.annotations.remove(&number).unwrap_or_default();
+        edit(&mut annotation);
+
+        self.character_cache.save_annotation(number, &annotation);
+        if !annotation.is_empty() {
+            self.annotations.insert(number, annotation);
+        }
+    }
+
+    /// Every tag in use, sorted.
+    fn tags(&self) -> Vec<&str> {
+        let mut tags: Vec<&str> = self.annotations
+            .values()
+            .flat_map(|annotation| annotation.tags.iter().map(String::as_str))
+            .collect();
+        tags.sort_unstable();
+        tags.dedup();
+        tags
+    }
+
+    /// Whether a character passes the favorite and tag filters.
+    pub fn matches_annotations(&self, number: u16) -> bool {
+        let annotation = self.annotation(number);
+
+        (!self.favorites_only || annotation.is_some_and(|annotation| annotation.favorite))
+            && self.tag_filter.as_ref().is_none_or(|tag| annotation.is_some_and(|annotation| annotation.tags.contains(tag)))
+    }
+
+    pub fn render_tags_menu(&mut self, ui: &mut egui::Ui) {
+        let active = self.favorites_only || self.tag_filter.is_some();
+        let title = if active { "Tags *" } else { "Tags" };
+
+        ui.menu_button(title, |ui| {
+            ui.checkbox(&mut self.favorites_only, "Only favorites");
+
+            ui.separator();
+
+            let tags: Vec<String> = self.tags().into_iter().map(str::to_string).collect();
+
+            ui.radio_value(&mut self.tag_filter, None, "Any tag");
+            if tags.is_empty() {
+                ui.weak("No tag yet, add them from the detail panel");
+            }
+            for tag in tags {
+                ui.radio_value(&mut self.tag_filter, Some(tag.clone()), tag);
+            }
+        });
+    }
+
+    /// Favorite, tags and notes of a character in the detail panel, saved as
+    /// they are edited.
+    pub fn render_annotation_section(&mut self, ui: &mut egui::Ui, number: u16) {
+        let annotation = self.annotation(number).cloned().unwrap_or_default();
+
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Notes").strong());
+
+            let star = if annotation.favorite { "★ Favorite" } else { "☆ Favorite" };
+            if ui.selectable_label(annotation.favorite, star).clicked() {
+                self.edit_annotation(number, |annotation| annotation.favorite = !annotation.favorite);
+            }
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            for tag in &annotation.tags {
+                ui.label(RichText::new(tag).background_color(ui.visuals().faint_bg_color));
+                if ui.small_button("x").on_hover_text("Remove the tag").clicked() {
+                    self.edit_annotation(number, |annotation| annotation.tags.retain(|other| other != tag));
+                }
+            }
+
+            let response = ui.add(egui::TextEdit::singleline(&mut self.new_tag).hint_text("Add a tag").desired_width(100.0));
+            if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                let tag = self.new_tag.trim().to_string();
+                self.new_tag.clear();
+
+                if !tag.is_empty() {
+                    self.edit_annotation(number, |annotation| {
+                        if !annotation.tags.contains(&tag) {
+                            annotation.tags.push(tag);
+                            annotation.tags.sort();
+                        }
+                    });
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.notes_preview, false, "Edit");
+            ui.selectable_value(&mut self.notes_preview, true, "Preview");
+        });
+
+        if self.notes_preview {
+            markdown_preview(ui, &annotation.notes);
+        } else {
+            let mut notes = annotation.notes.clone();
+            let response = ui.add(egui::TextEdit::multiline(&mut notes).desired_rows(4).hint_text("Markdown notes"));
+            if response.changed() {
+                self.edit_annotation(number, |annotation| annotation.notes = notes);
+            }
+        }
+    }
+}
+
+/// Draws the block-level markdown of the notes: headings, bullet lists and
+/// paragraphs. Inline markup is shown as written.
+fn markdown_preview(ui: &mut egui::Ui, text: &str) {
+    if text.trim().is_empty() {
+        ui.weak("No notes");
+        return;
+    }
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+
+        if let Some(heading) = trimmed.strip_prefix("## ") {
+            ui.label(RichText::new(heading).strong());
+        } else if let Some(heading) = trimmed.strip_prefix("# ") {
+            ui.label(RichText::new(heading).strong().size(16.0));
+        } else if let Some(item) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+            let indent = (line.len() - trimmed.len()) as f32 * 4.0;
+            ui.horizontal_wrapped(|ui| {
+                ui.add_space(indent);
+                ui.label("•");
+                ui.label(item);
+            });
+        } else if trimmed.is_empty() {
+            ui.add_space(4.0);
+        } else {
+            ui.add(egui::Label::new(line).wrap());
+        }
+    }
+}
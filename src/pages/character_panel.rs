@@ -0,0 +1,228 @@
+use eframe::egui::{self, ProgressBar, RichText};
+
+use crate::{
+    pages::{CharactersPage, ComparePage, characters_page::{element_badge, format_value}},
+    utils::{STAT_LIST, Stats, format_timestamp},
+};
+
+impl CharactersPage {
+    /// Side panel with everything known about the selected character.
+    pub fn render_detail_panel(&mut self, compare: &mut ComparePage, ui: &mut egui::Ui) {
+        // Cloned so that the roster can be edited while the panel is drawn
+        let Some(character) = self.selected_character().cloned() else {
+            return;
+        };
+
+        let mut close = false;
+        let mut refresh = false;
+        let mut show_versions = false;
+
+        egui::SidePanel::right("character_detail")
+            .resizable(true)
+            .default_width(320.0)
+            .show_inside(ui, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.heading(character.display_name(self.japanese_names));
+
+                        if ui.button("x").on_hover_text("Close").clicked() {
+                            close = true;
+                        }
+                    });
+
+                    self.portraits.show(ui, &self.character_cache, character.number, 128.0);
+
+                    let nickname = character.display_nickname(self.japanese_names);
+                    if !nickname.is_empty() {
+                        ui.label(RichText::new(format!("\"{nickname}\"")).italics());
+                    }
+
+                    // The name in the other language
+                    let other_name = character.display_name(!self.japanese_names);
+                    if other_name != character.display_name(self.japanese_names) {
+                        ui.weak(other_name);
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.label(format!("#{}", character.number));
+                        element_badge(ui, character.element);
+                        ui.label(character.position.to_str());
+                    });
+
+                    ui.separator();
+
+                    if let Some(stats) = &character.stats {
+                        let effective = self.modifiers.effective(&character);
+
+                        egui::Grid::new("detail_stats").num_columns(2).show(ui, |ui| {
+                            for stat in STAT_LIST {
+                                let value = stats.get(stat);
+                                let text = match effective.as_ref().map(|effective| effective.get(stat)) {
+                                    Some(boosted) if boosted != value => format!("{value} → {boosted}"),
+                                    _ => value.to_string(),
+                                };
+
+                                // Bars are relative to the best value among the results
+                                let best = self.characters
+                                    .iter()
+                                    .filter_map(|other| other.stats.as_ref().map(|stats| stats.get(stat)))
+                                    .max()
+                                    .unwrap_or(value)
+                                    .max(1);
+
+                                ui.label(stat.to_str());
+                                ui.add(ProgressBar::new(value as f32 / best as f32)
+                                    .text(text)
+                                    .desired_width(180.0));
+                                ui.end_row();
+                            }
+
+                            for column in &self.derived_columns {
+                                if let Some(value) = column.eval(&character) {
+                                    ui.label(&column.name);
+                                    ui.label(format_value(value));
+                                    ui.end_row();
+                                }
+                            }
+                        });
+                    }
+
+                    ui.separator();
+
+                    self.render_annotation_section(ui, character.number);
+
+                    ui.separator();
+
+                    self.render_roster_section(ui, character.number);
+
+                    ui.separator();
+
+                    self.render_modifier_section(ui, character.number);
+
+                    ui.separator();
+
+                    ui.label(RichText::new("Profile").strong());
+                    match &self.details.profile {
+                        Some(profile) => {
+                            egui::Grid::new("detail_profile").num_columns(2).show(ui, |ui| {
+                                for (label, value) in profile.fields() {
+                                    ui.label(label);
+                                    ui.add(egui::Label::new(value).wrap());
+                                    ui.end_row();
+                                }
+                            });
+
+                            if !profile.description.is_empty() {
+                                ui.add(egui::Label::new(RichText::new(&profile.description).italics()).wrap());
+                            }
+                        }
+                        None => {
+                            ui.label("Unknown, re-fetch the character to load it");
+                        }
+                    }
+
+                    ui.separator();
+
+                    ui.label(RichText::new("Techniques").strong());
+                    match &self.details.profile {
+                        Some(profile) if !profile.techniques.is_empty() => {
+                            for technique in &profile.techniques {
+                                ui.label(technique);
+                            }
+                        }
+                        Some(_) => {
+                            ui.label("None listed on the page of the character");
+                        }
+                        None => {
+                            ui.label("Unknown, re-fetch the character to load them");
+                        }
+                    }
+
+                    ui.separator();
+
+                    ui.label(RichText::new("Games").strong());
+                    if self.details.games.is_empty() {
+                        ui.label("Unknown, search with a single game selected to learn them");
+                    } else {
+                        let games: Vec<&str> = self.details.games.iter().map(|game| game.to_str()).collect();
+                        ui.label(games.join(", "));
+                    }
+
+                    if ui.button("Versions in other games").clicked() {
+                        show_versions = true;
+                    }
+
+                    ui.separator();
+
+                    ui.horizontal(|ui| {
+                        let fetched = self.details.fetched_at.map_or("unknown".to_string(), format_timestamp);
+                        ui.label(format!("Last fetched: {fetched}"));
+                    });
+
+                    ui.horizontal(|ui| {
+                        if self.refreshing {
+                            ui.spinner();
+                        } else if ui.button("Re-fetch").clicked() {
+                            refresh = true;
+                        }
+
+                        let pinned = compare.is_pinned(character.number);
+                        let label = if pinned { "Unpin" } else { "Pin to comparison" };
+
+                        if ui.add_enabled(pinned || !compare.is_full(), egui::Button::new(label)).clicked() {
+                            compare.toggle_pin(&character);
+                        }
+
+                        if !character.page_url.is_empty() {
+                            ui.hyperlink_to("Inazugle", &character.page_url);
+                        }
+                    });
+
+                    if self.details.stat_history.len() > 1 {
+                        ui.separator();
+                        ui.label(RichText::new("Stat history").strong());
+
+                        egui::Grid::new("detail_history").striped(true).show(ui, |ui| {
+                            ui.label("Date");
+                            for stat in STAT_LIST {
+                                ui.label(&stat.to_str()[..3]);
+                            }
+                            ui.end_row();
+
+                            let mut previous = None;
+
+                            for (fetched_at, stats) in &self.details.stat_history {
+                                ui.label(format_timestamp(*fetched_at));
+
+                                for stat in STAT_LIST {
+                                    let value = stats.get(stat);
+                                    let changed = previous.is_some_and(|previous: &Stats| previous.get(stat) != value);
+
+                                    if changed {
+                                        ui.label(RichText::new(value.to_string()).strong());
+                                    } else {
+                                        ui.label(value.to_string());
+                                    }
+                                }
+
+                                previous = Some(stats);
+                                ui.end_row();
+                            }
+                        });
+                    }
+                });
+            });
+
+        if close {
+            self.select_character(None);
+        }
+
+        if refresh {
+            self.refresh_selected();
+        }
+
+        if show_versions {
+            self.show_versions(&character);
+        }
+    }
+}
@@ -1,10 +1,10 @@
-use eframe::egui::{self, ProgressBar};
-use egui_extras::{Column, TableBuilder};
+use eframe::egui::{self, Color32, ProgressBar, RichText};
+use egui_extras::{Column, TableBuilder, TableRow};
 
-use crate::{pages::{CharactersPage, SettingsPage}, utils::{ELEMENT_LIST, GAME_LIST, POSITION_LIST, Progress}};
+use crate::{analysis::StatDistribution, database::SearchSource, pages::{CharactersPage, ComparePage, SettingsPage, pareto_view::ParetoMode}, query::DerivedColumn, roster::RosterEntry, utils::{Character, ELEMENT_LIST, Element, GAME_LIST, POSITION_LIST, Position, STAT_LIST, Stat, Stats, format_timestamp}};
 
 impl CharactersPage {
-    pub fn render(&mut self, settings: &SettingsPage, ui: &mut egui::Ui) {
+    pub fn render(&mut self, settings: &SettingsPage, compare: &mut ComparePage, ui: &mut egui::Ui) {
         let max_parallelism = settings.max_parallelism;
 
         ui.heading("Character Comparator");
@@ -15,22 +15,11 @@ impl CharactersPage {
             ui.text_edit_singleline(&mut self.request.name);
 
             if ui.button("Send Request").clicked() {
-                let request = self.request.clone();
-                let mut db = self.character_cache.clone();
-                let sender = self.sender.clone();
-
-                let progress = Progress::new();
-                self.progress = Some(progress.clone());
-
-                self.runtime.spawn(async move {
-                    let characters = request.send(&mut db, max_parallelism, progress).await;
+                self.send_request(max_parallelism);
+            }
 
-                    let characters = characters
-                        .into_iter()
-                        .filter(|char| char.stats.is_some())
-                        .collect();
-                    let _ = sender.send(characters);
-                });
+            if ui.button("Search Cache").clicked() {
+                self.search_cache();
             }
 
             if let Some(progress) = &self.progress {
@@ -80,123 +69,734 @@ impl CharactersPage {
                     }
                 }
             });
+
+            self.render_stat_filters(ui);
+
+            let school_label = if self.school_filter.is_some() { "School *" } else { "School" };
+            ui.menu_button(school_label, |ui| {
+                ui.radio_value(&mut self.school_filter, None, "Any");
+
+                if self.schools.is_empty() {
+                    ui.weak("No school known yet, schools are read from the character pages");
+                }
+
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for school in &self.schools {
+                        ui.radio_value(&mut self.school_filter, Some(school.clone()), school);
+                    }
+                });
+            });
+
+            ui.separator();
+
+            self.render_saved_searches(ui, max_parallelism);
+            self.render_search_history(ui, max_parallelism);
         });
 
+        ui.horizontal(|ui| {
+            ui.label("Query");
+
+            let mut text = self.query_text.clone();
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut text)
+                    .hint_text("element:fire pos:FW kick>=85 (agility+technique)>150 name~\"Go\"")
+                    .desired_width(f32::INFINITY),
+            );
+
+            if response.changed() {
+                self.set_query(text);
+            }
+        });
+
+        if let Some(error) = &self.query_error {
+            ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+        }
+
         ui.separator();
 
+        self.update_pareto();
+
+        let shown = self.characters
+            .iter()
+            .filter(|character| self.is_shown(character))
+            .count();
+
+        if shown != self.characters.len() {
+            ui.label(format!("Showing {} of {} characters", shown, self.characters.len()));
+        }
+
+        ui.horizontal(|ui| {
+            self.render_column_chooser(ui);
+
+            ui.label("Group by");
+            egui::ComboBox::from_id_salt("group_by")
+                .selected_text(self.group_by.map_or("None", GroupBy::to_str))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.group_by, None, "None");
+                    ui.selectable_value(&mut self.group_by, Some(GroupBy::Element), "Element");
+                    ui.selectable_value(&mut self.group_by, Some(GroupBy::Position), "Position");
+                });
+
+            ui.separator();
+
+            self.render_pareto_menu(ui);
+
+            ui.separator();
+
+            self.render_my_team_menu(ui);
+            self.render_roster_menu(ui);
+            self.render_tags_menu(ui);
+            self.render_modifiers_menu(ui);
+        });
+
+        self.render_pareto_plot(ui);
+        self.render_modifiers_window(ui);
+
+        let columns: Vec<TableColumn> = self.columns
+            .iter()
+            .filter(|(_, visible)| *visible)
+            .map(|(column, _)| column.clone())
+            .collect();
+
+        self.render_detail_panel(compare, ui);
+
+        let row_height = if columns.contains(&TableColumn::Portrait) { THUMBNAIL_SIZE + 4.0 } else { 18.0 };
+
+        let mut clicked = None;
+        let mut pin_toggled = None;
+        let mut similar_to = None;
+        let mut versions_of = None;
+        let mut my_team_toggled = None;
+        let mut favorite_toggled = None;
+
         TableBuilder::new(ui)
             .striped(true)
+            .sense(egui::Sense::click())
             .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
-            .column(Column::auto()) // ID
-            .column(Column::auto()) // Name
-            .column(Column::auto()) // Kick
-            .column(Column::auto()) // Control
-            .column(Column::auto()) // Technique
-            .column(Column::auto()) // Pressure
-            .column(Column::auto()) // Physical
-            .column(Column::auto()) // Agility
-            .column(Column::auto()) // Intelligence
-            .column(Column::auto()) // Link to the self
+            .columns(Column::auto(), columns.len())
             .header(20.0, |mut header| {
-                header.col(|ui| {
-                    sortable_header(ui, "ID", SortColumn::ID, self);
-                });
-                header.col(|ui| {
-                    sortable_header(ui, "Name", SortColumn::Name, self);
-                });
-                header.col(|ui| {
-                    sortable_header(ui, "Kick", SortColumn::Kick, self);
-                });
-                header.col(|ui| {
-                    sortable_header(ui, "Control", SortColumn::Control, self);
-                });
-                header.col(|ui| {
-                    sortable_header(ui, "Technique", SortColumn::Technique, self);
-                });
-                header.col(|ui| {
-                    sortable_header(ui, "Pressure", SortColumn::Pressure, self);
-                });
-                header.col(|ui| {
-                    sortable_header(ui, "Physical", SortColumn::Physical, self);
-                });
-                header.col(|ui| {
-                    sortable_header(ui, "Agility", SortColumn::Agility, self);
-                });
-                header.col(|ui| {
-                    sortable_header(ui, "Intelligence", SortColumn::Intelligence, self);
-                });
-                header.col(|ui| {
-                    ui.add_enabled(false, egui::Button::new("Link"));
-                });
+                for column in &columns {
+                    header.col(|ui| {
+                        if column.is_sortable() {
+                            sortable_header(ui, column.to_str(), column.clone(), self);
+                        } else {
+                            ui.add_enabled(false, egui::Button::new(column.to_str()));
+                        }
+                    });
+                }
             })
             .body(|mut body| {
-                for character in &self.characters {
-                    let stats = character.stats.as_ref().unwrap();
-                    body.row(18.0, |mut row| {
-                        row.col(|ui| {
-                            ui.label(character.number.to_string());
-                        });
-                        row.col(|ui| {
-                            ui.label(&character.name);
-                        });
-                        row.col(|ui| {
-                            ui.label(stats.kick.to_string());
-                        });
-                        row.col(|ui| {
-                            ui.label(stats.control.to_string());
-                        });
-                        row.col(|ui| {
-                            ui.label(stats.technique.to_string());
-                        });
-                        row.col(|ui| {
-                            ui.label(stats.pressure.to_string());
-                        });
-                        row.col(|ui| {
-                            ui.label(stats.physical.to_string());
-                        });
-                        row.col(|ui| {
-                            ui.label(stats.agility.to_string());
-                        });
-                        row.col(|ui| {
-                            ui.label(stats.intelligence.to_string());
+                let shown = self.shown_characters();
+
+                let groups = match self.group_by {
+                    Some(group_by) => group_by.split(&shown),
+                    None => vec![(String::new(), shown)],
+                };
+
+                for (label, group) in groups {
+                    if self.group_by.is_some() {
+                        body.row(SUBTOTAL_ROW_HEIGHT, |mut row| {
+                            subtotal_row(&mut row, &columns, &label, &group, &self.derived_columns, &self.distribution);
                         });
-                        row.col(|ui| {
-                            ui.hyperlink_to("Inazugle", &character.page_url);
+                    }
+
+                    for character in group {
+                        body.row(row_height, |mut row| {
+                            row.set_selected(self.selected == Some(character.number));
+
+                            let dimmed = self.pareto.mode == ParetoMode::Mark && self.pareto.is_dominated(character.number);
+
+                            for column in &columns {
+                                row.col(|ui| {
+                                    if dimmed {
+                                        ui.multiply_opacity(0.4);
+                                    }
+
+                                    if *column == TableColumn::Portrait {
+                                        self.portraits.show(ui, &self.character_cache, character.number, THUMBNAIL_SIZE);
+                                    } else if *column == TableColumn::Owned {
+                                        match self.roster_entry(character.number) {
+                                            Some(RosterEntry { level: Some(level), .. }) => ui.label(format!("Lv {level}")),
+                                            Some(_) => ui.label("Owned"),
+                                            None => ui.label(""),
+                                        };
+                                    } else if *column == TableColumn::Favorite {
+                                        let favorite = self.is_favorite(character.number);
+                                        let star = if favorite { "★" } else { "☆" };
+                                        if ui.selectable_label(favorite, star).on_hover_text("Favorite").clicked() {
+                                            favorite_toggled = Some(character.number);
+                                        }
+                                    } else if *column == TableColumn::Tags {
+                                        if let Some(annotation) = self.annotation(character.number) {
+                                            ui.label(annotation.tags.join(", "));
+                                        }
+                                    } else if *column == TableColumn::Notes {
+                                        if let Some(annotation) = self.annotation(character.number)
+                                            && let Some(first_line) = annotation.notes.lines().find(|line| !line.trim().is_empty())
+                                        {
+                                            ui.label(first_line).on_hover_text(&annotation.notes);
+                                        }
+                                    } else if let TableColumn::Stat(stat) = column
+                                        && let Some(effective) = self.modifiers.effective(character)
+                                    {
+                                        stat_cell_with_modifiers(ui, *stat, character, &effective);
+                                    } else if *column == TableColumn::MyTeam {
+                                        let mut in_team = self.is_in_my_team(character.number);
+                                        if ui.checkbox(&mut in_team, "").on_hover_text("In My Team").changed() {
+                                            my_team_toggled = Some(character.number);
+                                        }
+                                    } else {
+                                        character_cell(ui, column, character, &self.derived_columns, &self.distribution, self.japanese_names);
+                                    }
+                                });
+                            }
+
+                            let response = row.response();
+
+                            if response.clicked() {
+                                clicked = Some(character.number);
+                            }
+
+                            response.context_menu(|ui| {
+                                let label = if compare.is_pinned(character.number) { "Unpin from comparison" } else { "Pin to comparison" };
+
+                                if ui.add_enabled(compare.is_pinned(character.number) || !compare.is_full(), egui::Button::new(label)).clicked() {
+                                    pin_toggled = Some(character.clone());
+                                    ui.close();
+                                }
+
+                                if ui.add_enabled(character.stats.is_some(), egui::Button::new("Find similar")).clicked() {
+                                    similar_to = Some(character.clone());
+                                    ui.close();
+                                }
+
+                                if ui.button("Versions in other games").clicked() {
+                                    versions_of = Some(character.clone());
+                                    ui.close();
+                                }
+                            });
                         });
-                    });
+                    }
+                }
+            });
+
+        if let Some(character) = pin_toggled {
+            compare.toggle_pin(&character);
+        }
+
+        if let Some(character) = similar_to {
+            self.find_similar(character);
+        }
+
+        if let Some(number) = favorite_toggled {
+            self.edit_annotation(number, |annotation| annotation.favorite = !annotation.favorite);
+        }
+
+        if let Some(number) = my_team_toggled {
+            self.toggle_my_team(number);
+        }
+
+        if let Some(character) = versions_of {
+            self.show_versions(&character);
+        }
+
+        self.render_similar_window(compare, ui);
+        self.render_versions_window(compare, ui);
+
+        if let Some(number) = clicked {
+            let selected = if self.selected == Some(number) { None } else { Some(number) };
+            self.select_character(selected);
+        }
+    }
+
+    fn render_column_chooser(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Columns", |ui| {
+            let mut changed = false;
+            let mut moved = None;
+            let count = self.columns.len();
+
+            for (index, (column, visible)) in self.columns.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    changed |= ui.checkbox(visible, column.to_str()).changed();
+
+                    if ui.add_enabled(index > 0, egui::Button::new("^")).clicked() {
+                        moved = Some((index, index - 1));
+                    }
+                    if ui.add_enabled(index + 1 < count, egui::Button::new("v")).clicked() {
+                        moved = Some((index, index + 1));
+                    }
+                });
+            }
+
+            if let Some((from, to)) = moved {
+                self.columns.swap(from, to);
+                changed = true;
+            }
+
+            if ui.button("Reset").clicked() {
+                self.columns = TableColumn::default_layout(&self.derived_columns);
+                changed = true;
+            }
+
+            if changed {
+                self.store_column_layout();
+            }
+        });
+    }
+
+    fn render_stat_filters(&mut self, ui: &mut egui::Ui) {
+        let title = if self.request.stat_filters.is_empty() { "Stats" } else { "Stats *" };
+
+        ui.menu_button(title, |ui| {
+            egui::Grid::new("stat_filters").num_columns(3).show(ui, |ui| {
+                for stat in STAT_LIST {
+                    ui.label(stat.to_str());
+                    ui.add(egui::DragValue::new(self.request.stat_filters.min_mut(stat)).prefix(">= "));
+                    ui.add(egui::DragValue::new(self.request.stat_filters.max_mut(stat)).prefix("<= "));
+                    ui.end_row();
+                }
+            });
+
+            if ui.button("Reset").clicked() {
+                self.request.stat_filters.reset();
+            }
+        });
+    }
+
+    fn render_saved_searches(&mut self, ui: &mut egui::Ui, max_parallelism: usize) {
+        ui.menu_button("Saved searches", |ui| {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.saved_search_name);
+
+                let save = ui
+                    .add_enabled(self.query_error.is_none(), egui::Button::new("Save current"))
+                    .on_disabled_hover_text("The query has an error");
+                if save.clicked() {
+                    self.save_current_search();
                 }
             });
+
+            ui.separator();
+
+            if self.saved_searches.is_empty() {
+                ui.label("No saved searches");
+            }
+
+            let mut action = None;
+
+            for search in &self.saved_searches {
+                ui.horizontal(|ui| {
+                    ui.label(&search.name);
+
+                    if ui.button("Online").clicked() {
+                        action = Some((search.clone(), Some(SearchSource::Online)));
+                    }
+                    if ui.button("Cache").clicked() {
+                        action = Some((search.clone(), Some(SearchSource::Cache)));
+                    }
+                    if ui.button("Delete").clicked() {
+                        action = Some((search.clone(), None));
+                    }
+                });
+            }
+
+            match action {
+                Some((search, Some(source))) => {
+                    self.load_saved_search(&search, source, max_parallelism);
+                    ui.close();
+                }
+                Some((search, None)) => self.delete_saved_search(search.id),
+                None => (),
+            }
+        });
     }
 
+    fn render_search_history(&mut self, ui: &mut egui::Ui, max_parallelism: usize) {
+        ui.menu_button("History", |ui| {
+            if self.search_history.is_empty() {
+                ui.label("No search sent yet");
+            } else if ui.button("Clear history").clicked() {
+                self.clear_search_history();
+                return;
+            }
+
+            ui.separator();
+
+            let mut action = None;
+
+            egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                for entry in &self.search_history {
+                    ui.horizontal(|ui| {
+                        let name = if entry.request.name.is_empty() { "*" } else { entry.request.name.as_str() };
+
+                        let query = if entry.query.is_empty() { String::new() } else { format!(" | {}", entry.query) };
+
+                        ui.label(format!(
+                            "{} | {} | \"{}\"{} | {} results",
+                            format_timestamp(entry.sent_at),
+                            entry.source.to_str(),
+                            name,
+                            query,
+                            entry.result_count,
+                        ));
+
+                        if ui.button("Online").clicked() {
+                            action = Some((entry.clone(), SearchSource::Online));
+                        }
+                        if ui.button("Cache").clicked() {
+                            action = Some((entry.clone(), SearchSource::Cache));
+                        }
+                    });
+                }
+            });
+
+            if let Some((entry, source)) = action {
+                self.set_query(entry.query);
+                self.run_search(entry.request, source, max_parallelism);
+                ui.close();
+            }
+        });
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum SortColumn {
+/// Side of the portraits shown in the table.
+const THUMBNAIL_SIZE: f32 = 32.0;
+
+/// Height of the group rows, which show a total above an average.
+const SUBTOTAL_ROW_HEIGHT: f32 = 36.0;
+
+/// Headers of the percentile columns, in `STAT_LIST` order.
+const PERCENTILE_LABELS: [&str; 7] = ["Kick %", "Control %", "Technique %", "Pressure %", "Physical %", "Agility %", "Intelligence %"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableColumn {
+    /// Checkbox adding the character to My Team.
+    MyTeam,
+    /// Whether the character is in the roster, with its level.
+    Owned,
+    /// Star marking the favorites, toggled from the table.
+    Favorite,
+    Tags,
+    /// First line of the notes.
+    Notes,
+    Portrait,
     ID,
     Name,
-    Kick,
-    Control,
-    Technique,
-    Pressure,
-    Physical,
-    Agility,
-    Intelligence,
+    Nickname,
+    Element,
+    Position,
+    School,
+    Stat(Stat),
+    /// A derived column, referenced by name.
+    Derived(String),
+    /// Percentile rank of a stat among the loaded characters.
+    Percentile(Stat),
+    /// Link to the character page on the website.
+    Link,
+}
+
+impl TableColumn {
+    pub fn to_str(&self) -> &str {
+        match self {
+            TableColumn::MyTeam => "My Team",
+            TableColumn::Owned => "Owned",
+            TableColumn::Favorite => "Favorite",
+            TableColumn::Tags => "Tags",
+            TableColumn::Notes => "Notes",
+            TableColumn::Portrait => "Portrait",
+            TableColumn::ID => "ID",
+            TableColumn::Name => "Name",
+            TableColumn::Nickname => "Nickname",
+            TableColumn::Element => "Element",
+            TableColumn::Position => "Position",
+            TableColumn::School => "School",
+            TableColumn::Stat(stat) => stat.to_str(),
+            TableColumn::Derived(name) => name,
+            TableColumn::Percentile(stat) => PERCENTILE_LABELS[stat.index()],
+            TableColumn::Link => "Link",
+        }
+    }
+
+    /// Parses a name produced by `to_str`. Unknown names are assumed to be
+    /// derived columns.
+    pub fn from_name(name: &str) -> TableColumn {
+        match name {
+            "My Team" => TableColumn::MyTeam,
+            "Owned" => TableColumn::Owned,
+            "Favorite" => TableColumn::Favorite,
+            "Tags" => TableColumn::Tags,
+            "Notes" => TableColumn::Notes,
+            "Portrait" => TableColumn::Portrait,
+            "ID" => TableColumn::ID,
+            "Name" => TableColumn::Name,
+            "Nickname" => TableColumn::Nickname,
+            "Element" => TableColumn::Element,
+            "Position" => TableColumn::Position,
+            "School" => TableColumn::School,
+            "Link" => TableColumn::Link,
+            _ if PERCENTILE_LABELS.contains(&name) => {
+                let stat = name.trim_end_matches(" %");
+                Stat::from_name(stat).map_or(TableColumn::Derived(name.to_string()), TableColumn::Percentile)
+            }
+            _ => match Stat::from_name(name) {
+                Some(stat) => TableColumn::Stat(stat),
+                None => TableColumn::Derived(name.to_string()),
+            },
+        }
+    }
+
+    pub fn is_sortable(&self) -> bool {
+        !matches!(
+            self,
+            TableColumn::MyTeam | TableColumn::Owned | TableColumn::Favorite | TableColumn::Tags | TableColumn::Notes | TableColumn::Portrait | TableColumn::Link
+        )
+    }
+
+    /// Whether the column holds numbers that can be averaged over a group.
+    fn is_numeric(&self) -> bool {
+        matches!(self, TableColumn::Stat(_) | TableColumn::Derived(_) | TableColumn::Percentile(_))
+    }
+
+    /// Every column in their default order, all visible but the percentiles.
+    pub fn default_layout(derived: &[DerivedColumn]) -> Vec<(TableColumn, bool)> {
+        let mut columns = vec![
+            TableColumn::Favorite,
+            TableColumn::MyTeam,
+            TableColumn::Owned,
+            TableColumn::Portrait,
+            TableColumn::ID,
+            TableColumn::Name,
+            TableColumn::Nickname,
+            TableColumn::Element,
+            TableColumn::Position,
+            TableColumn::School,
+        ];
+        columns.extend(STAT_LIST.map(TableColumn::Stat));
+        columns.extend(derived.iter().map(|column| TableColumn::Derived(column.name.clone())));
+        columns.extend(STAT_LIST.map(TableColumn::Percentile));
+        columns.push(TableColumn::Tags);
+        columns.push(TableColumn::Notes);
+        columns.push(TableColumn::Link);
+
+        columns
+            .into_iter()
+            .map(|column| {
+                let visible = !matches!(column, TableColumn::Percentile(_));
+                (column, visible)
+            })
+            .collect()
+    }
+
+    /// Numeric value of the column for a character, used for group averages.
+    fn value(&self, character: &Character, derived: &[DerivedColumn], distribution: &StatDistribution) -> Option<f64> {
+        match self {
+            TableColumn::Stat(stat) => character.stats.as_ref().map(|stats| stats.get(*stat) as f64),
+            TableColumn::Percentile(stat) => {
+                let value = character.stats.as_ref()?.get(*stat);
+                distribution.percentile_rank(*stat, value)
+            }
+            TableColumn::Derived(name) => derived
+                .iter()
+                .find(|column| &column.name == name)
+                .and_then(|column| column.eval(character)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupBy {
+    Element,
+    Position,
+}
+
+impl GroupBy {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            GroupBy::Element => "Element",
+            GroupBy::Position => "Position",
+        }
+    }
+
+    /// Splits characters into labelled groups, keeping their order within
+    /// each group. Empty groups are left out.
+    pub fn split<'a>(self, characters: &[&'a Character]) -> Vec<(String, Vec<&'a Character>)> {
+        let groups: Vec<(String, Vec<&Character>)> = match self {
+            GroupBy::Element => ELEMENT_LIST
+                .iter()
+                .chain([Element::NONE].iter())
+                .map(|element| {
+                    let members = characters.iter().copied().filter(|c| c.element == *element).collect();
+                    (element.db_str().to_string(), members)
+                })
+                .collect(),
+            GroupBy::Position => POSITION_LIST
+                .iter()
+                .chain([Position::NONE].iter())
+                .map(|position| {
+                    let label = if *position == Position::NONE { "Unknown" } else { position.to_str() };
+                    let members = characters.iter().copied().filter(|c| c.position == *position).collect();
+                    (label.to_string(), members)
+                })
+                .collect(),
+        };
+
+        groups.into_iter().filter(|(_, members)| !members.is_empty()).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortKey {
+    pub column: TableColumn,
+    pub ascending: bool,
+}
+
+pub fn element_color(element: Element) -> Color32 {
+    match element {
+        Element::FIRE => Color32::from_rgb(214, 64, 48),
+        Element::FOREST => Color32::from_rgb(56, 150, 60),
+        Element::MOUNTAIN => Color32::from_rgb(160, 110, 50),
+        Element::WIND => Color32::from_rgb(50, 140, 210),
+        Element::NONE => Color32::GRAY,
+    }
+}
+
+/// Element name on a background of the element color.
+pub fn element_badge(ui: &mut egui::Ui, element: Element) {
+    ui.label(
+        RichText::new(format!(" {} ", element.db_str()))
+            .color(Color32::WHITE)
+            .background_color(element_color(element)),
+    );
+}
+
+fn character_cell(ui: &mut egui::Ui, column: &TableColumn, character: &Character, derived: &[DerivedColumn], distribution: &StatDistribution, japanese: bool) {
+    match column {
+        // Drawn by the table, which holds the portraits and the personal data
+        TableColumn::MyTeam | TableColumn::Owned | TableColumn::Favorite | TableColumn::Tags | TableColumn::Notes | TableColumn::Portrait => (),
+        TableColumn::ID => {
+            ui.label(character.number.to_string());
+        }
+        TableColumn::Name => {
+            ui.label(character.display_name(japanese));
+        }
+        TableColumn::Nickname => {
+            ui.label(character.display_nickname(japanese));
+        }
+        TableColumn::Element => element_badge(ui, character.element),
+        TableColumn::Position => {
+            ui.label(character.position.to_str());
+        }
+        TableColumn::School => {
+            ui.label(&character.school);
+        }
+        TableColumn::Link => {
+            ui.hyperlink_to("Inazugle", &character.page_url);
+        }
+        TableColumn::Stat(_) | TableColumn::Derived(_) => {
+            match column.value(character, derived, distribution) {
+                Some(value) => ui.label(format_value(value)),
+                None => ui.label("-"),
+            };
+        }
+        TableColumn::Percentile(_) => {
+            match column.value(character, derived, distribution) {
+                Some(value) => ui.label(format!("{value:.0}")),
+                None => ui.label("-"),
+            };
+        }
+    }
+}
+
+/// Base value of a stat followed by its value once the modifiers of the
+/// character are applied.
+fn stat_cell_with_modifiers(ui: &mut egui::Ui, stat: Stat, character: &Character, effective: &Stats) {
+    let base = character.stats.as_ref().map_or(0, |stats| stats.get(stat));
+    let value = effective.get(stat);
+
+    if value == base {
+        ui.label(base.to_string());
+    } else {
+        ui.label(format!("{base} → {value}"))
+            .on_hover_text(format!("Base {base}, {value} with the modifiers"));
+    }
+}
+
+/// Group header row showing the group name and size in the first column, and
+/// the total and average of every other numeric column. Percentiles only get
+/// their average, as their total means nothing.
+fn subtotal_row(row: &mut TableRow, columns: &[TableColumn], label: &str, group: &[&Character], derived: &[DerivedColumn], distribution: &StatDistribution) {
+    for (index, column) in columns.iter().enumerate() {
+        row.col(|ui| {
+            if index == 0 {
+                ui.label(RichText::new(format!("{} ({})", label, group.len())).strong())
+                    .on_hover_text("Totals, with averages below");
+                return;
+            }
+
+            if !column.is_numeric() {
+                return;
+            }
+
+            let values: Vec<f64> = group
+                .iter()
+                .filter_map(|character| column.value(character, derived, distribution))
+                .collect();
+
+            if values.is_empty() {
+                return;
+            }
+
+            let total: f64 = values.iter().sum();
+            let average = total / values.len() as f64;
+
+            ui.vertical(|ui| {
+                if !matches!(column, TableColumn::Percentile(_)) {
+                    ui.label(RichText::new(format_value((total * 10.0).round() / 10.0)).strong());
+                }
+                ui.weak(format!("{average:.1}"));
+            });
+        });
+    }
+}
+
+/// Displays a computed value without decimals when it is a whole number.
+pub fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.1}")
+    }
 }
 
-pub fn sortable_header(ui: &mut egui::Ui, label: &str, column: SortColumn, state: &mut CharactersPage) {
+/// Header button sorting by its column. A click sorts by this column alone,
+/// or flips its direction if it already is the only key. A shift-click adds
+/// the column as the next key of the sort stack, or flips its direction if it
+/// is already part of it.
+pub fn sortable_header(ui: &mut egui::Ui, label: &str, column: TableColumn, state: &mut CharactersPage) {
     let mut text = label.to_string();
+    let position = state.sort.iter().position(|key| key.column == column);
+
+    if let Some(index) = position {
+        text.push_str(if state.sort[index].ascending { " ^" } else { " v" });
 
-    if state.sort_column == column {
-        text.push_str(if state.sort_ascending { " ^" } else { " v" });
+        if state.sort.len() > 1 {
+            text.push_str(&(index + 1).to_string());
+        }
     }
 
-    if ui.button(text).clicked() {
-        if state.sort_column == column {
-            state.sort_ascending = !state.sort_ascending;
-        } else {
-            state.sort_column = column;
-            state.sort_ascending = true;
+    let response = ui.button(text).on_hover_text("Shift-click to add as a secondary sort");
+
+    if response.clicked() {
+        let shift = ui.input(|input| input.modifiers.shift);
+
+        match (position, shift) {
+            (Some(index), true) => state.sort[index].ascending = !state.sort[index].ascending,
+            (None, true) => state.sort.push(SortKey { column, ascending: true }),
+            (Some(0), false) if state.sort.len() == 1 => state.sort[0].ascending = !state.sort[0].ascending,
+            (_, false) => state.sort = vec![SortKey { column, ascending: true }],
         }
+
         state.sort_characters();
     }
-}
\ No newline at end of file
+}
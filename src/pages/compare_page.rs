@@ -0,0 +1,224 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use eframe::egui::{self, Align2, Color32, FontId, Pos2, RichText, Sense, Shape, Stroke, Vec2};
+
+use crate::{
+    modifiers::ModifierSet,
+    pages::{ComparePage, SettingsPage, MAX_PINNED, characters_page::{element_badge, format_value}},
+    utils::{Character, STAT_LIST},
+};
+
+/// Colors given to the pinned characters, in pinning order.
+const PALETTE: [Color32; MAX_PINNED] = [
+    Color32::from_rgb(66, 133, 244),
+    Color32::from_rgb(219, 68, 55),
+    Color32::from_rgb(244, 180, 0),
+    Color32::from_rgb(15, 157, 88),
+    Color32::from_rgb(171, 71, 188),
+    Color32::from_rgb(0, 172, 193),
+];
+
+impl ComparePage {
+    pub fn render(&mut self, settings: &SettingsPage, modifiers: &ModifierSet, ui: &mut egui::Ui) {
+        ui.heading("Comparison");
+
+        ui.separator();
+
+        if self.pinned.is_empty() {
+            ui.label(format!(
+                "Pin 2 to {MAX_PINNED} characters from the Characters page (right-click a row) to compare them here."
+            ));
+            return;
+        }
+
+        let mut unpinned = None;
+
+        ui.horizontal_wrapped(|ui| {
+            for (character, color) in self.pinned.iter().zip(PALETTE) {
+                ui.label(RichText::new("■").color(color));
+                ui.label(&character.name);
+                element_badge(ui, character.element);
+                ui.label(character.position.to_str());
+
+                if ui.small_button("x").on_hover_text("Unpin").clicked() {
+                    unpinned = Some(character.number);
+                }
+
+                ui.separator();
+            }
+        });
+
+        if let Some(number) = unpinned {
+            self.unpin(number);
+        }
+
+        if self.pinned.len() < 2 {
+            ui.label("Pin at least one more character to compare.");
+            return;
+        }
+
+        let modified = self.pinned.iter().any(|character| modifiers.effective(character).is_some());
+        if modified {
+            ui.checkbox(&mut self.effective, "Effective stats")
+                .on_hover_text("Apply the modifiers attached to the characters");
+        }
+
+        let characters: Vec<Character> = if self.effective {
+            self.pinned.iter().map(|character| modifiers.apply(character)).collect()
+        } else {
+            self.pinned.clone()
+        };
+
+        ui.separator();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.horizontal_top(|ui| {
+                radar_chart(ui, &characters);
+
+                ui.vertical(|ui| {
+                    render_stat_table(settings, &characters, ui);
+                });
+            });
+        });
+    }
+}
+
+/// Table of every stat and derived value, highlighting the best value of each
+/// row and showing the difference with the first character.
+fn render_stat_table(settings: &SettingsPage, characters: &[Character], ui: &mut egui::Ui) {
+    let mut rows: Vec<(String, Vec<Option<f64>>)> = STAT_LIST
+        .iter()
+        .map(|stat| {
+            let values = characters
+                .iter()
+                .map(|character| character.stats.as_ref().map(|stats| stats.get(*stat) as f64))
+                .collect();
+            (stat.to_str().to_string(), values)
+        })
+        .collect();
+
+    rows.extend(settings.derived_columns.iter().map(|column| {
+        let values = characters.iter().map(|character| column.eval(character)).collect();
+        (column.name.clone(), values)
+    }));
+
+    let mut wins = vec![0; characters.len()];
+
+    egui::Grid::new("compare_table").striped(true).spacing([16.0, 4.0]).show(ui, |ui| {
+        ui.label("");
+        for (character, color) in characters.iter().zip(PALETTE) {
+            ui.label(RichText::new(&character.name).color(color).strong());
+        }
+        ui.end_row();
+
+        for (label, values) in &rows {
+            ui.label(label);
+
+            let best = values.iter().flatten().copied().fold(f64::NEG_INFINITY, f64::max);
+            let base = values[0];
+
+            for (index, value) in values.iter().enumerate() {
+                let Some(value) = value else {
+                    ui.label("-");
+                    continue;
+                };
+
+                let mut text = format_value(*value);
+                if let (Some(base), true) = (base, index > 0) {
+                    let delta = value - base;
+                    if delta != 0.0 {
+                        text.push_str(&format!(" ({}{})", if delta > 0.0 { "+" } else { "" }, format_value(delta)));
+                    }
+                }
+
+                if *value == best {
+                    wins[index] += 1;
+                    ui.label(RichText::new(text).strong().color(ui.visuals().warn_fg_color));
+                } else {
+                    ui.label(text);
+                }
+            }
+
+            ui.end_row();
+        }
+
+        ui.label(RichText::new("Best in").strong());
+        for count in &wins {
+            ui.label(RichText::new(format!("{count} / {}", rows.len())).strong());
+        }
+        ui.end_row();
+    });
+}
+
+/// Overlays the seven stats of each character on a radar chart. The scale
+/// goes from 0 to the highest pinned stat, rounded up to the next ten.
+fn radar_chart(ui: &mut egui::Ui, characters: &[Character]) {
+    let size = ui.available_width().clamp(240.0, 420.0);
+    let (response, painter) = ui.allocate_painter(Vec2::splat(size), Sense::hover());
+
+    let center = response.rect.center();
+    let radius = size * 0.36;
+    let axis_count = STAT_LIST.len();
+
+    let max = characters
+        .iter()
+        .filter_map(|character| character.stats.as_ref())
+        .flat_map(|stats| STAT_LIST.map(|stat| stats.get(stat)))
+        .max()
+        .unwrap_or(0);
+    let scale = (max as f32 / 10.0).ceil().max(1.0) * 10.0;
+
+    let point = |axis: usize, fraction: f32| -> Pos2 {
+        let angle = -FRAC_PI_2 + axis as f32 * TAU / axis_count as f32;
+        center + Vec2::angled(angle) * radius * fraction
+    };
+
+    let grid_stroke = Stroke::new(1.0, ui.visuals().weak_text_color());
+
+    for level in 1..=4 {
+        let fraction = level as f32 / 4.0;
+        let ring = (0..axis_count).map(|axis| point(axis, fraction)).collect();
+        painter.add(Shape::closed_line(ring, grid_stroke));
+    }
+
+    for (axis, stat) in STAT_LIST.iter().enumerate() {
+        painter.line_segment([center, point(axis, 1.0)], grid_stroke);
+        painter.text(
+            point(axis, 1.18),
+            Align2::CENTER_CENTER,
+            stat.to_str(),
+            FontId::proportional(13.0),
+            ui.visuals().text_color(),
+        );
+    }
+
+    painter.text(
+        point(0, 1.0) + Vec2::new(4.0, 0.0),
+        Align2::LEFT_CENTER,
+        format!("{scale:.0}"),
+        FontId::proportional(11.0),
+        ui.visuals().weak_text_color(),
+    );
+
+    for (character, color) in characters.iter().zip(PALETTE) {
+        let Some(stats) = &character.stats else {
+            continue;
+        };
+
+        let points: Vec<Pos2> = STAT_LIST
+            .iter()
+            .enumerate()
+            .map(|(axis, stat)| point(axis, stats.get(*stat) as f32 / scale))
+            .collect();
+
+        // The polygon may be concave, so it is filled as a fan of triangles
+        // around the center, which it always contains.
+        let fill = color.gamma_multiply(0.15);
+        for axis in 0..axis_count {
+            let next = (axis + 1) % axis_count;
+            painter.add(Shape::convex_polygon(vec![center, points[axis], points[next]], fill, Stroke::NONE));
+        }
+
+        painter.add(Shape::closed_line(points, Stroke::new(2.0, color)));
+    }
+}
@@ -0,0 +1,211 @@
+use eframe::egui::{self, DragValue, ProgressBar, RichText};
+
+use crate::{
+    duel::{DEFAULT_SCALE, DuelSide, ENCOUNTER_LIST, Encounter, estimate},
+    elements::AffinityTable,
+    modifiers::ModifierSet,
+    pages::{DuelPage, characters_page::format_value, elements_page::character_picker},
+    query::{DerivedColumn, Query},
+    team::find,
+};
+
+impl DuelPage {
+    pub fn render(&mut self, modifiers: &ModifierSet, affinity: &AffinityTable, ui: &mut egui::Ui) {
+        ui.heading("Duels");
+        ui.label("Estimates who wins a one-on-one encounter from formulas over the stats of both characters.");
+
+        ui.separator();
+
+        if self.characters.is_none() {
+            self.characters = Some(self.database.query_characters(&Query::All));
+        }
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                for encounter in ENCOUNTER_LIST {
+                    ui.selectable_value(&mut self.encounter, encounter, encounter.to_str());
+                }
+            });
+
+            ui.checkbox(&mut self.use_elements, "Apply the element affinities of the Elements page");
+
+            ui.separator();
+
+            self.render_sides(ui);
+
+            ui.separator();
+
+            self.render_estimate(modifiers, affinity, ui);
+
+            ui.separator();
+
+            self.render_formulas(ui);
+        });
+    }
+
+    fn render_sides(&mut self, ui: &mut egui::Ui) {
+        let characters = self.characters.as_deref().unwrap_or_default();
+        let (attacker_label, defender_label) = self.encounter.sides();
+
+        ui.horizontal_top(|ui| {
+            ui.vertical(|ui| {
+                ui.label(RichText::new(attacker_label).strong());
+                character_picker(ui, "duel_attacker", &mut self.attacker_filter, &mut self.attacker, characters);
+
+                ui.horizontal(|ui| {
+                    ui.label("Technique power");
+                    ui.add(DragValue::new(&mut self.attacker_power).range(0.0..=999.0));
+                });
+            });
+
+            ui.vertical(|ui| {
+                ui.label(RichText::new(defender_label).strong());
+                character_picker(ui, "duel_defender", &mut self.defender_filter, &mut self.defender, characters);
+
+                ui.horizontal(|ui| {
+                    ui.label("Technique power");
+                    ui.add(DragValue::new(&mut self.defender_power).range(0.0..=999.0));
+                });
+            });
+        });
+    }
+
+    /// Outcome of the selected encounter, then of every encounter for the
+    /// same pair without techniques.
+    fn render_estimate(&self, modifiers: &ModifierSet, affinity: &AffinityTable, ui: &mut egui::Ui) {
+        let characters = self.characters.as_deref().unwrap_or_default();
+
+        let (Some(attacker), Some(defender)) = (
+            self.attacker.and_then(|number| find(characters, number)),
+            self.defender.and_then(|number| find(characters, number)),
+        ) else {
+            ui.weak("Pick both characters.");
+            return;
+        };
+
+        // The modifiers attached to the characters count in the duel
+        let (attacker, defender) = (modifiers.apply(attacker), modifiers.apply(defender));
+        let affinity = self.use_elements.then_some(affinity);
+
+        let outcome = |encounter: Encounter, attacker_power: f64, defender_power: f64| {
+            estimate(
+                &self.formulas[encounter as usize],
+                DuelSide { character: &attacker, power: attacker_power },
+                DuelSide { character: &defender, power: defender_power },
+                affinity,
+                self.scale,
+            )
+        };
+
+        let Some(result) = outcome(self.encounter, self.attacker_power, self.defender_power) else {
+            ui.label("The formulas can't be evaluated, check them and the stats of both characters.");
+            return;
+        };
+
+        let (attacker_label, defender_label) = self.encounter.sides();
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{attacker_label} {}", format_value((result.attacker_score * 10.0).round() / 10.0)));
+            ui.label("vs");
+            ui.label(format!("{defender_label} {}", format_value((result.defender_score * 10.0).round() / 10.0)));
+            ui.label(RichText::new(format!("margin {:+.1}", result.margin())).strong());
+        });
+
+        ui.add(ProgressBar::new(result.probability as f32)
+            .text(format!("{} wins {:.0} % of the time", attacker.name, result.probability * 100.0))
+            .desired_width(360.0));
+
+        ui.add_space(8.0);
+        ui.label(format!("Every encounter, {} attacking, without techniques", attacker.name));
+
+        egui::Grid::new("duel_encounters").striped(true).spacing([16.0, 4.0]).show(ui, |ui| {
+            for header in ["Encounter", "Margin", "Win"] {
+                ui.label(RichText::new(header).strong());
+            }
+            ui.end_row();
+
+            for encounter in ENCOUNTER_LIST {
+                ui.label(encounter.to_str());
+
+                match outcome(encounter, 0.0, 0.0) {
+                    Some(result) => {
+                        ui.label(format!("{:+.1}", result.margin()));
+                        ui.label(format!("{:.0} %", result.probability * 100.0));
+                    }
+                    None => {
+                        ui.label("-");
+                        ui.label("-");
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    }
+
+    fn render_formulas(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Formulas");
+        ui.label("Formulas over kick, control, technique, pressure, physical, agility and intelligence. \
+            The technique power is added to the result.");
+
+        let mut changed = false;
+
+        egui::Grid::new("duel_formulas").num_columns(3).show(ui, |ui| {
+            for (encounter, formula) in ENCOUNTER_LIST.iter().zip(self.formulas.iter_mut()) {
+                let (attacker_label, defender_label) = encounter.sides();
+
+                ui.label(encounter.to_str());
+                for (label, column) in [(attacker_label, &mut formula.attacker), (defender_label, &mut formula.defender)] {
+                    ui.vertical(|ui| {
+                        ui.weak(label);
+                        changed |= formula_field(ui, column);
+                    });
+                }
+
+                if ui.button("Reset").clicked() {
+                    *formula = encounter.default_formula();
+                    changed = true;
+                }
+                ui.end_row();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Scale");
+            changed |= ui.add(DragValue::new(&mut self.scale).range(1.0..=500.0)).changed();
+            ui.weak("margin at which the attacker wins 73 % of the time");
+
+            if ui.small_button("Reset").clicked() {
+                self.scale = DEFAULT_SCALE;
+                changed = true;
+            }
+        });
+
+        if changed {
+            self.save_formulas();
+        }
+    }
+
+    fn save_formulas(&self) {
+        for (encounter, formula) in ENCOUNTER_LIST.iter().zip(&self.formulas) {
+            self.database.set_setting(encounter.setting_key(), &formula.to_spec());
+        }
+        self.database.set_setting("duel_scale", &self.scale.to_string());
+    }
+}
+
+/// Text field editing a formula, showing its parse error. Returns whether it
+/// changed.
+fn formula_field(ui: &mut egui::Ui, column: &mut DerivedColumn) -> bool {
+    let mut formula = column.formula().to_string();
+    let changed = ui.add(egui::TextEdit::singleline(&mut formula).desired_width(240.0)).changed();
+
+    if changed {
+        column.set_formula(formula);
+    }
+
+    if let Some(error) = column.error() {
+        ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+    }
+
+    changed
+}
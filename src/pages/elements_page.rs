@@ -0,0 +1,300 @@
+use eframe::egui::{self, DragValue, RichText};
+
+use crate::{
+    elements::{AffinityTable, coverage, coverage_warnings, matchup},
+    modifiers::ModifierSet,
+    pages::{ElementsPage, characters_page::{element_badge, format_value}},
+    query::Query,
+    team::find,
+    utils::{Character, ELEMENT_LIST, Position, Stat},
+};
+
+/// Number of matching characters listed when picking one.
+const PICKER_SUGGESTIONS: usize = 8;
+
+/// Characters whose element coverage is analyzed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoverageSource {
+    MyTeam,
+    /// A saved team, by id.
+    Team(i64),
+}
+
+impl ElementsPage {
+    pub fn affinity(&self) -> &AffinityTable {
+        &self.affinity
+    }
+
+    pub fn render(&mut self, modifiers: &ModifierSet, ui: &mut egui::Ui) {
+        ui.heading("Elements");
+        ui.label("Element affinities, matchups between two characters and the coverage of a team.");
+
+        ui.separator();
+
+        if self.characters.is_none() {
+            self.characters = Some(self.database.query_characters(&Query::All));
+        }
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            self.render_affinity_table(ui);
+
+            ui.separator();
+
+            self.render_calculator(modifiers, ui);
+
+            ui.separator();
+
+            self.render_coverage(ui);
+        });
+    }
+
+    fn render_affinity_table(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("Affinities");
+
+            if ui.small_button("Reset").clicked() {
+                self.affinity = AffinityTable::default();
+                self.save_affinity();
+            }
+        });
+        ui.label("Multiplier of the stats of the attacker (row) against the defender (column).");
+
+        let mut changed = false;
+
+        egui::Grid::new("affinity_table").striped(true).spacing([12.0, 4.0]).show(ui, |ui| {
+            ui.label("");
+            for defender in ELEMENT_LIST {
+                element_badge(ui, defender);
+            }
+            ui.end_row();
+
+            for attacker in ELEMENT_LIST {
+                element_badge(ui, attacker);
+
+                for defender in ELEMENT_LIST {
+                    if let Some(multiplier) = self.affinity.get_mut(attacker, defender) {
+                        changed |= ui
+                            .add(DragValue::new(multiplier).range(0.0..=5.0).speed(0.01).max_decimals(2))
+                            .changed();
+                    }
+                }
+                ui.end_row();
+            }
+        });
+
+        if changed {
+            self.save_affinity();
+        }
+    }
+
+    fn save_affinity(&self) {
+        self.database.set_setting("element_affinity", &self.affinity.to_spec());
+    }
+
+    /// Adjusted comparison of the stats of two characters, the modifiers
+    /// attached to them being applied.
+    fn render_calculator(&mut self, modifiers: &ModifierSet, ui: &mut egui::Ui) {
+        ui.heading("Matchup");
+
+        let characters = self.characters.as_deref().unwrap_or_default();
+
+        ui.horizontal_top(|ui| {
+            ui.vertical(|ui| {
+                ui.label(RichText::new("Attacker").strong());
+                character_picker(ui, "matchup_attacker", &mut self.attacker_filter, &mut self.attacker, characters);
+            });
+
+            ui.vertical(|ui| {
+                ui.label(RichText::new("Defender").strong());
+                character_picker(ui, "matchup_defender", &mut self.defender_filter, &mut self.defender, characters);
+            });
+        });
+
+        let (Some(attacker), Some(defender)) = (
+            self.attacker.and_then(|number| find(characters, number)),
+            self.defender.and_then(|number| find(characters, number)),
+        ) else {
+            ui.weak("Pick an attacker and a defender.");
+            return;
+        };
+
+        let (attacker, defender) = (modifiers.apply(attacker), modifiers.apply(defender));
+
+        ui.horizontal(|ui| {
+            element_badge(ui, attacker.element);
+            ui.label("against");
+            element_badge(ui, defender.element);
+            ui.label(format!(
+                "{}: ×{} for {}, ×{} for {}",
+                self.affinity.affinity(attacker.element, defender.element).to_str(),
+                format_value(self.affinity.get(attacker.element, defender.element)),
+                attacker.name,
+                format_value(self.affinity.get(defender.element, attacker.element)),
+                defender.name,
+            ));
+        });
+
+        let rows = matchup(&attacker, &defender, &self.affinity);
+        if rows.is_empty() {
+            ui.label("Both characters need stats to be compared.");
+            return;
+        }
+
+        egui::Grid::new("matchup_table").striped(true).spacing([16.0, 4.0]).show(ui, |ui| {
+            for header in ["", attacker.name.as_str(), defender.name.as_str(), "Adjusted", "Margin"] {
+                ui.label(RichText::new(header).strong());
+            }
+            ui.end_row();
+
+            for row in &rows {
+                let margin = row.margin();
+
+                let (attacking, defending) = row.encounter.stats();
+                let names = |stats: [Stat; 2]| stats.map(Stat::to_str).join(" + ");
+                ui.label(row.encounter.to_str())
+                    .on_hover_text(format!("{} vs {}", names(attacking), names(defending)));
+                ui.label(row.attacker.to_string());
+                ui.label(row.defender.to_string());
+                ui.label(format!(
+                    "{} vs {}",
+                    format_value((row.adjusted_attacker * 10.0).round() / 10.0),
+                    format_value((row.adjusted_defender * 10.0).round() / 10.0),
+                ));
+
+                let text = format!("{margin:+.1}");
+                if margin > 0.0 {
+                    ui.label(RichText::new(text).color(ui.visuals().warn_fg_color).strong());
+                } else {
+                    ui.label(text);
+                }
+                ui.end_row();
+            }
+        });
+    }
+
+    fn render_coverage(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("Team coverage");
+
+            let selected = match self.coverage_source {
+                CoverageSource::MyTeam => "My Team".to_string(),
+                CoverageSource::Team(id) => self.teams
+                    .iter()
+                    .find(|team| team.id == Some(id))
+                    .map_or("Deleted team".to_string(), |team| team.name.clone()),
+            };
+
+            egui::ComboBox::from_id_salt("coverage_source")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.coverage_source, CoverageSource::MyTeam, "My Team");
+                    for team in &self.teams {
+                        if let Some(id) = team.id {
+                            ui.selectable_value(&mut self.coverage_source, CoverageSource::Team(id), &team.name);
+                        }
+                    }
+                });
+
+            if ui.small_button("Reload").on_hover_text("Load the teams and My Team again").clicked() {
+                self.teams = self.database.teams();
+                self.my_team = self.database.my_team();
+            }
+        });
+
+        let characters = self.characters.as_deref().unwrap_or_default();
+
+        // Starters of a team play the position of their slot, the other
+        // characters their own
+        let players: Vec<(&Character, Position)> = match self.coverage_source {
+            CoverageSource::MyTeam => self.my_team
+                .iter()
+                .filter_map(|number| find(characters, *number))
+                .map(|character| (character, character.position))
+                .collect(),
+            CoverageSource::Team(id) => match self.teams.iter().find(|team| team.id == Some(id)) {
+                Some(team) => team.formation
+                    .slots()
+                    .into_iter()
+                    .zip(team.players)
+                    .filter_map(|(position, number)| Some((find(characters, number?)?, position)))
+                    .collect(),
+                None => Vec::new(),
+            },
+        };
+
+        if players.is_empty() {
+            ui.label("No cached character in this team.");
+            return;
+        }
+
+        let coverage = coverage(&players, &self.affinity);
+
+        ui.label("Players beating / beaten by each element");
+
+        egui::Grid::new("coverage_table").striped(true).spacing([16.0, 4.0]).show(ui, |ui| {
+            for header in ["Against", "Defense", "Midfield", "Attack"] {
+                ui.label(RichText::new(header).strong());
+            }
+            ui.end_row();
+
+            for entry in &coverage {
+                element_badge(ui, entry.element);
+
+                for line in [&entry.defense, &entry.midfield, &entry.attack] {
+                    let text = format!("{} / {} of {}", line.strong, line.weak, line.total);
+                    if line.is_weak() {
+                        ui.colored_label(ui.visuals().error_fg_color, text);
+                    } else {
+                        ui.label(text);
+                    }
+                }
+                ui.end_row();
+            }
+        });
+
+        let warnings = coverage_warnings(&coverage);
+        if warnings.is_empty() {
+            ui.label("No element has the upper hand on this team.");
+        }
+        for warning in warnings {
+            ui.colored_label(ui.visuals().warn_fg_color, warning);
+        }
+    }
+}
+
+/// Text field finding a cached character with a query, listing the matching
+/// ones to pick from.
+pub fn character_picker(ui: &mut egui::Ui, id: &str, filter: &mut String, selected: &mut Option<u16>, characters: &[Character]) {
+    if let Some(character) = selected.and_then(|number| find(characters, number)) {
+        ui.horizontal(|ui| {
+            element_badge(ui, character.element);
+            ui.label(RichText::new(&character.name).strong());
+            ui.weak(character.position.to_str());
+
+            if ui.small_button("x").on_hover_text("Pick another character").clicked() {
+                *selected = None;
+            }
+        });
+        return;
+    }
+
+    ui.add(egui::TextEdit::singleline(filter).id_salt(id).hint_text("Query, e.g. name~\"Go\" pos:FW"));
+
+    let Ok(query) = Query::parse(filter) else {
+        return;
+    };
+    if filter.trim().is_empty() {
+        return;
+    }
+
+    for character in characters.iter().filter(|character| query.matches(character)).take(PICKER_SUGGESTIONS) {
+        ui.horizontal(|ui| {
+            if ui.small_button("Pick").clicked() {
+                *selected = Some(character.number);
+            }
+            element_badge(ui, character.element);
+            ui.label(&character.name);
+            ui.weak(character.position.to_str());
+        });
+    }
+}
@@ -0,0 +1,263 @@
+use eframe::egui::{self, DragValue, RichText};
+
+use crate::{
+    modifiers::{BoostKind, Modifier, ModifierScope, ModifierSet, StatBoost},
+    pages::CharactersPage,
+    team::Team,
+    utils::{STAT_LIST, Stat},
+};
+
+/// Window where the modifiers of the profile are defined and attached to
+/// groups of characters, in the roster or only within a saved team.
+pub struct ModifierEditor {
+    /// Working copies of the modifiers, saved when asked.
+    modifiers: Vec<Modifier>,
+    new_name: String,
+    /// Outcome of the last save.
+    message: Option<String>,
+    /// Saved teams, whose members a modifier can be attached to.
+    teams: Vec<Team>,
+}
+
+impl CharactersPage {
+    pub fn modifiers(&self) -> &ModifierSet {
+        &self.modifiers
+    }
+
+    pub fn render_modifiers_menu(&mut self, ui: &mut egui::Ui) {
+        if ui.button("Modifiers").on_hover_text("Equipment and training boosting the stats").clicked() {
+            self.modifier_editor = Some(ModifierEditor {
+                modifiers: self.modifiers.modifiers.clone(),
+                new_name: String::new(),
+                message: None,
+                teams: self.character_cache.teams(),
+            });
+        }
+    }
+
+    pub fn render_modifiers_window(&mut self, ui: &mut egui::Ui) {
+        let Some(editor) = &mut self.modifier_editor else {
+            return;
+        };
+
+        let mut open = true;
+        let mut saved = None;
+        let mut deleted = None;
+        let mut attach = None;
+        let mut detached = None;
+
+        egui::Window::new("Modifiers")
+            .open(&mut open)
+            .default_width(460.0)
+            .show(ui.ctx(), |ui| {
+                ui.label("Boosts from equipment or training. The flat boosts are added first, then the percentages.");
+
+                if let Some(message) = &editor.message {
+                    ui.weak(message);
+                }
+
+                let names: Vec<String> = editor.modifiers.iter().map(|modifier| modifier.name.trim().to_string()).collect();
+
+                egui::ScrollArea::vertical().max_height(480.0).show(ui, |ui| {
+                    for (index, modifier) in editor.modifiers.iter_mut().enumerate() {
+                        ui.separator();
+
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(&mut modifier.name).desired_width(160.0));
+
+                            let name = modifier.name.trim();
+                            let taken = names.iter().enumerate().any(|(other, other_name)| other != index && other_name == name);
+                            let valid = !name.is_empty() && !taken;
+
+                            let save = ui
+                                .add_enabled(valid, egui::Button::new("Save"))
+                                .on_disabled_hover_text(if taken { "Another modifier has this name" } else { "The name is empty" });
+                            if save.clicked() {
+                                saved = Some(index);
+                            }
+
+                            if let Some(id) = modifier.id
+                                && ui.button("Delete").clicked()
+                            {
+                                deleted = Some(id);
+                            }
+                        });
+
+                        boosts_editor(ui, index, &mut modifier.boosts);
+
+                        let Some(id) = modifier.id else {
+                            ui.weak("Save the modifier to attach it to characters.");
+                            continue;
+                        };
+
+                        let attached = self.modifiers.attached_count(id);
+
+                        ui.horizontal(|ui| {
+                            ui.weak(format!("Attached to {attached} characters"));
+
+                            ui.menu_button("Attach to", |ui| {
+                                if ui.button("Every owned character").clicked() {
+                                    let owned = self.roster.iter().map(|entry| entry.character_id).collect();
+                                    attach = Some((id, ModifierScope::Roster, owned, true));
+                                    ui.close();
+                                }
+
+                                if ui.button("My Team").clicked() {
+                                    attach = Some((id, ModifierScope::Roster, self.my_team.clone(), true));
+                                    ui.close();
+                                }
+
+                                // Only applies while the members play in the team
+                                for team in &editor.teams {
+                                    let Some(team_id) = team.id else {
+                                        continue;
+                                    };
+
+                                    let button = ui
+                                        .button(format!("Team {}", team.name))
+                                        .on_hover_text("Only applies to the members within this team");
+                                    if button.clicked() {
+                                        attach = Some((id, ModifierScope::Team(team_id), team.members(), true));
+                                        ui.close();
+                                    }
+                                }
+                            });
+
+                            if ui.add_enabled(attached > 0, egui::Button::new("Detach from all")).clicked() {
+                                detached = Some(id);
+                            }
+                        });
+                    }
+                });
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut editor.new_name).hint_text("New modifier"));
+
+                    let name = editor.new_name.trim().to_string();
+                    let taken = names.contains(&name);
+
+                    if ui.add_enabled(!name.is_empty() && !taken, egui::Button::new("Add")).clicked() {
+                        editor.modifiers.push(Modifier::new(&name));
+                        editor.new_name.clear();
+                    }
+                });
+            });
+
+        if let Some(index) = saved {
+            let modifier = &mut editor.modifiers[index];
+            modifier.name = modifier.name.trim().to_string();
+
+            editor.message = Some(match self.character_cache.save_modifier(modifier) {
+                Some(id) => {
+                    modifier.id = Some(id);
+                    format!("Saved \"{}\"", modifier.name)
+                }
+                None => format!("The name \"{}\" is already used by another modifier", modifier.name),
+            });
+        }
+
+        if let Some(id) = deleted {
+            self.character_cache.delete_modifier(id);
+            editor.modifiers.retain(|modifier| modifier.id != Some(id));
+        }
+
+        let changed = saved.is_some() || deleted.is_some() || attach.is_some() || detached.is_some();
+
+        if let Some((id, scope, numbers, attached)) = attach {
+            self.character_cache.set_modifier_attached(id, scope, &numbers, attached);
+        }
+
+        if let Some(id) = detached {
+            self.character_cache.detach_modifier(id);
+        }
+
+        if changed {
+            self.modifiers = self.character_cache.modifier_set();
+        }
+
+        if !open {
+            self.modifier_editor = None;
+        }
+    }
+
+    /// Modifiers attached to a character in the detail panel, with a checkbox
+    /// per modifier of the profile. The checkboxes attach in the roster;
+    /// modifiers only attached within teams are listed below them.
+    pub fn render_modifier_section(&mut self, ui: &mut egui::Ui, number: u16) {
+        ui.label(RichText::new("Modifiers").strong());
+
+        if self.modifiers.modifiers.is_empty() {
+            ui.weak("No modifier yet, define them from the Modifiers window");
+            return;
+        }
+
+        let mut toggled = None;
+
+        for modifier in &self.modifiers.modifiers {
+            let Some(id) = modifier.id else {
+                continue;
+            };
+
+            let mut attached = self.modifiers.is_attached(number, ModifierScope::Roster, id);
+            if ui.checkbox(&mut attached, &modifier.name).on_hover_text(modifier.describe()).changed() {
+                toggled = Some((id, attached));
+            }
+        }
+
+        let in_teams = self
+            .modifiers
+            .attached
+            .get(&number)
+            .map(|links| links.iter().filter(|(scope, _)| *scope != ModifierScope::Roster).count())
+            .unwrap_or_default();
+        if in_teams > 0 {
+            ui.weak(format!("Plus {in_teams} modifiers only within saved teams"));
+        }
+
+        if let Some((id, attached)) = toggled {
+            self.character_cache.set_modifier_attached(id, ModifierScope::Roster, &[number], attached);
+            self.modifiers = self.character_cache.modifier_set();
+        }
+    }
+}
+
+fn boosts_editor(ui: &mut egui::Ui, index: usize, boosts: &mut Vec<StatBoost>) {
+    let mut removed = None;
+
+    egui::Grid::new(("modifier_boosts", index)).show(ui, |ui| {
+        for (row, boost) in boosts.iter_mut().enumerate() {
+            egui::ComboBox::from_id_salt(("boost_stat", index, row))
+                .selected_text(boost.stat.to_str())
+                .show_ui(ui, |ui| {
+                    for stat in STAT_LIST {
+                        ui.selectable_value(&mut boost.stat, stat, stat.to_str());
+                    }
+                });
+
+            ui.add(DragValue::new(&mut boost.amount).range(-255..=255));
+
+            egui::ComboBox::from_id_salt(("boost_kind", index, row))
+                .selected_text(boost.kind.to_str())
+                .show_ui(ui, |ui| {
+                    for kind in [BoostKind::Flat, BoostKind::Percent] {
+                        ui.selectable_value(&mut boost.kind, kind, kind.to_str());
+                    }
+                });
+
+            if ui.small_button("x").on_hover_text("Remove the boost").clicked() {
+                removed = Some(row);
+            }
+            ui.end_row();
+        }
+    });
+
+    if let Some(row) = removed {
+        boosts.remove(row);
+    }
+
+    if ui.small_button("Add boost").clicked() {
+        boosts.push(StatBoost { stat: Stat::Kick, kind: BoostKind::Flat, amount: 5 });
+    }
+}
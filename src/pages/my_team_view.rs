@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use eframe::egui;
+
+use crate::pages::CharactersPage;
+
+/// My Team as the website keeps it: a JSON array of the values of its My Team
+/// checkboxes. Characters whose key wasn't read yet are left out, along with
+/// the count of them.
+fn export(numbers: &[u16], keys: &HashMap<u16, String>) -> (String, usize) {
+    let exported: Vec<String> = numbers
+        .iter()
+        .filter_map(|number| keys.get(number))
+        .map(|key| format!("\"{}\"", key.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+
+    (format!("[{}]", exported.join(",")), numbers.len() - exported.len())
+}
+
+/// Reads the characters of a My Team exported by the website or by `export`.
+/// Every word of the text is looked up among the My Team keys of the website,
+/// then among the character numbers, so that JSON arrays, comma-separated
+/// lists and one entry per line are all accepted. Repeated characters are
+/// dropped.
+fn parse(text: &str, keys: &HashMap<u16, String>) -> Vec<u16> {
+    let by_key: HashMap<&str, u16> = keys.iter().map(|(number, key)| (key.as_str(), *number)).collect();
+    let mut numbers = Vec::new();
+
+    let words = text
+        .split(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | '[' | ']' | '"' | '\''))
+        .filter(|word| !word.is_empty());
+
+    for word in words {
+        let number = by_key.get(word).copied().or_else(|| word.parse().ok());
+
+        if let Some(number) = number
+            && !numbers.contains(&number)
+        {
+            numbers.push(number);
+        }
+    }
+
+    numbers
+}
+
+impl CharactersPage {
+    pub fn is_in_my_team(&self, number: u16) -> bool {
+        self.my_team.contains(&number)
+    }
+
+    pub fn toggle_my_team(&mut self, number: u16) {
+        let in_team = !self.is_in_my_team(number);
+        self.character_cache.set_in_my_team(number, in_team);
+        self.my_team = self.character_cache.my_team();
+    }
+
+    pub fn render_my_team_menu(&mut self, ui: &mut egui::Ui) {
+        let title = if self.my_team_only { "My Team *" } else { "My Team" };
+
+        ui.menu_button(title, |ui| {
+            ui.label(format!("{} characters", self.my_team.len()));
+
+            ui.checkbox(&mut self.my_team_only, "Only show My Team");
+
+            if ui.button("Load from cache").on_hover_text("Replace the results with the cached characters of My Team").clicked() {
+                self.characters = self.character_cache.my_team_characters();
+                self.progress = None;
+                self.characters_changed();
+                ui.close();
+            }
+
+            ui.separator();
+
+            if ui.button("Export").on_hover_text("Copy My Team in the format of the website").clicked() {
+                let (text, missing) = export(&self.my_team, &self.character_cache.my_team_keys());
+                ui.ctx().copy_text(text);
+
+                self.my_team_message = (missing > 0)
+                    .then(|| format!("{missing} characters were left out, search them online first so their key is known"));
+            }
+
+            if let Some(message) = &self.my_team_message {
+                ui.weak(message);
+            }
+
+            ui.label("Paste My Team as exported by the website, or character numbers");
+            ui.add(egui::TextEdit::multiline(&mut self.my_team_import).desired_rows(3));
+
+            let mut changed = false;
+
+            ui.horizontal(|ui| {
+                let filled = !self.my_team_import.trim().is_empty();
+                let replace = ui.add_enabled(filled, egui::Button::new("Replace")).clicked();
+                let add = ui.add_enabled(filled, egui::Button::new("Add")).clicked();
+
+                if replace || add {
+                    let mut numbers = parse(&self.my_team_import, &self.character_cache.my_team_keys());
+
+                    if add {
+                        numbers.retain(|number| !self.my_team.contains(number));
+                        numbers.splice(0..0, self.my_team.iter().copied());
+                    }
+
+                    self.character_cache.replace_my_team(&numbers);
+                    self.my_team_import.clear();
+                    changed = true;
+                }
+
+                if ui.button("Clear").clicked() {
+                    self.character_cache.replace_my_team(&[]);
+                    changed = true;
+                }
+            });
+
+            if changed {
+                self.my_team = self.character_cache.my_team();
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_round_trips_through_keys() {
+        let keys = HashMap::from([(12, "c-0012".to_string()), (7, "c-0007".to_string())]);
+
+        let (text, missing) = export(&[12, 7, 99], &keys);
+
+        assert_eq!(text, r#"["c-0012","c-0007"]"#);
+        assert_eq!(missing, 1);
+        assert_eq!(parse(&text, &keys), vec![12, 7]);
+        assert_eq!(parse("[\"c-0012\", 99, 12]", &keys), vec![12, 99]);
+    }
+}
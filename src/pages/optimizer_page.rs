@@ -0,0 +1,287 @@
+use eframe::egui::{self, DragValue, RichText};
+
+use crate::{
+    pages::{OptimizerPage, TeamPage, characters_page::{element_badge, format_value}},
+    query::Query,
+    roster::Ownership,
+    team::{FORMATION_LIST, OptimizerConfig, find},
+    utils::{Character, ELEMENT_LIST, GAME_LIST, POSITION_LIST, STAT_LIST},
+};
+
+/// Number of matching characters listed when looking for one to ban.
+const BAN_SUGGESTIONS: usize = 8;
+
+impl OptimizerPage {
+    pub fn render(&mut self, team_page: &mut TeamPage, ui: &mut egui::Ui) {
+        ui.heading("Team optimizer");
+        ui.label("Picks the eleven maximizing the weighted sum of the stats of each slot among the cached characters.");
+
+        ui.separator();
+
+        egui::SidePanel::left("optimizer_constraints")
+            .resizable(true)
+            .default_width(420.0)
+            .show_inside(ui, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    self.render_constraints(ui);
+                });
+            });
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            self.render_results(team_page, ui);
+        });
+    }
+
+    fn render_constraints(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Formation")
+                .selected_text(self.config.formation.name)
+                .show_ui(ui, |ui| {
+                    for formation in FORMATION_LIST {
+                        // Slot weights follow the slots of the old formation
+                        if ui.selectable_value(&mut self.config.formation, formation, formation.name).changed() {
+                            self.config.slot_weights = OptimizerConfig::default().slot_weights;
+                        }
+                    }
+                });
+
+            ui.label("Alternatives");
+            ui.add(DragValue::new(&mut self.config.alternatives).range(1..=20));
+        });
+
+        ui.horizontal(|ui| {
+            let mut off_position = self.config.off_position_factor.is_some();
+            if ui.checkbox(&mut off_position, "Allow players out of position").changed() {
+                self.config.off_position_factor = off_position.then_some(0.8);
+            }
+
+            if let Some(factor) = &mut self.config.off_position_factor {
+                ui.add(egui::Slider::new(factor, 0.1..=1.0).text("score factor"));
+            }
+        });
+
+        ui.separator();
+
+        ui.label(RichText::new("Minimum players per element").strong());
+        ui.horizontal(|ui| {
+            for (element, min) in ELEMENT_LIST.iter().zip(self.config.min_elements.iter_mut()) {
+                element_badge(ui, *element);
+                ui.add(DragValue::new(min).range(0..=11));
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Roster").strong());
+            for ownership in [Ownership::Any, Ownership::Owned, Ownership::NotOwned] {
+                ui.radio_value(&mut self.ownership, ownership, ownership.to_str());
+            }
+        });
+
+        ui.label(RichText::new("Games").strong());
+        ui.horizontal_wrapped(|ui| {
+            for game in &GAME_LIST {
+                let checked = self.config.games.contains(game.flag());
+
+                if ui.selectable_label(checked, game.to_str()).clicked() {
+                    self.config.games.toggle(game.flag());
+                }
+            }
+        });
+        if !self.config.games.is_empty() {
+            ui.weak("The games of a character are learned from searches filtered on a single game.");
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Stat weights per position").strong());
+
+            if ui.small_button("Reset").clicked() {
+                self.config.weights = OptimizerConfig::default().weights;
+                self.config.slot_weights = OptimizerConfig::default().slot_weights;
+            }
+        });
+
+        egui::Grid::new("optimizer_weights").striped(true).show(ui, |ui| {
+            ui.label("");
+            for stat in STAT_LIST {
+                ui.label(stat.to_str());
+            }
+            ui.end_row();
+
+            for (position, weights) in POSITION_LIST.iter().zip(self.config.weights.iter_mut()) {
+                ui.label(position.to_str());
+                for weight in weights.iter_mut() {
+                    ui.add(DragValue::new(weight).range(0.0..=5.0).speed(0.05).max_decimals(2));
+                }
+                ui.end_row();
+            }
+        });
+
+        self.render_slot_weights(ui);
+
+        ui.separator();
+
+        self.render_banned(ui);
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.add_enabled(!self.running, egui::Button::new("Optimize")).clicked() {
+                self.run();
+            }
+
+            if self.running {
+                ui.spinner();
+            }
+        });
+    }
+
+    /// Slots given their own weights instead of those of their position.
+    fn render_slot_weights(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Stat weights per slot", |ui| {
+            let slots = self.config.formation.slots();
+
+            egui::Grid::new("optimizer_slot_weights").striped(true).show(ui, |ui| {
+                ui.label("");
+                for stat in STAT_LIST {
+                    ui.label(stat.to_str());
+                }
+                ui.end_row();
+
+                for (slot, position) in slots.iter().enumerate() {
+                    let mut custom = self.config.slot_weights[slot].is_some();
+                    if ui.checkbox(&mut custom, format!("{} {}", position.to_str(), slot + 1)).changed() {
+                        self.config.slot_weights[slot] = custom.then(|| *self.config.weights_for(*position).unwrap_or(&[0.0; 7]));
+                    }
+
+                    if let Some(weights) = &mut self.config.slot_weights[slot] {
+                        for weight in weights.iter_mut() {
+                            ui.add(DragValue::new(weight).range(0.0..=5.0).speed(0.05).max_decimals(2));
+                        }
+                    } else {
+                        ui.weak("Weights of the position");
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    fn render_banned(&mut self, ui: &mut egui::Ui) {
+        ui.label(RichText::new("Banned characters").strong());
+
+        let mut unbanned = None;
+        ui.horizontal_wrapped(|ui| {
+            if self.config.banned.is_empty() {
+                ui.weak("None");
+            }
+
+            for number in &self.config.banned {
+                let name = find(&self.characters, *number).map_or_else(|| format!("#{number}"), |character| character.name.clone());
+                ui.label(name);
+
+                if ui.small_button("x").on_hover_text("Allow again").clicked() {
+                    unbanned = Some(*number);
+                }
+            }
+        });
+
+        if let Some(number) = unbanned {
+            self.config.banned.retain(|other| *other != number);
+        }
+
+        ui.add(egui::TextEdit::singleline(&mut self.ban_filter).hint_text("Find a character to ban"));
+
+        let Ok(query) = Query::parse(&self.ban_filter) else {
+            return;
+        };
+        if self.ban_filter.trim().is_empty() {
+            return;
+        }
+
+        let matching: Vec<&Character> = self.characters
+            .iter()
+            .filter(|character| !self.config.banned.contains(&character.number) && query.matches(character))
+            .take(BAN_SUGGESTIONS)
+            .collect();
+
+        let mut banned = None;
+        for character in matching {
+            ui.horizontal(|ui| {
+                if ui.small_button("Ban").clicked() {
+                    banned = Some(character.number);
+                }
+                element_badge(ui, character.element);
+                ui.label(&character.name);
+                ui.weak(character.position.to_str());
+            });
+        }
+
+        if let Some(number) = banned {
+            self.config.banned.push(number);
+        }
+    }
+
+    fn render_results(&self, team_page: &mut TeamPage, ui: &mut egui::Ui) {
+        let Some(result) = &self.result else {
+            ui.label("Set the constraints and press Optimize.");
+            return;
+        };
+
+        if result.lineups.is_empty() {
+            ui.label("No lineup satisfies the constraints with the cached characters.");
+            return;
+        }
+
+        if !result.complete {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "The search was stopped early, better lineups may exist. Try stricter constraints.",
+            );
+        }
+
+        let slots = self.config.formation.slots();
+
+        for (rank, lineup) in result.lineups.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(format!("#{}", rank + 1)).strong());
+                ui.label(format!("Score {}", format_value((lineup.score * 10.0).round() / 10.0)));
+
+                if ui.button("Open in team builder").clicked() {
+                    let name = format!("Optimized {} #{}", self.config.formation.name, rank + 1);
+                    team_page.open_team(lineup.to_team(&name, self.config.formation));
+                }
+            });
+
+            egui::Grid::new(("optimizer_lineup", rank)).spacing([12.0, 2.0]).show(ui, |ui| {
+                for (slot, (position, number)) in slots.iter().zip(lineup.players).enumerate() {
+                    ui.weak(position.to_str());
+
+                    match find(&self.characters, number) {
+                        Some(character) => {
+                            element_badge(ui, character.element);
+                            ui.label(&character.name);
+
+                            if character.position != *position {
+                                ui.colored_label(ui.visuals().warn_fg_color, format!("plays {}", character.position.to_str()));
+                            } else {
+                                ui.label("");
+                            }
+
+                            let score = self.config.slot_score(character, slot).unwrap_or(0.0);
+                            ui.label(format_value((score * 10.0).round() / 10.0));
+                        }
+                        None => {
+                            ui.label("");
+                            ui.label(format!("#{number}"));
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+
+            ui.separator();
+        }
+    }
+}
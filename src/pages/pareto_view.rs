@@ -0,0 +1,261 @@
+use eframe::egui::{self, Align2, Color32, FontId, Pos2, Rect, Sense, Stroke, Vec2};
+
+use crate::{
+    analysis::pareto_frontier,
+    pages::{CharactersPage, characters_page::element_color},
+    utils::{Character, STAT_LIST, Stat},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParetoMode {
+    Off,
+    /// Dominated characters are dimmed in the table.
+    Mark,
+    /// Dominated characters are hidden from the table.
+    Filter,
+}
+
+/// Pareto frontier of the characters matching the current filters, over a
+/// chosen subset of the stats.
+pub struct ParetoView {
+    pub mode: ParetoMode,
+    stats: Vec<Stat>,
+    frontier: Vec<u16>,
+    /// Characters and stats the frontier was computed for, `None` when it
+    /// must be computed again.
+    computed_for: Option<(Vec<u16>, Vec<Stat>)>,
+
+    plot_open: bool,
+    x: Stat,
+    y: Stat,
+}
+
+impl ParetoView {
+    pub fn new() -> ParetoView {
+        ParetoView {
+            mode: ParetoMode::Off,
+            stats: STAT_LIST.to_vec(),
+            frontier: Vec::new(),
+            computed_for: None,
+
+            plot_open: false,
+            x: Stat::Kick,
+            y: Stat::Agility,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.mode != ParetoMode::Off || self.plot_open
+    }
+
+    pub fn is_dominated(&self, number: u16) -> bool {
+        self.is_active() && !self.frontier.contains(&number)
+    }
+
+    /// Forgets the frontier, e.g. when the stats of a character changed.
+    pub fn invalidate(&mut self) {
+        self.computed_for = None;
+    }
+
+    /// Computes the frontier again if the characters or the stats changed.
+    fn update(&mut self, characters: &[&Character]) {
+        if !self.is_active() {
+            return;
+        }
+
+        let mut numbers: Vec<u16> = characters.iter().map(|character| character.number).collect();
+        numbers.sort_unstable();
+
+        let key = (numbers, self.stats.clone());
+        if self.computed_for.as_ref() == Some(&key) {
+            return;
+        }
+
+        self.frontier = pareto_frontier(characters.iter().copied(), &self.stats);
+        self.computed_for = Some(key);
+    }
+}
+
+impl CharactersPage {
+    /// Keeps the frontier in sync with the characters matching the filters.
+    pub fn update_pareto(&mut self) {
+        let matching: Vec<&Character> = self.characters
+            .iter()
+            .filter(|character| self.matches_filters(character))
+            .collect();
+
+        self.pareto.update(&matching);
+    }
+
+    pub fn render_pareto_menu(&mut self, ui: &mut egui::Ui) {
+        let title = if self.pareto.mode == ParetoMode::Off { "Pareto" } else { "Pareto *" };
+
+        ui.menu_button(title, |ui| {
+            ui.label("Characters no one beats on every selected stat at once.");
+
+            ui.radio_value(&mut self.pareto.mode, ParetoMode::Off, "Off");
+            ui.radio_value(&mut self.pareto.mode, ParetoMode::Mark, "Dim dominated characters");
+            ui.radio_value(&mut self.pareto.mode, ParetoMode::Filter, "Hide dominated characters");
+
+            ui.separator();
+
+            for stat in STAT_LIST {
+                let mut checked = self.pareto.stats.contains(&stat);
+
+                if ui.checkbox(&mut checked, stat.to_str()).changed() {
+                    if checked {
+                        self.pareto.stats.push(stat);
+                    } else {
+                        self.pareto.stats.retain(|other| *other != stat);
+                    }
+                }
+            }
+
+            ui.separator();
+
+            if ui.button("Scatter plot").clicked() {
+                self.pareto.plot_open = true;
+                ui.close();
+            }
+        });
+
+        if self.pareto.is_active() {
+            ui.label(format!("{} on the frontier", self.pareto.frontier.len()));
+        }
+    }
+
+    /// Window plotting two stats of the matching characters, the frontier
+    /// being drawn in element colors over the dominated characters.
+    pub fn render_pareto_plot(&mut self, ui: &mut egui::Ui) {
+        let mut open = self.pareto.plot_open;
+        let mut clicked = None;
+
+        egui::Window::new("Pareto frontier")
+            .open(&mut open)
+            .default_size([520.0, 480.0])
+            .show(ui.ctx(), |ui| {
+                ui.horizontal(|ui| {
+                    stat_combo(ui, "X", &mut self.pareto.x);
+                    stat_combo(ui, "Y", &mut self.pareto.y);
+                });
+
+                let selected: Vec<&str> = self.pareto.stats.iter().map(|stat| stat.to_str()).collect();
+                ui.weak(format!("Frontier over {}", selected.join(", ")));
+
+                let points: Vec<(&Character, bool)> = self.characters
+                    .iter()
+                    .filter(|character| character.stats.is_some() && self.matches_filters(character))
+                    .map(|character| (character, self.pareto.frontier.contains(&character.number)))
+                    .collect();
+
+                clicked = scatter_plot(ui, &points, self.pareto.x, self.pareto.y, self.selected);
+            });
+
+        self.pareto.plot_open = open;
+
+        if let Some(number) = clicked {
+            self.select_character(Some(number));
+        }
+    }
+}
+
+fn stat_combo(ui: &mut egui::Ui, label: &str, stat: &mut Stat) {
+    egui::ComboBox::from_label(label)
+        .selected_text(stat.to_str())
+        .show_ui(ui, |ui| {
+            for option in STAT_LIST {
+                ui.selectable_value(stat, option, option.to_str());
+            }
+        });
+}
+
+/// Draws the characters as points, returning the one clicked if any.
+fn scatter_plot(ui: &mut egui::Ui, points: &[(&Character, bool)], x: Stat, y: Stat, selected: Option<u16>) -> Option<u16> {
+    let size = Vec2::new(ui.available_width().max(240.0), ui.available_height().max(240.0));
+    let (response, painter) = ui.allocate_painter(size, Sense::click());
+
+    let margin = 32.0;
+    let area = Rect::from_min_max(
+        response.rect.min + Vec2::new(margin, 8.0),
+        response.rect.max - Vec2::new(8.0, margin),
+    );
+
+    let value = |character: &Character, stat: Stat| character.stats.as_ref().map_or(0, |stats| stats.get(stat)) as f32;
+    let range = |stat: Stat| {
+        let (min, max) = points
+            .iter()
+            .map(|(character, _)| value(character, stat))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| (min.min(value), max.max(value)));
+
+        if min > max {
+            (0.0, 1.0)
+        } else {
+            ((min / 10.0).floor() * 10.0, ((max / 10.0).ceil() * 10.0).max(min + 1.0))
+        }
+    };
+
+    let (x_min, x_max) = range(x);
+    let (y_min, y_max) = range(y);
+
+    let to_screen = |character: &Character| Pos2::new(
+        area.left() + (value(character, x) - x_min) / (x_max - x_min) * area.width(),
+        area.bottom() - (value(character, y) - y_min) / (y_max - y_min) * area.height(),
+    );
+
+    let axis_stroke = Stroke::new(1.0, ui.visuals().weak_text_color());
+    let text_color = ui.visuals().text_color();
+    let font = FontId::proportional(11.0);
+
+    painter.line_segment([area.left_bottom(), area.right_bottom()], axis_stroke);
+    painter.line_segment([area.left_bottom(), area.left_top()], axis_stroke);
+
+    for (position, label) in [(area.left_bottom(), x_min), (area.right_bottom(), x_max)] {
+        painter.text(position + Vec2::new(0.0, 4.0), Align2::CENTER_TOP, format!("{label:.0}"), font.clone(), text_color);
+    }
+    for (position, label) in [(area.left_bottom(), y_min), (area.left_top(), y_max)] {
+        painter.text(position - Vec2::new(4.0, 0.0), Align2::RIGHT_CENTER, format!("{label:.0}"), font.clone(), text_color);
+    }
+
+    painter.text(area.center_bottom() + Vec2::new(0.0, 16.0), Align2::CENTER_TOP, x.to_str(), font.clone(), text_color);
+    painter.text(area.left_top() + Vec2::new(4.0, 0.0), Align2::LEFT_TOP, y.to_str(), font, text_color);
+
+    // Dominated characters first so the frontier is drawn on top
+    let mut ordered: Vec<&(&Character, bool)> = points.iter().collect();
+    ordered.sort_by_key(|(_, on_frontier)| *on_frontier);
+
+    for (character, on_frontier) in ordered {
+        let position = to_screen(character);
+
+        if *on_frontier {
+            painter.circle_filled(position, 4.5, element_color(character.element));
+        } else {
+            painter.circle_filled(position, 2.5, Color32::GRAY.gamma_multiply(0.6));
+        }
+
+        if selected == Some(character.number) {
+            painter.circle_stroke(position, 7.0, Stroke::new(2.0, ui.visuals().strong_text_color()));
+        }
+    }
+
+    let hovered = response.hover_pos().and_then(|pointer| {
+        points
+            .iter()
+            .map(|(character, _)| (*character, to_screen(character).distance(pointer)))
+            .filter(|(_, distance)| *distance < 8.0)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(character, _)| character)
+    });
+
+    let hovered = hovered?;
+
+    response.clone().on_hover_text(format!(
+        "{} ({} {:.0}, {} {:.0})",
+        hovered.name,
+        x.to_str(),
+        value(hovered, x),
+        y.to_str(),
+        value(hovered, y),
+    ));
+
+    response.clicked().then_some(hovered.number)
+}
@@ -0,0 +1,49 @@
+use std::{cell::RefCell, collections::HashMap, sync::Arc};
+
+use eframe::egui::{self, Vec2};
+
+use crate::database::Database;
+
+/// Portraits read from the database, kept in memory once loaded.
+pub struct PortraitCache {
+    /// `None` for characters without a stored portrait.
+    images: RefCell<HashMap<u16, Option<Arc<[u8]>>>>,
+}
+
+impl PortraitCache {
+    pub fn new() -> PortraitCache {
+        PortraitCache {
+            images: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Forgets what was loaded, so that newly downloaded portraits show up.
+    pub fn clear(&self) {
+        self.images.borrow_mut().clear();
+    }
+
+    fn get(&self, database: &Database, number: u16) -> Option<Arc<[u8]>> {
+        self.images
+            .borrow_mut()
+            .entry(number)
+            .or_insert_with(|| database.portrait(number).map(Arc::from))
+            .clone()
+    }
+
+    /// Draws the portrait of a character in a square of the given size, which
+    /// is left empty when there is no portrait.
+    pub fn show(&self, ui: &mut egui::Ui, database: &Database, number: u16, size: f32) {
+        match self.get(database, number) {
+            Some(data) => {
+                ui.add(
+                    egui::Image::from_bytes(format!("bytes://portrait/{number}"), data)
+                        .fit_to_exact_size(Vec2::splat(size))
+                        .corner_radius(2.0),
+                );
+            }
+            None => {
+                ui.allocate_space(Vec2::splat(size));
+            }
+        }
+    }
+}
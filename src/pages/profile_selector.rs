@@ -0,0 +1,83 @@
+use eframe::egui;
+
+use crate::{database::DEFAULT_PROFILE, pages::ProfileSelector};
+
+impl ProfileSelector {
+    /// Renders the profile combo box and its menu. Returns true when another
+    /// profile became current, the pages then have to load its data.
+    pub fn render(&mut self, ui: &mut egui::Ui) -> bool {
+        let current = self.database.profile_id();
+        let mut selected = current;
+        let mut edited = false;
+
+        let current_name = self.profiles
+            .iter()
+            .find(|profile| profile.id == current)
+            .map_or("Default", |profile| profile.name.as_str());
+
+        egui::ComboBox::from_id_salt("profile")
+            .selected_text(current_name)
+            .show_ui(ui, |ui| {
+                for profile in &self.profiles {
+                    ui.selectable_value(&mut selected, profile.id, &profile.name);
+                }
+            });
+
+        ui.menu_button("Profiles", |ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.name).hint_text("Profile name"));
+
+            let name = self.name.trim().to_string();
+            let taken = self.profiles.iter().any(|profile| profile.name == name);
+
+            ui.horizontal(|ui| {
+                if ui.add_enabled(!name.is_empty() && !taken, egui::Button::new("New")).clicked() {
+                    selected = self.database.create_profile(&name);
+                    self.name.clear();
+                    edited = true;
+                    ui.close();
+                }
+
+                if ui.add_enabled(!name.is_empty() && !taken, egui::Button::new("Rename current")).clicked()
+                    && self.database.rename_profile(current, &name)
+                {
+                    self.name.clear();
+                    edited = true;
+                    ui.close();
+                }
+            });
+
+            ui.separator();
+
+            if current == DEFAULT_PROFILE {
+                ui.weak("The default profile can't be deleted.");
+            } else if self.confirm_delete {
+                ui.label(format!("Delete {current_name} and all of its data?"));
+                ui.horizontal(|ui| {
+                    if ui.button("Delete").clicked() {
+                        self.database.delete_profile(current);
+                        selected = DEFAULT_PROFILE;
+                        self.confirm_delete = false;
+                        edited = true;
+                        ui.close();
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_delete = false;
+                    }
+                });
+            } else if ui.button("Delete current").clicked() {
+                self.confirm_delete = true;
+            }
+        });
+
+        if edited {
+            self.profiles = self.database.profiles();
+        }
+
+        if selected == current {
+            return false;
+        }
+
+        self.database.switch_profile(selected);
+        true
+    }
+}
@@ -0,0 +1,150 @@
+use eframe::egui::{self, DragValue, RichText};
+
+use crate::{
+    pages::CharactersPage,
+    query::{Query, TextField, TextOp},
+    roster::{Ownership, RosterEntry, export_csv, parse_csv},
+};
+
+impl CharactersPage {
+    pub fn roster_entry(&self, number: u16) -> Option<&RosterEntry> {
+        self.roster.iter().find(|entry| entry.character_id == number)
+    }
+
+    pub fn render_roster_menu(&mut self, ui: &mut egui::Ui) {
+        let title = if self.ownership == Ownership::Any { "Roster" } else { "Roster *" };
+
+        ui.menu_button(title, |ui| {
+            ui.label(format!("{} characters recruited", self.roster.len()));
+
+            for ownership in [Ownership::Any, Ownership::Owned, Ownership::NotOwned] {
+                ui.radio_value(&mut self.ownership, ownership, ownership.to_str());
+            }
+
+            ui.separator();
+
+            if ui.button("Export CSV").on_hover_text("Copy the roster as CSV").clicked() {
+                ui.ctx().copy_text(export_csv(&self.roster));
+                ui.close();
+            }
+
+            ui.label("Paste a CSV with the columns id or name, recruited_on, level, notes");
+            ui.add(egui::TextEdit::multiline(&mut self.roster_import).desired_rows(6).code_editor());
+
+            if ui.button("Import CSV").clicked() {
+                self.import_roster();
+            }
+
+            if let Some(message) = &self.roster_message {
+                ui.label(message);
+            }
+        });
+    }
+
+    /// Adds the characters of the pasted CSV to the roster, finding those
+    /// given by name among the cached characters.
+    fn import_roster(&mut self) {
+        let (rows, mut errors) = parse_csv(&self.roster_import);
+        let mut entries = Vec::new();
+        let mut unknown = Vec::new();
+
+        for row in rows {
+            let character_id = match row.character.parse() {
+                Ok(number) => Some(number),
+                Err(_) => {
+                    let named = |field| Query::Text { field, op: TextOp::Equals, value: row.character.clone() };
+                    let matches = self.character_cache.query_characters(&Query::Or(
+                        Box::new(named(TextField::Name)),
+                        Box::new(named(TextField::Nickname)),
+                    ));
+
+                    // Names shared by several characters are ambiguous
+                    match matches.as_slice() {
+                        [character] => Some(character.number),
+                        _ => None,
+                    }
+                }
+            };
+
+            match character_id {
+                Some(character_id) => entries.push(RosterEntry {
+                    character_id,
+                    recruited_on: row.recruited_on,
+                    level: row.level,
+                    notes: row.notes,
+                }),
+                None => unknown.push(row.character),
+            }
+        }
+
+        self.character_cache.save_roster_entries(&entries);
+        self.roster = self.character_cache.roster();
+
+        let mut message = format!("Imported {} characters", entries.len());
+        if !unknown.is_empty() {
+            message.push_str(&format!(", not found or ambiguous: {}", unknown.join(", ")));
+        }
+        if !errors.is_empty() {
+            errors.sort_unstable();
+            let lines: Vec<String> = errors.iter().map(usize::to_string).collect();
+            message.push_str(&format!(", unreadable lines: {}", lines.join(", ")));
+        }
+
+        self.roster_message = Some(message);
+        if unknown.is_empty() && errors.is_empty() {
+            self.roster_import.clear();
+        }
+    }
+
+    /// Roster fields of a character in the detail panel, saved as they are
+    /// edited.
+    pub fn render_roster_section(&mut self, ui: &mut egui::Ui, number: u16) {
+        ui.label(RichText::new("Roster").strong());
+
+        let mut entry = self.roster_entry(number).cloned();
+        let mut owned = entry.is_some();
+
+        if ui.checkbox(&mut owned, "Recruited").changed() {
+            if owned {
+                self.character_cache.save_roster_entries(&[RosterEntry::new(number)]);
+            } else {
+                self.character_cache.remove_from_roster(number);
+            }
+            self.roster = self.character_cache.roster();
+            return;
+        }
+
+        let Some(entry) = &mut entry else {
+            return;
+        };
+
+        let mut changed = false;
+
+        egui::Grid::new("detail_roster").num_columns(2).show(ui, |ui| {
+            ui.label("Recruited on");
+            changed |= ui.add(egui::TextEdit::singleline(&mut entry.recruited_on).hint_text("YYYY-MM-DD")).changed();
+            ui.end_row();
+
+            ui.label("Level");
+            ui.horizontal(|ui| {
+                let mut known = entry.level.is_some();
+                if ui.checkbox(&mut known, "").changed() {
+                    entry.level = known.then_some(1);
+                    changed = true;
+                }
+                if let Some(level) = &mut entry.level {
+                    changed |= ui.add(DragValue::new(level).range(1..=999)).changed();
+                }
+            });
+            ui.end_row();
+        });
+
+        ui.label("Notes");
+        changed |= ui.add(egui::TextEdit::multiline(&mut entry.notes).desired_rows(3)).changed();
+
+        if changed {
+            self.character_cache.save_roster_entries(std::slice::from_ref(entry));
+            self.roster = self.character_cache.roster();
+        }
+    }
+}
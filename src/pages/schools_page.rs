@@ -0,0 +1,177 @@
+use eframe::egui::{self, RichText};
+
+use crate::{
+    analysis::StatDistribution,
+    pages::{SchoolsPage, SettingsPage, characters_page::{element_badge, format_value}},
+    query::Query,
+    utils::{Character, POSITION_LIST, STAT_LIST, Stat},
+};
+
+/// Aggregates of the cached characters of a school.
+struct SchoolSummary<'a> {
+    name: &'a str,
+    members: Vec<&'a Character>,
+    /// Mean of each stat in `STAT_LIST` order, `None` without stats.
+    means: [Option<f64>; 7],
+    total: Option<f64>,
+}
+
+impl SchoolSummary<'_> {
+    /// Mean of the stat, or of the total of the stats when `None`.
+    fn sort_value(&self, stat: Option<Stat>) -> Option<f64> {
+        match stat {
+            Some(stat) => self.means[stat.index()],
+            None => self.total,
+        }
+    }
+}
+
+impl SchoolsPage {
+    pub fn render(&mut self, settings: &SettingsPage, ui: &mut egui::Ui) {
+        ui.heading("Schools");
+        ui.label("Teams and schools the cached characters belong to, as read from their pages.");
+
+        ui.horizontal(|ui| {
+            if ui.button("Reload").clicked() {
+                self.characters = None;
+            }
+
+            ui.add(egui::TextEdit::singleline(&mut self.filter).hint_text("Filter schools"));
+
+            egui::ComboBox::from_label("Sort by mean")
+                .selected_text(self.sort.map_or("Total", Stat::to_str))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.sort, None, "Total");
+                    for stat in STAT_LIST {
+                        ui.selectable_value(&mut self.sort, Some(stat), stat.to_str());
+                    }
+                });
+        });
+
+        ui.separator();
+
+        let characters = self.characters.get_or_insert_with(|| self.database.query_characters(&Query::All));
+
+        let filter = self.filter.to_lowercase();
+        let mut summaries = summarize(characters);
+        summaries.retain(|summary| summary.name.to_lowercase().contains(&filter));
+        summaries.sort_by(|a, b| {
+            b.sort_value(self.sort)
+                .partial_cmp(&a.sort_value(self.sort))
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.name.cmp(b.name))
+        });
+
+        if summaries.is_empty() {
+            ui.label("No school known yet. Schools are read when the page of a character is fetched, re-fetch characters to learn theirs.");
+            return;
+        }
+
+        egui::ScrollArea::vertical().id_salt("school_summaries").max_height(ui.available_height() / 2.0).show(ui, |ui| {
+            egui::Grid::new("school_summaries_grid").striped(true).spacing([12.0, 4.0]).show(ui, |ui| {
+                ui.label(RichText::new("Roster").strong());
+                ui.label(RichText::new("School").strong());
+                ui.label(RichText::new("Members").strong());
+                for stat in STAT_LIST {
+                    ui.label(RichText::new(stat.to_str()).strong());
+                }
+                ui.label(RichText::new("Total").strong());
+                ui.end_row();
+
+                for summary in &summaries {
+                    let mut shown = self.rosters.iter().any(|name| name == summary.name);
+                    if ui.checkbox(&mut shown, "").changed() {
+                        if shown {
+                            self.rosters.push(summary.name.to_string());
+                        } else {
+                            self.rosters.retain(|name| name != summary.name);
+                        }
+                    }
+
+                    ui.label(summary.name);
+                    ui.label(summary.members.len().to_string());
+                    for mean in summary.means.iter().chain([&summary.total]) {
+                        ui.label(mean.map_or("-".to_string(), |mean| format_value((mean * 10.0).round() / 10.0)));
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+
+        ui.separator();
+
+        if self.rosters.is_empty() {
+            ui.label("Tick schools to compare their rosters side by side.");
+            return;
+        }
+
+        egui::ScrollArea::both().id_salt("school_rosters").show(ui, |ui| {
+            ui.horizontal_top(|ui| {
+                for name in &self.rosters {
+                    let Some(summary) = summaries.iter().find(|summary| summary.name == name) else {
+                        continue;
+                    };
+
+                    ui.vertical(|ui| {
+                        ui.heading(summary.name);
+                        roster(ui, summary, settings.japanese_names);
+                    });
+
+                    ui.separator();
+                }
+            });
+        });
+    }
+}
+
+/// Groups the characters by school, characters without a school left out.
+fn summarize(characters: &[Character]) -> Vec<SchoolSummary<'_>> {
+    let mut summaries: Vec<SchoolSummary> = Vec::new();
+
+    for character in characters.iter().filter(|character| !character.school.is_empty()) {
+        match summaries.iter_mut().find(|summary| summary.name == character.school) {
+            Some(summary) => summary.members.push(character),
+            None => summaries.push(SchoolSummary {
+                name: &character.school,
+                members: vec![character],
+                means: [None; 7],
+                total: None,
+            }),
+        }
+    }
+
+    for summary in &mut summaries {
+        let distribution = StatDistribution::new(summary.members.iter().copied());
+
+        summary.means = STAT_LIST.map(|stat| distribution.summary(stat).map(|summary| summary.mean));
+        summary.total = summary.means.iter().copied().sum();
+    }
+
+    summaries
+}
+
+/// Members of a school by position, strongest first.
+fn roster(ui: &mut egui::Ui, summary: &SchoolSummary, japanese: bool) {
+    let total = |character: &Character| {
+        character.stats.as_ref().map_or(0, |stats| STAT_LIST.iter().map(|stat| stats.get(*stat) as u32).sum())
+    };
+
+    egui::Grid::new(("school_roster", summary.name)).striped(true).spacing([8.0, 2.0]).show(ui, |ui| {
+        for position in POSITION_LIST {
+            let mut members: Vec<&Character> = summary.members
+                .iter()
+                .copied()
+                .filter(|character| character.position == position)
+                .collect();
+            members.sort_by_key(|character| std::cmp::Reverse(total(character)));
+
+            for character in members {
+                ui.weak(position.to_str());
+                element_badge(ui, character.element);
+                ui.label(character.display_name(japanese));
+                ui.label(total(character).to_string());
+                ui.end_row();
+            }
+        }
+    });
+}
@@ -1,12 +1,107 @@
 use eframe::egui::{self, Slider};
 
-use crate::{pages::SettingsPage};
+use crate::{pages::SettingsPage, query::DerivedColumn};
 
 impl SettingsPage {
     pub fn render(&mut self, ui: &mut egui::Ui) {
-        ui.add(Slider::new(&mut self.max_parallelism, 1..=50)
+        let response = ui.add(Slider::new(&mut self.max_parallelism, 1..=50)
             .text("Max parallel connections")
         );
+
+        if response.changed() {
+            self.database.set_setting("max_parallelism", &self.max_parallelism.to_string());
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Character names");
+
+            let english = ui.radio_value(&mut self.japanese_names, false, "English");
+            let japanese = ui.radio_value(&mut self.japanese_names, true, "Japanese");
+
+            if english.changed() || japanese.changed() {
+                let language = if self.japanese_names { "ja" } else { "en" };
+                self.database.set_setting("name_language", language);
+                self.revision += 1;
+            }
+        });
+
+        ui.separator();
+
+        self.render_derived_columns(ui);
     }
-}
 
+    fn render_derived_columns(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Derived columns");
+        ui.label("Formulas over kick, control, technique, pressure, physical, agility, intelligence and id. \
+            Their names can be used in queries like any stat.");
+
+        // Edits are saved once a field is left rather than on every key
+        let mut changed = false;
+        let mut removed = None;
+
+        egui::Grid::new("derived_columns").num_columns(3).show(ui, |ui| {
+            for index in 0..self.derived_columns.len() {
+                let duplicate = DerivedColumn::is_duplicate(&self.derived_columns, index);
+                let column = &mut self.derived_columns[index];
+
+                changed |= ui.add(egui::TextEdit::singleline(&mut column.name).desired_width(120.0)).lost_focus();
+
+                let mut formula = column.formula().to_string();
+                let response = ui.add(egui::TextEdit::singleline(&mut formula).desired_width(360.0));
+                if response.changed() {
+                    column.set_formula(formula);
+                }
+                changed |= response.lost_focus();
+
+                if ui.button("Remove").clicked() {
+                    removed = Some(index);
+                }
+                ui.end_row();
+
+                if !DerivedColumn::is_valid_name(&column.name) {
+                    ui.colored_label(ui.visuals().error_fg_color, "Invalid name");
+                    ui.label("Use a single word that isn't a stat, a query field or a table column");
+                    ui.end_row();
+                } else if duplicate {
+                    ui.colored_label(ui.visuals().error_fg_color, "Duplicate name");
+                    ui.label("Another column above already has this name");
+                    ui.end_row();
+                } else if let Some(error) = column.error() {
+                    ui.label("");
+                    ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+                    ui.end_row();
+                }
+            }
+        });
+
+        if let Some(index) = removed {
+            self.derived_columns.remove(index);
+            changed = true;
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Add column").clicked() {
+                let name = DerivedColumn::free_name(&self.derived_columns);
+                self.derived_columns.push(DerivedColumn::new(&name, "kick + technique"));
+                changed = true;
+            }
+
+            if ui.button("Restore defaults").clicked() {
+                self.derived_columns = DerivedColumn::defaults();
+                changed = true;
+            }
+        });
+
+        let names_valid = (0..self.derived_columns.len()).all(|index| {
+            DerivedColumn::is_valid_name(&self.derived_columns[index].name)
+                && !DerivedColumn::is_duplicate(&self.derived_columns, index)
+        });
+
+        if !names_valid {
+            ui.colored_label(ui.visuals().error_fg_color, "Columns aren't saved until every name is fixed");
+        } else if changed {
+            self.revision += 1;
+            self.database.store_derived_columns(&self.derived_columns);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use eframe::egui::{self, DragValue, RichText};
+
+use crate::{
+    analysis::{SimilarOptions, Similarity, similar_characters},
+    pages::{CharactersPage, ComparePage, characters_page::{element_badge, format_value}},
+    query::Query,
+    utils::{Character, STAT_LIST},
+};
+
+/// Characters with a stat profile close to a target, searched in the whole
+/// cache.
+pub struct SimilarView {
+    target: Character,
+    /// Every cached character.
+    pool: Vec<Character>,
+    options: SimilarOptions,
+}
+
+impl CharactersPage {
+    pub fn find_similar(&mut self, target: Character) {
+        self.similar = Some(SimilarView {
+            target,
+            pool: self.character_cache.query_characters(&Query::All),
+            options: self.similar.take().map(|view| view.options).unwrap_or_default(),
+        });
+    }
+
+    pub fn render_similar_window(&mut self, compare: &mut ComparePage, ui: &mut egui::Ui) {
+        let Some(view) = &mut self.similar else {
+            return;
+        };
+
+        let mut open = true;
+        let mut show_in_table = None;
+
+        egui::Window::new(format!("Similar to {}", view.target.name))
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ui.ctx(), |ui| {
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_label("Distance")
+                        .selected_text(view.options.similarity.to_str())
+                        .show_ui(ui, |ui| {
+                            for similarity in [Similarity::Cosine, Similarity::Euclidean] {
+                                ui.selectable_value(&mut view.options.similarity, similarity, similarity.to_str());
+                            }
+                        });
+
+                    ui.label("Count");
+                    ui.add(DragValue::new(&mut view.options.count).range(1..=100));
+                });
+
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut view.options.same_element, "Same element");
+                    ui.checkbox(&mut view.options.same_position, "Same position");
+                });
+
+                if view.target.stats.is_none() {
+                    ui.label("This character has no stats to compare.");
+                    return;
+                }
+
+                let neighbors = similar_characters(&view.target, &view.pool, &view.options);
+
+                if neighbors.is_empty() {
+                    ui.label("No other cached character matches.");
+                    return;
+                }
+
+                let label = match view.options.similarity {
+                    Similarity::Cosine => "Similarity",
+                    Similarity::Euclidean => "Distance",
+                };
+
+                egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                    egui::Grid::new("similar_characters").striped(true).spacing([10.0, 4.0]).show(ui, |ui| {
+                        ui.label("");
+                        ui.label(RichText::new("Name").strong());
+                        ui.label("");
+                        ui.label("");
+                        ui.label(RichText::new(label).strong());
+                        for stat in STAT_LIST {
+                            ui.label(RichText::new(stat.to_str()).strong());
+                        }
+                        ui.end_row();
+
+                        ui.label("");
+                        profile_row(ui, &view.target, RichText::new("Target").weak());
+                        ui.end_row();
+
+                        for (character, distance) in &neighbors {
+                            let pinned = compare.is_pinned(character.number);
+                            let pin_label = if pinned { "Unpin" } else { "Pin" };
+
+                            if ui.add_enabled(pinned || !compare.is_full(), egui::Button::new(pin_label).small()).clicked() {
+                                compare.toggle_pin(character);
+                            }
+
+                            let distance = match view.options.similarity {
+                                Similarity::Cosine => format!("{:.1} %", (1.0 - distance) * 100.0),
+                                Similarity::Euclidean => format!("{distance:.2}"),
+                            };
+                            profile_row(ui, character, RichText::new(distance));
+                            ui.end_row();
+                        }
+                    });
+                });
+
+                if ui.button("Show in table").on_hover_text("Replace the results with these characters").clicked() {
+                    let mut characters = vec![view.target.clone()];
+                    characters.extend(neighbors.iter().map(|(character, _)| (*character).clone()));
+                    show_in_table = Some(characters);
+                }
+            });
+
+        if !open {
+            self.similar = None;
+        }
+
+        if let Some(characters) = show_in_table {
+            self.characters = characters;
+            self.progress = None;
+            self.characters_changed();
+        }
+    }
+}
+
+fn profile_row(ui: &mut egui::Ui, character: &Character, measure: RichText) {
+    ui.label(&character.name);
+    element_badge(ui, character.element);
+    ui.label(character.position.to_str());
+    ui.label(measure);
+
+    for stat in STAT_LIST {
+        let value = character.stats.as_ref().map(|stats| stats.get(stat) as f64);
+        ui.label(value.map_or("-".to_string(), format_value));
+    }
+}
@@ -0,0 +1,391 @@
+use eframe::egui::{self, Color32, Frame, Id, RichText, Vec2};
+
+use crate::{
+    modifiers::{ModifierScope, ModifierSet},
+    pages::{TeamPage, characters_page::{element_badge, format_value}},
+    query::Query,
+    roster::Ownership,
+    team::{BENCH_SIZE, FORMATION_LIST, TEAM_SIZE, Team, TeamSummary, find},
+    utils::{Character, ELEMENT_LIST, Position, STAT_LIST},
+};
+
+const SLOT_SIZE: Vec2 = Vec2::new(130.0, 52.0);
+
+/// Changes to the team requested while rendering, applied once the frame is
+/// drawn.
+enum Action {
+    Assign(usize, u16),
+    Bench(u16),
+    Add(u16),
+    Remove(u16),
+}
+
+impl TeamPage {
+    pub fn render(&mut self, modifiers: &ModifierSet, ui: &mut egui::Ui) {
+        self.render_toolbar(ui);
+
+        ui.separator();
+
+        let mut actions = Vec::new();
+
+        egui::SidePanel::left("team_candidates")
+            .resizable(true)
+            .default_width(300.0)
+            .show_inside(ui, |ui| {
+                self.render_candidates(ui, &mut actions);
+            });
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            self.render_pitch(ui, &mut actions);
+
+            ui.separator();
+
+            self.render_bench(ui, &mut actions);
+
+            ui.separator();
+
+            self.render_summary(modifiers, ui);
+        });
+
+        for action in actions {
+            match action {
+                Action::Assign(slot, number) => self.team.assign(slot, number),
+                Action::Bench(number) => {
+                    if !self.team.add_to_bench(number) {
+                        self.message = Some(format!("The bench is limited to {BENCH_SIZE} players"));
+                    }
+                }
+                Action::Add(number) => {
+                    let position = find(&self.characters, number).map_or(Position::MF, |character| character.position);
+                    match self.team.free_slot(position) {
+                        Some(slot) => self.team.assign(slot, number),
+                        None => {
+                            if !self.team.add_to_bench(number) {
+                                self.message = Some("The team is full".to_string());
+                            }
+                        }
+                    }
+                }
+                Action::Remove(number) => self.team.remove(number),
+            }
+        }
+    }
+
+    fn render_toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Name");
+            ui.add(egui::TextEdit::singleline(&mut self.team.name).desired_width(160.0));
+
+            egui::ComboBox::from_label("Formation")
+                .selected_text(self.team.formation.name)
+                .show_ui(ui, |ui| {
+                    for formation in FORMATION_LIST {
+                        ui.selectable_value(&mut self.team.formation, formation, formation.name);
+                    }
+                });
+
+            if ui.add_enabled(!self.team.name.trim().is_empty(), egui::Button::new("Save")).clicked() {
+                self.save_team();
+            }
+
+            if ui.button("New").clicked() {
+                self.team = Team::new("New team");
+                self.message = None;
+            }
+
+            ui.menu_button("Saved teams", |ui| {
+                if self.saved_teams.is_empty() {
+                    ui.label("No saved team");
+                }
+
+                let mut deleted = None;
+
+                for team in &self.saved_teams {
+                    ui.horizontal(|ui| {
+                        if ui.button(format!("{} ({})", team.name, team.formation.name)).clicked() {
+                            self.team = team.clone();
+                            self.message = None;
+                            ui.close();
+                        }
+
+                        if ui.small_button("x").on_hover_text("Delete").clicked() {
+                            deleted = team.id;
+                        }
+                    });
+                }
+
+                if let Some(id) = deleted {
+                    self.delete_team(id);
+                }
+            });
+
+            if ui.button("Export").on_hover_text("Copy the team to the clipboard").clicked() {
+                ui.ctx().copy_text(self.team.export());
+                self.message = Some("Team copied to the clipboard".to_string());
+            }
+
+            ui.menu_button("Import", |ui| {
+                ui.label("Paste an exported team");
+                ui.add(egui::TextEdit::multiline(&mut self.import_text).desired_rows(8));
+
+                if ui.button("Import").clicked() {
+                    self.import_team();
+                    ui.close();
+                }
+            });
+
+            if ui.button("Reload characters").on_hover_text("Reload the characters from the cache").clicked() {
+                self.reload_characters();
+            }
+        });
+
+        if let Some(message) = &self.message {
+            ui.label(message);
+        }
+    }
+
+    fn render_candidates(&mut self, ui: &mut egui::Ui, actions: &mut Vec<Action>) {
+        ui.heading("Characters");
+
+        ui.add(egui::TextEdit::singleline(&mut self.filter).hint_text("Query, e.g. pos:DF kick>80"));
+
+        let query = Query::parse(&self.filter);
+        if let Err(error) = &query {
+            ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+        }
+        let query = query.unwrap_or(Query::All);
+
+        egui::ComboBox::from_label("Roster")
+            .selected_text(self.ownership.to_str())
+            .show_ui(ui, |ui| {
+                for ownership in [Ownership::Any, Ownership::Owned, Ownership::NotOwned] {
+                    ui.selectable_value(&mut self.ownership, ownership, ownership.to_str());
+                }
+            });
+
+        let candidates: Vec<&Character> = self.characters
+            .iter()
+            .filter(|character| query.matches(character))
+            .filter(|character| self.ownership.matches(self.owned.contains(&character.number)))
+            .collect();
+
+        ui.label(format!("{} characters, drag them onto the pitch", candidates.len()));
+
+        let row_height = ui.spacing().interact_size.y;
+
+        egui::ScrollArea::vertical().auto_shrink(false).show_rows(ui, row_height, candidates.len(), |ui, range| {
+            for character in &candidates[range] {
+                ui.horizontal(|ui| {
+                    let in_team = self.team.contains(character.number);
+
+                    if ui.add_enabled(!in_team, egui::Button::new("+").small()).on_hover_text("Add to the team").clicked() {
+                        actions.push(Action::Add(character.number));
+                    }
+
+                    ui.dnd_drag_source(Id::new(("team_candidate", character.number)), character.number, |ui| {
+                        character_label(ui, character, in_team);
+                    });
+                });
+            }
+        });
+
+        if self.characters.is_empty() {
+            ui.label("The cache is empty, run a search from the Characters page first.");
+        }
+    }
+
+    /// Draws the formation with forwards at the top and the goalkeeper at the
+    /// bottom.
+    fn render_pitch(&self, ui: &mut egui::Ui, actions: &mut Vec<Action>) {
+        let slots = self.team.formation.slots();
+        let misfits = self.team.misfits(&self.characters);
+
+        for position in [Position::FW, Position::MF, Position::DF, Position::GK] {
+            ui.horizontal(|ui| {
+                let line: Vec<usize> = (0..TEAM_SIZE).filter(|slot| slots[*slot] == position).collect();
+                let width = line.len() as f32 * (SLOT_SIZE.x + ui.spacing().item_spacing.x + 12.0);
+                ui.add_space(((ui.available_width() - width) / 2.0).max(0.0));
+
+                for slot in line {
+                    let (_, dropped) = ui.dnd_drop_zone::<u16, _>(Frame::group(ui.style()), |ui| {
+                        ui.set_min_size(SLOT_SIZE);
+                        ui.set_max_width(SLOT_SIZE.x);
+
+                        ui.vertical(|ui| {
+                            let color = if misfits.contains(&slot) {
+                                ui.visuals().error_fg_color
+                            } else {
+                                ui.visuals().weak_text_color()
+                            };
+
+                            let player = self.team.players[slot];
+                            let character = player.and_then(|number| find(&self.characters, number));
+
+                            ui.horizontal(|ui| {
+                                ui.label(RichText::new(position.to_str()).color(color).strong());
+
+                                if let Some(number) = player
+                                    && ui.small_button("x").on_hover_text("Remove").clicked()
+                                {
+                                    actions.push(Action::Remove(number));
+                                }
+                            });
+
+                            match (player, character) {
+                                (Some(number), Some(character)) => {
+                                    ui.dnd_drag_source(Id::new(("team_slot", slot)), number, |ui| {
+                                        character_label(ui, character, false);
+                                    });
+                                }
+                                (Some(number), None) => {
+                                    ui.label(format!("#{number} (not cached)"));
+                                }
+                                (None, _) => {
+                                    ui.weak("Empty");
+                                }
+                            }
+                        });
+                    });
+
+                    if let Some(number) = dropped {
+                        actions.push(Action::Assign(slot, *number));
+                    }
+                }
+            });
+        }
+    }
+
+    fn render_bench(&self, ui: &mut egui::Ui, actions: &mut Vec<Action>) {
+        ui.label(RichText::new(format!("Bench ({} / {BENCH_SIZE})", self.team.bench.len())).strong());
+
+        let (_, dropped) = ui.dnd_drop_zone::<u16, _>(Frame::group(ui.style()), |ui| {
+            ui.set_min_size(Vec2::new(ui.available_width(), SLOT_SIZE.y / 2.0));
+
+            ui.horizontal_wrapped(|ui| {
+                if self.team.bench.is_empty() {
+                    ui.weak("Drop substitutes here");
+                }
+
+                for number in &self.team.bench {
+                    match find(&self.characters, *number) {
+                        Some(character) => {
+                            ui.dnd_drag_source(Id::new(("team_bench", *number)), *number, |ui| {
+                                character_label(ui, character, false);
+                            });
+                        }
+                        None => {
+                            ui.label(format!("#{number} (not cached)"));
+                        }
+                    }
+
+                    if ui.small_button("x").on_hover_text("Remove").clicked() {
+                        actions.push(Action::Remove(*number));
+                    }
+
+                    ui.separator();
+                }
+            });
+        });
+
+        if let Some(number) = dropped {
+            actions.push(Action::Bench(*number));
+        }
+    }
+
+    /// Totals of the starting eleven with the modifiers of the roster and
+    /// those attached within the team.
+    fn render_summary(&self, modifiers: &ModifierSet, ui: &mut egui::Ui) {
+        let scope = ModifierScope::of_team(self.team.id);
+        let members: Vec<Character> = self.team
+            .members()
+            .into_iter()
+            .filter_map(|number| find(&self.characters, number))
+            .map(|character| modifiers.apply_in(character, scope))
+            .collect();
+        let boosted = self.team
+            .members()
+            .into_iter()
+            .filter(|number| !modifiers.of_in(*number, scope).is_empty())
+            .count();
+
+        let summary = TeamSummary::new(&self.team, &members);
+
+        ui.heading(format!("Starting eleven ({} / {TEAM_SIZE})", summary.player_count));
+
+        if boosted > 0 {
+            ui.weak(format!("Stats include the modifiers of {boosted} members"));
+        }
+
+        ui.horizontal_top(|ui| {
+            egui::Grid::new("team_stats").striped(true).spacing([16.0, 4.0]).show(ui, |ui| {
+                ui.label("");
+                ui.label(RichText::new("Total").strong());
+                ui.label(RichText::new("Average").strong());
+                ui.end_row();
+
+                for (index, stat) in STAT_LIST.iter().enumerate() {
+                    ui.label(stat.to_str());
+                    ui.label(summary.totals[index].to_string());
+                    ui.label(format_value((summary.average(index) * 10.0).round() / 10.0));
+                    ui.end_row();
+                }
+
+                let total: u32 = summary.totals.iter().sum();
+                ui.label(RichText::new("All stats").strong());
+                ui.label(RichText::new(total.to_string()).strong());
+                ui.label("");
+                ui.end_row();
+            });
+
+            ui.add_space(24.0);
+
+            ui.vertical(|ui| {
+                ui.label(RichText::new("Elements").strong());
+
+                for element in ELEMENT_LIST {
+                    ui.horizontal(|ui| {
+                        element_badge(ui, element);
+                        ui.label(summary.element_count(element).to_string());
+                    });
+                }
+            });
+        });
+
+        let slots = self.team.formation.slots();
+        let warnings: Vec<String> = self.team
+            .misfits(&self.characters)
+            .into_iter()
+            .filter_map(|slot| {
+                let character = find(&self.characters, self.team.players[slot]?)?;
+                Some(format!(
+                    "{} plays {} but is in a {} slot",
+                    character.name,
+                    character.position.to_str(),
+                    slots[slot].to_str()
+                ))
+            })
+            .chain(
+                self.team
+                    .members()
+                    .into_iter()
+                    .filter_map(|number| find(&self.characters, number))
+                    .filter(|character| character.stats.is_none())
+                    .map(|character| format!("{} has no stats in the cache", character.name)),
+            )
+            .collect();
+
+        for warning in warnings {
+            ui.colored_label(ui.visuals().warn_fg_color, warning);
+        }
+    }
+}
+
+fn character_label(ui: &mut egui::Ui, character: &Character, dimmed: bool) {
+    ui.horizontal(|ui| {
+        element_badge(ui, character.element);
+
+        let name = RichText::new(&character.name);
+        ui.label(if dimmed { name.color(Color32::GRAY) } else { name });
+        ui.weak(character.position.to_str());
+    });
+}
@@ -0,0 +1,129 @@
+use eframe::egui::{self, RichText};
+
+use crate::{
+    pages::{CharactersPage, ComparePage, characters_page::element_badge},
+    utils::{Character, GAME_LIST, Game, STAT_LIST},
+};
+
+/// Every cached version of one character, from the games they appear in.
+pub struct VersionsView {
+    name: String,
+    /// Versions with the games they were found in, ordered by number.
+    versions: Vec<(Character, Vec<Game>)>,
+}
+
+impl CharactersPage {
+    pub fn show_versions(&mut self, character: &Character) {
+        self.versions = Some(VersionsView {
+            name: character.display_name(self.japanese_names).to_string(),
+            versions: self.character_cache.character_versions(character.number),
+        });
+    }
+
+    pub fn render_versions_window(&mut self, compare: &mut ComparePage, ui: &mut egui::Ui) {
+        let Some(view) = &self.versions else {
+            return;
+        };
+
+        let mut open = true;
+        let japanese = self.japanese_names;
+
+        egui::Window::new(format!("Versions of {}", view.name))
+            .open(&mut open)
+            .default_width(560.0)
+            .show(ui.ctx(), |ui| {
+                ui.weak("Versions are matched by name and nickname. Games are learned from searches filtered on a single game.");
+
+                if view.versions.len() < 2 {
+                    ui.label("No other version of this character is cached.");
+                }
+
+                let total = |character: &Character| {
+                    character.stats.as_ref().map(|stats| STAT_LIST.iter().map(|stat| stats.get(*stat) as u32).sum::<u32>())
+                };
+
+                if let Some((strongest, _)) = view.versions.iter().max_by_key(|(character, _)| total(character)) {
+                    ui.label(format!(
+                        "Strongest: #{} in {}",
+                        strongest.number,
+                        games_label(&view.versions, strongest.number),
+                    ));
+                }
+
+                let best = STAT_LIST.map(|stat| {
+                    view.versions
+                        .iter()
+                        .filter_map(|(character, _)| character.stats.as_ref().map(|stats| stats.get(stat)))
+                        .max()
+                });
+
+                egui::Grid::new("character_versions").striped(true).spacing([10.0, 4.0]).show(ui, |ui| {
+                    for header in ["", "Game", "ID", "Name", "", ""] {
+                        ui.label(RichText::new(header).strong());
+                    }
+                    for stat in STAT_LIST {
+                        ui.label(RichText::new(stat.to_str()).strong());
+                    }
+                    ui.label(RichText::new("Total").strong());
+                    ui.end_row();
+
+                    // One row per game a version appears in, unknown games last
+                    let rows = GAME_LIST
+                        .iter()
+                        .flat_map(|game| {
+                            view.versions
+                                .iter()
+                                .filter(move |(_, games)| games.contains(game))
+                                .map(move |(character, _)| (game.to_str(), character))
+                        })
+                        .chain(view.versions.iter().filter(|(_, games)| games.is_empty()).map(|(character, _)| ("?", character)));
+
+                    for (game, character) in rows {
+                        let pinned = compare.is_pinned(character.number);
+                        let pin_label = if pinned { "Unpin" } else { "Pin" };
+
+                        if ui.add_enabled(pinned || !compare.is_full(), egui::Button::new(pin_label).small()).clicked() {
+                            compare.toggle_pin(character);
+                        }
+
+                        ui.label(game);
+                        ui.label(character.number.to_string());
+                        ui.label(character.display_name(japanese));
+                        element_badge(ui, character.element);
+                        ui.label(character.position.to_str());
+
+                        for (stat, best) in STAT_LIST.iter().zip(best) {
+                            match character.stats.as_ref().map(|stats| stats.get(*stat)) {
+                                Some(value) if Some(value) == best && view.versions.len() > 1 => {
+                                    ui.label(RichText::new(value.to_string()).strong());
+                                }
+                                Some(value) => {
+                                    ui.label(value.to_string());
+                                }
+                                None => {
+                                    ui.label("-");
+                                }
+                            }
+                        }
+
+                        ui.label(total(character).map_or("-".to_string(), |total| total.to_string()));
+                        ui.end_row();
+                    }
+                });
+            });
+
+        if !open {
+            self.versions = None;
+        }
+    }
+}
+
+fn games_label(versions: &[(Character, Vec<Game>)], number: u16) -> String {
+    let games: Vec<&str> = versions
+        .iter()
+        .filter(|(character, _)| character.number == number)
+        .flat_map(|(_, games)| games.iter().map(|game| game.to_str()))
+        .collect();
+
+    if games.is_empty() { "an unknown game".to_string() } else { games.join(", ") }
+}
@@ -0,0 +1,306 @@
+use std::fmt;
+
+use crate::utils::{Character, Element, Position, STAT_LIST, Stat};
+
+mod parser;
+mod sql;
+
+/// A filter over characters, parsed from expressions such as
+/// `element:fire pos:FW kick>=85 (agility+technique)>150 name~"Go"`.
+///
+/// Terms separated by spaces must all hold. They can be combined with `or`,
+/// negated with `not` or `!`, and grouped with parentheses.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    /// Matches every character, produced by an empty query.
+    All,
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Element(Element),
+    Position(Position),
+    Text {
+        field: TextField,
+        op: TextOp,
+        value: String,
+    },
+    Compare {
+        left: Expr,
+        op: CompareOp,
+        right: Expr,
+    },
+}
+
+/// An arithmetic expression over the numeric fields of a character.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Stat(Stat),
+    Id,
+    Neg(Box<Expr>),
+    Binary {
+        op: BinaryOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextField {
+    /// Either the name or the nickname.
+    Any,
+    /// The English or Japanese name.
+    Name,
+    /// The English or Japanese nickname.
+    Nickname,
+    /// The team or school of the character.
+    School,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextOp {
+    /// Case-insensitive substring match.
+    Contains,
+    /// Case-insensitive full match.
+    Equals,
+}
+
+/// Error returned when a query can't be parsed. `position` is the character
+/// offset in the query where the problem was found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at column {})", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A computed column such as the total of all stats, defined by a formula
+/// over the stats of a character. Its name can be used in queries like any
+/// raw stat.
+#[derive(Debug, Clone)]
+pub struct DerivedColumn {
+    pub name: String,
+    formula: String,
+    expr: Result<Expr, ParseError>,
+}
+
+impl DerivedColumn {
+    pub fn new(name: &str, formula: &str) -> DerivedColumn {
+        DerivedColumn {
+            name: name.to_string(),
+            formula: formula.to_string(),
+            expr: parser::parse_expression(formula),
+        }
+    }
+
+    pub fn defaults() -> Vec<DerivedColumn> {
+        let total = STAT_LIST
+            .iter()
+            .map(|stat| stat.db_column())
+            .collect::<Vec<_>>()
+            .join(" + ");
+
+        vec![
+            DerivedColumn::new("total", &total),
+            DerivedColumn::new("offense", "kick + control + technique + agility"),
+            DerivedColumn::new("defense", "pressure + physical + intelligence"),
+            DerivedColumn::new("gk_rating", "physical + pressure"),
+        ]
+    }
+
+    pub fn formula(&self) -> &str {
+        &self.formula
+    }
+
+    pub fn set_formula(&mut self, formula: String) {
+        self.expr = parser::parse_expression(&formula);
+        self.formula = formula;
+    }
+
+    pub fn expr(&self) -> Option<&Expr> {
+        self.expr.as_ref().ok()
+    }
+
+    pub fn error(&self) -> Option<&ParseError> {
+        self.expr.as_ref().err()
+    }
+
+    pub fn eval(&self, character: &Character) -> Option<f64> {
+        self.expr().and_then(|expr| expr.eval(character))
+    }
+
+    /// Whether the name can be referenced from a query: a single word that
+    /// isn't already a stat, a keyword, a query field or the label of a
+    /// built-in table column.
+    pub fn is_valid_name(name: &str) -> bool {
+        let reserved = [
+            // Keywords and numeric fields
+            "id", "number", "and", "or", "not",
+            // Text and field filters
+            "name", "nick", "nickname", "text", "school", "team",
+            "element", "elem", "el", "attr", "position", "pos",
+            // Built-in table columns
+            "owned", "favorite", "tags", "notes", "portrait", "link",
+        ];
+
+        !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+            && Stat::from_name(name).is_none()
+            && !reserved.iter().any(|word| word.eq_ignore_ascii_case(name))
+    }
+
+    /// Whether a column before the one at `index` already has its name.
+    /// Names are compared like queries do, regardless of case.
+    pub fn is_duplicate(columns: &[DerivedColumn], index: usize) -> bool {
+        columns[..index]
+            .iter()
+            .any(|column| column.name.eq_ignore_ascii_case(&columns[index].name))
+    }
+
+    /// The first `custom_N` name no column uses yet.
+    pub fn free_name(columns: &[DerivedColumn]) -> String {
+        (1..)
+            .map(|index| format!("custom_{index}"))
+            .find(|name| !columns.iter().any(|column| column.name.eq_ignore_ascii_case(name)))
+            .unwrap()
+    }
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Query, ParseError> {
+        parser::parse_query(text, &[])
+    }
+
+    /// Parses a query in which the names of the derived columns can be used
+    /// like stats.
+    pub fn parse_with(text: &str, derived: &[DerivedColumn]) -> Result<Query, ParseError> {
+        parser::parse_query(text, derived)
+    }
+
+    pub fn matches(&self, character: &Character) -> bool {
+        match self {
+            Query::All => true,
+            Query::And(left, right) => left.matches(character) && right.matches(character),
+            Query::Or(left, right) => left.matches(character) || right.matches(character),
+            Query::Not(query) => !query.matches(character),
+            Query::Element(element) => character.element == *element,
+            Query::Position(position) => character.position == *position,
+            Query::Text { field, op, value } => {
+                let value = value.to_lowercase();
+                let test = |text: &str| {
+                    let text = text.to_lowercase();
+                    match op {
+                        TextOp::Contains => text.contains(&value),
+                        TextOp::Equals => text == value,
+                    }
+                };
+
+                let name = || test(&character.name) || test(&character.japanese_name);
+                let nickname = || test(&character.nickname) || test(&character.japanese_nickname);
+
+                match field {
+                    TextField::Any => name() || nickname(),
+                    TextField::Name => name(),
+                    TextField::Nickname => nickname(),
+                    TextField::School => test(&character.school),
+                }
+            }
+            Query::Compare { left, op, right } => {
+                match (left.eval(character), right.eval(character)) {
+                    (Some(left), Some(right)) => op.apply(left, right),
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Expr, ParseError> {
+        parser::parse_expression(text)
+    }
+
+    /// Evaluates the expression, or returns `None` when it depends on stats
+    /// the character doesn't have or divides by zero, as SQL does.
+    pub fn eval(&self, character: &Character) -> Option<f64> {
+        match self {
+            Expr::Number(value) => Some(*value),
+            Expr::Stat(stat) => character.stats.as_ref().map(|stats| stats.get(*stat) as f64),
+            Expr::Id => Some(character.number as f64),
+            Expr::Neg(expr) => expr.eval(character).map(|value| -value),
+            Expr::Binary { op, left, right } => {
+                let left = left.eval(character)?;
+                let right = right.eval(character)?;
+
+                match op {
+                    BinaryOp::Add => Some(left + right),
+                    BinaryOp::Sub => Some(left - right),
+                    BinaryOp::Mul => Some(left * right),
+                    BinaryOp::Div if right == 0.0 => None,
+                    BinaryOp::Div => Some(left / right),
+                }
+            }
+        }
+    }
+}
+
+impl CompareOp {
+    pub fn apply(self, left: f64, right: f64) -> bool {
+        match self {
+            CompareOp::Eq => left == right,
+            CompareOp::Ne => left != right,
+            CompareOp::Lt => left < right,
+            CompareOp::Le => left <= right,
+            CompareOp::Gt => left > right,
+            CompareOp::Ge => left >= right,
+        }
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            CompareOp::Eq => "=",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        }
+    }
+}
+
+impl BinaryOp {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+        }
+    }
+}
@@ -0,0 +1,695 @@
+use crate::{
+    query::{BinaryOp, CompareOp, DerivedColumn, Expr, ParseError, Query, TextField, TextOp},
+    utils::{ELEMENT_LIST, Element, POSITION_LIST, Position, STAT_LIST, Stat},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Number(f64),
+    Colon,
+    Tilde,
+    Compare(CompareOp),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    LParen,
+    RParen,
+    Pipe,
+    Amp,
+    Bang,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Ident(word) => format!("\"{word}\""),
+            Token::Str(text) => format!("string \"{text}\""),
+            Token::Number(value) => format!("number {value}"),
+            Token::Colon => "\":\"".to_string(),
+            Token::Tilde => "\"~\"".to_string(),
+            Token::Compare(op) => format!("\"{}\"", op.to_str()),
+            Token::Plus => "\"+\"".to_string(),
+            Token::Minus => "\"-\"".to_string(),
+            Token::Star => "\"*\"".to_string(),
+            Token::Slash => "\"/\"".to_string(),
+            Token::LParen => "\"(\"".to_string(),
+            Token::RParen => "\")\"".to_string(),
+            Token::Pipe => "\"|\"".to_string(),
+            Token::Amp => "\"&\"".to_string(),
+            Token::Bang => "\"!\"".to_string(),
+        }
+    }
+}
+
+/// Nesting of `not`, `-` and parentheses beyond which a query is refused
+/// rather than overflowing the stack.
+const MAX_DEPTH: usize = 64;
+
+fn error<T>(message: impl Into<String>, position: usize) -> Result<T, ParseError> {
+    Err(ParseError {
+        message: message.into(),
+        position,
+    })
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        let token = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '"' => {
+                let mut value = String::new();
+                i += 1;
+
+                loop {
+                    match chars.get(i) {
+                        None => return error("unterminated string, missing closing '\"'", start),
+                        Some('"') => break,
+                        Some('\\') if i + 1 < chars.len() => {
+                            value.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(c) => {
+                            value.push(*c);
+                            i += 1;
+                        }
+                    }
+                }
+
+                i += 1;
+                tokens.push((Token::Str(value), start));
+                continue;
+            }
+            c if c.is_ascii_digit() || (c == '.' && next.is_some_and(|n| n.is_ascii_digit())) => {
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+
+                let literal: String = chars[start..i].iter().collect();
+                match literal.parse() {
+                    Ok(value) => tokens.push((Token::Number(value), start)),
+                    Err(_) => return error(format!("invalid number \"{literal}\""), start),
+                }
+                continue;
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+
+                tokens.push((Token::Ident(chars[start..i].iter().collect()), start));
+                continue;
+            }
+            ':' => Token::Colon,
+            '~' => Token::Tilde,
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Star,
+            '/' => Token::Slash,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '|' => {
+                if next == Some('|') {
+                    i += 1;
+                }
+                Token::Pipe
+            }
+            '&' => {
+                if next == Some('&') {
+                    i += 1;
+                }
+                Token::Amp
+            }
+            '=' => {
+                if next == Some('=') {
+                    i += 1;
+                }
+                Token::Compare(CompareOp::Eq)
+            }
+            '!' if next == Some('=') => {
+                i += 1;
+                Token::Compare(CompareOp::Ne)
+            }
+            '!' => Token::Bang,
+            '<' if next == Some('=') => {
+                i += 1;
+                Token::Compare(CompareOp::Le)
+            }
+            '<' => Token::Compare(CompareOp::Lt),
+            '>' if next == Some('=') => {
+                i += 1;
+                Token::Compare(CompareOp::Ge)
+            }
+            '>' => Token::Compare(CompareOp::Gt),
+            c => return error(format!("unexpected character '{c}'"), start),
+        };
+
+        i += 1;
+        tokens.push((token, start));
+    }
+
+    Ok(tokens)
+}
+
+pub fn parse_query(text: &str, derived: &[DerivedColumn]) -> Result<Query, ParseError> {
+    let tokens = tokenize(text)?;
+
+    if tokens.is_empty() {
+        return Ok(Query::All);
+    }
+
+    let mut parser = Parser {
+        tokens,
+        index: 0,
+        end: text.chars().count(),
+        depth: 0,
+        derived,
+    };
+
+    let query = parser.parse_or()?;
+
+    match parser.peek() {
+        None => Ok(query),
+        Some(Token::RParen) => error("unmatched \")\"", parser.position()),
+        Some(token) => error(format!("unexpected {}", token.describe()), parser.position()),
+    }
+}
+
+/// Parses a standalone arithmetic expression, as used by derived columns.
+pub fn parse_expression(text: &str) -> Result<Expr, ParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        index: 0,
+        end: text.chars().count(),
+        depth: 0,
+        derived: &[],
+    };
+
+    let expr = parser.parse_sum()?;
+
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => error(format!("unexpected {}", token.describe()), parser.position()),
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    end: usize,
+    /// Current nesting, bounded by `MAX_DEPTH`.
+    depth: usize,
+    /// Derived columns that can be referenced by name, their formula being
+    /// substituted in place.
+    derived: &'a [DerivedColumn],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(token, _)| token)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.index + offset).map(|(token, _)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map(|(_, position)| *position)
+            .unwrap_or(self.end)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).map(|(token, _)| token.clone());
+        self.index += 1;
+        token
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    /// Runs a parsing step one level deeper.
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, ParseError>) -> Result<T, ParseError> {
+        if self.depth >= MAX_DEPTH {
+            return error("the query is nested too deeply", self.position());
+        }
+
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_or(&mut self) -> Result<Query, ParseError> {
+        let mut query = self.parse_and()?;
+
+        while self.peek() == Some(&Token::Pipe) || self.peek_keyword("or") {
+            self.next();
+            let right = self.parse_and()?;
+            query = Query::Or(Box::new(query), Box::new(right));
+        }
+
+        Ok(query)
+    }
+
+    fn parse_and(&mut self) -> Result<Query, ParseError> {
+        let mut query = self.parse_unary()?;
+
+        loop {
+            if self.peek() == Some(&Token::Amp) || self.peek_keyword("and") {
+                self.next();
+            } else if matches!(self.peek(), None | Some(Token::RParen) | Some(Token::Pipe)) || self.peek_keyword("or") {
+                break;
+            }
+
+            let right = self.parse_unary()?;
+            query = Query::And(Box::new(query), Box::new(right));
+        }
+
+        Ok(query)
+    }
+
+    fn parse_unary(&mut self) -> Result<Query, ParseError> {
+        if self.peek() == Some(&Token::Bang) || self.peek_keyword("not") {
+            self.next();
+            return Ok(Query::Not(Box::new(self.nested(Self::parse_unary)?)));
+        }
+
+        self.parse_term()
+    }
+
+    fn parse_term(&mut self) -> Result<Query, ParseError> {
+        let position = self.position();
+
+        match self.peek().cloned() {
+            None => error("expected a filter, found the end of the query", position),
+            Some(Token::LParen) => {
+                // Either a parenthesized arithmetic expression starting a
+                // comparison, as in `(kick+agility)>150`, or a group of filters.
+                let start = self.index;
+
+                let comparison_error = match self.parse_comparison() {
+                    Ok(query) => return Ok(query),
+                    Err(error) => error,
+                };
+
+                self.index = start;
+                self.next();
+
+                let group = self.nested(Self::parse_or).and_then(|query| match self.next() {
+                    Some(Token::RParen) => Ok(query),
+                    _ => error("missing closing \")\"", position),
+                });
+
+                match group {
+                    Ok(query) => Ok(query),
+                    Err(group_error) if group_error.position >= comparison_error.position => Err(group_error),
+                    Err(_) => Err(comparison_error),
+                }
+            }
+            Some(Token::Str(value)) => {
+                self.next();
+                Ok(Query::Text {
+                    field: TextField::Any,
+                    op: TextOp::Contains,
+                    value,
+                })
+            }
+            Some(Token::Ident(word)) => {
+                if let Some(field) = text_field(&word) {
+                    match self.peek_at(1) {
+                        Some(Token::Colon) | Some(Token::Tilde) => {
+                            self.index += 2;
+                            let value = self.parse_value(&word)?;
+                            return Ok(Query::Text { field, op: TextOp::Contains, value });
+                        }
+                        Some(Token::Compare(CompareOp::Eq)) => {
+                            self.index += 2;
+                            let value = self.parse_value(&word)?;
+                            return Ok(Query::Text { field, op: TextOp::Equals, value });
+                        }
+                        _ => (),
+                    }
+                }
+
+                if self.peek_at(1) == Some(&Token::Colon) {
+                    return self.parse_field_filter(&word);
+                }
+
+                let starts_expression = matches!(
+                    self.peek_at(1),
+                    Some(Token::Compare(_)) | Some(Token::Plus) | Some(Token::Minus) | Some(Token::Star) | Some(Token::Slash)
+                );
+
+                if self.numeric_ident(&word).is_some() || starts_expression {
+                    return self.parse_comparison();
+                }
+
+                // A bare word searches names and nicknames
+                self.next();
+                Ok(Query::Text {
+                    field: TextField::Any,
+                    op: TextOp::Contains,
+                    value: word,
+                })
+            }
+            Some(Token::Number(_)) | Some(Token::Minus) => self.parse_comparison(),
+            Some(token) => error(format!("expected a filter, found {}", token.describe()), position),
+        }
+    }
+
+    /// Resolves a word used in an arithmetic expression: the id, a stat, or
+    /// the formula of a valid derived column.
+    fn numeric_ident(&self, word: &str) -> Option<Expr> {
+        if word.eq_ignore_ascii_case("id") || word.eq_ignore_ascii_case("number") {
+            return Some(Expr::Id);
+        }
+
+        if let Some(stat) = Stat::from_name(word) {
+            return Some(Expr::Stat(stat));
+        }
+
+        self.derived
+            .iter()
+            .find(|column| column.name.eq_ignore_ascii_case(word))
+            .and_then(|column| column.expr().cloned())
+    }
+
+    /// Parses `field:value` filters other than the text ones.
+    fn parse_field_filter(&mut self, field: &str) -> Result<Query, ParseError> {
+        let field_position = self.position();
+        self.index += 2;
+
+        match field.to_lowercase().as_str() {
+            "element" | "elem" | "el" | "attr" => {
+                let position = self.position();
+                let value = self.parse_value(field)?;
+
+                match parse_element(&value) {
+                    Some(element) => Ok(Query::Element(element)),
+                    None => error(
+                        format!(
+                            "unknown element \"{value}\", expected one of {}",
+                            ELEMENT_LIST.iter().map(|element| element.db_str().to_lowercase()).collect::<Vec<_>>().join(", ")
+                        ),
+                        position,
+                    ),
+                }
+            }
+            "position" | "pos" => {
+                let position = self.position();
+                let value = self.parse_value(field)?;
+
+                match parse_position(&value) {
+                    Some(pos) => Ok(Query::Position(pos)),
+                    None => error(
+                        format!(
+                            "unknown position \"{value}\", expected one of {}",
+                            POSITION_LIST.iter().map(|position| position.to_str()).collect::<Vec<_>>().join(", ")
+                        ),
+                        position,
+                    ),
+                }
+            }
+            "id" | "number" => {
+                let position = self.position();
+
+                match self.next() {
+                    Some(Token::Number(value)) => Ok(Query::Compare {
+                        left: Expr::Id,
+                        op: CompareOp::Eq,
+                        right: Expr::Number(value),
+                    }),
+                    _ => error(format!("expected a number after \"{field}:\""), position),
+                }
+            }
+            _ => error(
+                format!("unknown field \"{field}\", expected element, pos, name, nick or id"),
+                field_position,
+            ),
+        }
+    }
+
+    /// Parses the value following `field:`, either a word or a quoted string.
+    fn parse_value(&mut self, field: &str) -> Result<String, ParseError> {
+        let position = self.position();
+
+        match self.next() {
+            Some(Token::Ident(value)) | Some(Token::Str(value)) => Ok(value),
+            Some(Token::Number(value)) => Ok(value.to_string()),
+            Some(token) => error(format!("expected a value after \"{field}\", found {}", token.describe()), position),
+            None => error(format!("expected a value after \"{field}\""), position),
+        }
+    }
+
+    fn parse_comparison(&mut self) -> Result<Query, ParseError> {
+        let left = self.parse_sum()?;
+        let position = self.position();
+
+        let op = match self.next() {
+            Some(Token::Compare(op)) => op,
+            Some(token) => return error(format!("expected a comparison such as \">=\", found {}", token.describe()), position),
+            None => return error("expected a comparison such as \">=\" after the expression", position),
+        };
+
+        let right = self.parse_sum()?;
+
+        Ok(Query::Compare { left, op, right })
+    }
+
+    fn parse_sum(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_product()?;
+
+        loop {
+            let op = match self.peek() {
+                Some(Token::Plus) => BinaryOp::Add,
+                Some(Token::Minus) => BinaryOp::Sub,
+                _ => return Ok(expr),
+            };
+
+            self.next();
+            let right = self.parse_product()?;
+            expr = Expr::Binary { op, left: Box::new(expr), right: Box::new(right) };
+        }
+    }
+
+    fn parse_product(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_factor()?;
+
+        loop {
+            let op = match self.peek() {
+                Some(Token::Star) => BinaryOp::Mul,
+                Some(Token::Slash) => BinaryOp::Div,
+                _ => return Ok(expr),
+            };
+
+            self.next();
+            let right = self.parse_factor()?;
+            expr = Expr::Binary { op, left: Box::new(expr), right: Box::new(right) };
+        }
+    }
+
+    fn parse_factor(&mut self) -> Result<Expr, ParseError> {
+        let position = self.position();
+
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Minus) => Ok(Expr::Neg(Box::new(self.nested(Self::parse_factor)?))),
+            Some(Token::LParen) => {
+                let expr = self.nested(Self::parse_sum)?;
+
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => error("missing closing \")\"", position),
+                }
+            }
+            Some(Token::Ident(word)) => match self.numeric_ident(&word) {
+                Some(expr) => Ok(expr),
+                None => {
+                    let known = STAT_LIST
+                        .iter()
+                        .map(|stat| stat.to_str().to_lowercase())
+                        .chain(self.derived.iter().map(|column| column.name.clone()));
+
+                    error(
+                        format!(
+                            "unknown stat \"{word}\", expected one of id, {}",
+                            known.collect::<Vec<_>>().join(", ")
+                        ),
+                        position,
+                    )
+                }
+            },
+            Some(token) => error(format!("expected a stat or a number, found {}", token.describe()), position),
+            None => error("expected a stat or a number, found the end of the query", position),
+        }
+    }
+}
+
+fn text_field(word: &str) -> Option<TextField> {
+    match word.to_lowercase().as_str() {
+        "name" => Some(TextField::Name),
+        "nick" | "nickname" => Some(TextField::Nickname),
+        "text" => Some(TextField::Any),
+        "school" | "team" => Some(TextField::School),
+        _ => None,
+    }
+}
+
+fn parse_element(value: &str) -> Option<Element> {
+    ELEMENT_LIST
+        .into_iter()
+        .find(|element| element.db_str().eq_ignore_ascii_case(value) || element.req_str() == value)
+}
+
+fn parse_position(value: &str) -> Option<Position> {
+    POSITION_LIST
+        .into_iter()
+        .find(|position| position.to_str().eq_ignore_ascii_case(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(stat: Stat) -> Box<Expr> {
+        Box::new(Expr::Stat(stat))
+    }
+
+    fn text(value: &str) -> Box<Query> {
+        Box::new(Query::Text { field: TextField::Any, op: TextOp::Contains, value: value.to_string() })
+    }
+
+    #[test]
+    fn products_bind_tighter_than_sums() {
+        assert_eq!(
+            parse_expression("kick + control * 2").unwrap(),
+            Expr::Binary {
+                op: BinaryOp::Add,
+                left: stat(Stat::Kick),
+                right: Box::new(Expr::Binary { op: BinaryOp::Mul, left: stat(Stat::Control), right: Box::new(Expr::Number(2.0)) }),
+            }
+        );
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            parse_query("a or b c", &[]).unwrap(),
+            Query::Or(text("a"), Box::new(Query::And(text("b"), text("c"))))
+        );
+        assert_eq!(parse_query("a | b & c", &[]), parse_query("a or b and c", &[]));
+    }
+
+    #[test]
+    fn not_applies_to_the_next_term_or_group() {
+        assert_eq!(parse_query("not a b", &[]).unwrap(), Query::And(Box::new(Query::Not(text("a"))), text("b")));
+        assert_eq!(
+            parse_query("!(a or b)", &[]).unwrap(),
+            Query::Not(Box::new(Query::Or(text("a"), text("b"))))
+        );
+    }
+
+    #[test]
+    fn parentheses_group_expressions_or_filters() {
+        assert_eq!(
+            parse_query("(kick+agility)>150", &[]).unwrap(),
+            Query::Compare {
+                left: Expr::Binary { op: BinaryOp::Add, left: stat(Stat::Kick), right: stat(Stat::Agility) },
+                op: CompareOp::Gt,
+                right: Expr::Number(150.0),
+            }
+        );
+        assert_eq!(
+            parse_query("(pos:FW or pos:MF)", &[]).unwrap(),
+            Query::Or(Box::new(Query::Position(Position::FW)), Box::new(Query::Position(Position::MF)))
+        );
+    }
+
+    #[test]
+    fn text_operators() {
+        let parse = |text: &str| parse_query(text, &[]).unwrap();
+
+        assert_eq!(parse("name:go"), Query::Text { field: TextField::Name, op: TextOp::Contains, value: "go".to_string() });
+        assert_eq!(parse("nick~go"), Query::Text { field: TextField::Nickname, op: TextOp::Contains, value: "go".to_string() });
+        assert_eq!(
+            parse("school=\"Raimon Jr\""),
+            Query::Text { field: TextField::School, op: TextOp::Equals, value: "Raimon Jr".to_string() }
+        );
+        assert_eq!(parse("\"two words\""), *text("two words"));
+    }
+
+    #[test]
+    fn derived_column_names_avoid_fields_and_duplicates() {
+        for name in ["total", "gk_rating", "power2"] {
+            assert!(DerivedColumn::is_valid_name(name), "{name}");
+        }
+        for name in ["kick", "Name", "nick", "team", "element", "pos", "text", "Link", "and", "2x", "a b", ""] {
+            assert!(!DerivedColumn::is_valid_name(name), "{name}");
+        }
+
+        let mut columns = vec![DerivedColumn::new("custom_1", "kick"), DerivedColumn::new("Custom_1", "kick")];
+        assert!(!DerivedColumn::is_duplicate(&columns, 0));
+        assert!(DerivedColumn::is_duplicate(&columns, 1));
+
+        columns[0].name = "custom_3".to_string();
+        columns[1].name = "custom_2".to_string();
+        assert_eq!(DerivedColumn::free_name(&columns), "custom_1");
+        columns.push(DerivedColumn::new("custom_1", "kick"));
+        assert_eq!(DerivedColumn::free_name(&columns), "custom_4");
+    }
+
+    #[test]
+    fn derived_columns_are_substituted() {
+        let derived = [DerivedColumn::new("power", "kick * 2")];
+
+        assert_eq!(
+            parse_query("power>=100", &derived).unwrap(),
+            Query::Compare {
+                left: Expr::Binary { op: BinaryOp::Mul, left: stat(Stat::Kick), right: Box::new(Expr::Number(2.0)) },
+                op: CompareOp::Ge,
+                right: Expr::Number(100.0),
+            }
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let position = |text: &str| parse_query(text, &[]).unwrap_err().position;
+
+        assert_eq!(position("kick >"), 6);
+        assert_eq!(position("element:lava"), 8);
+        assert_eq!(position("color:red"), 0);
+        assert_eq!(position("a)"), 1);
+        assert_eq!(position("(kick > 1"), 0);
+        assert_eq!(position("name:\"open"), 5);
+        assert_eq!(position("kick + luck > 1"), 7);
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        let nots = "not ".repeat(10_000) + "a";
+        let parentheses = "(".repeat(10_000) + "kick" + &")".repeat(10_000) + ">1";
+        let negations = "kick > ".to_string() + &"-".repeat(10_000) + "1";
+
+        assert!(parse_query(&nots, &[]).is_err());
+        assert!(parse_query(&parentheses, &[]).is_err());
+        assert!(parse_query(&negations, &[]).is_err());
+        assert!(parse_query(&("not ".repeat(20) + "a"), &[]).is_ok());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{database::SCHOOL_NAME_SQL, query::{BinaryOp, Expr, Query, TextField, TextOp}};
+
+impl Query {
+    /// Compiles the query to a condition on the `characters` table. Strings
+    /// are bound through `?` placeholders, returned in order alongside the
+    /// condition.
+    pub fn to_sql(&self) -> (String, Vec<String>) {
+        let mut params = Vec::new();
+        let condition = self.write_sql(&mut params);
+        (condition, params)
+    }
+
+    fn write_sql(&self, params: &mut Vec<String>) -> String {
+        match self {
+            Query::All => "1".to_string(),
+            Query::And(left, right) => format!("({} AND {})", left.write_sql(params), right.write_sql(params)),
+            Query::Or(left, right) => format!("({} OR {})", left.write_sql(params), right.write_sql(params)),
+            Query::Not(query) => format!("(NOT {})", query.write_sql(params)),
+            Query::Element(element) => {
+                params.push(element.db_str().to_string());
+                "element = ?".to_string()
+            }
+            Query::Position(position) => {
+                params.push(position.to_str().to_string());
+                "position = ?".to_string()
+            }
+            Query::Text { field, op, value } => {
+                let value = value.to_lowercase();
+                let (comparison, pattern) = match op {
+                    TextOp::Contains => ("LIKE ? ESCAPE '\\'", format!("%{}%", escape_like(&value))),
+                    TextOp::Equals => ("= ?", value),
+                };
+
+                // Japanese names are missing from characters cached before
+                // they were scraped
+                let columns: &[&str] = match field {
+                    TextField::Any => &["name", "nickname", "IFNULL(japanese_name, '')", "IFNULL(japanese_nickname, '')"],
+                    TextField::Name => &["name", "IFNULL(japanese_name, '')"],
+                    TextField::Nickname => &["nickname", "IFNULL(japanese_nickname, '')"],
+                    TextField::School => &[SCHOOL_NAME_SQL],
+                };
+
+                let comparisons: Vec<String> = columns
+                    .iter()
+                    .map(|column| {
+                        params.push(pattern.clone());
+                        format!("unicode_lower({column}) {comparison}")
+                    })
+                    .collect();
+
+                format!("({})", comparisons.join(" OR "))
+            }
+            // A division by zero gives NULL, which must be false rather than
+            // unknown so that `not` behaves as in memory
+            Query::Compare { left, op, right } => {
+                format!("IFNULL(({} {} {}), 0)", left.to_sql(), op.to_str(), right.to_sql())
+            }
+        }
+    }
+}
+
+impl Expr {
+    /// Compiles the expression to SQL. Numbers are written as real literals
+    /// so divisions aren't truncated.
+    pub fn to_sql(&self) -> String {
+        match self {
+            Expr::Number(value) => format!("{value:?}"),
+            Expr::Stat(stat) => stat.db_column().to_string(),
+            Expr::Id => "id".to_string(),
+            Expr::Neg(expr) => format!("(-{})", expr.to_sql()),
+            Expr::Binary { op: BinaryOp::Div, left, right } => {
+                format!("(CAST({} AS REAL) / {})", left.to_sql(), right.to_sql())
+            }
+            Expr::Binary { op, left, right } => {
+                format!("({} {} {})", left.to_sql(), op.to_str(), right.to_sql())
+            }
+        }
+    }
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        database::Database,
+        query::Query,
+        utils::{Character, CharacterProfile, Element, Position, Stats},
+    };
+
+    fn character(number: u16, name: &str, japanese_name: &str, kick: u8, control: u8) -> Character {
+        Character {
+            number,
+            name: name.to_string(),
+            nickname: name.to_uppercase(),
+            japanese_name: japanese_name.to_string(),
+            japanese_nickname: String::new(),
+            school: String::new(),
+            element: if number.is_multiple_of(2) { Element::FIRE } else { Element::WIND },
+            position: if number.is_multiple_of(3) { Position::GK } else { Position::FW },
+            stats: Some(Stats { kick, control, technique: 50, pressure: 60, physical: 70, agility: 80, intelligence: 90 }),
+            page_url: String::new(),
+            portrait_url: String::new(),
+            my_team_key: String::new(),
+        }
+    }
+
+    #[test]
+    fn sql_agrees_with_memory() {
+        let path = std::env::temp_dir().join(format!("inazugle_query_{}.sqlite", std::process::id()));
+        let database = Database::connect(path.to_str().unwrap());
+
+        let characters = [
+            character(1, "Éric Évans", "", 80, 0),
+            character(2, "Axel Blaze", "豪炎寺修也", 95, 40),
+            character(3, "Nathan Swift", "", 60, 85),
+            character(4, "ÉLODIE", "", 0, 0),
+        ];
+        for character in &characters {
+            database.store_character(character);
+        }
+        database.store_character_profile(2, &CharacterProfile { affiliation: "Raimon".to_string(), ..Default::default() });
+
+        let cached = database.query_characters(&Query::All);
+
+        let queries = [
+            "kick>=80",
+            "kick / control > 1",
+            "not kick / control > 1",
+            "not (kick / control > 1 or control = 0)",
+            "name:éric",
+            "name=\"éric évans\"",
+            "élodie",
+            "nick:AXEL",
+            "豪炎寺",
+            "school=raimon",
+            "not school:raimon",
+            "element:fire or pos:GK",
+            "-kick + control * 2 >= 10",
+            "name:\"_\"",
+            "name:\"%\"",
+        ];
+
+        for text in queries {
+            let query = Query::parse(text).unwrap();
+
+            let mut in_memory: Vec<u16> = cached.iter().filter(|character| query.matches(character)).map(|character| character.number).collect();
+            let mut in_sql: Vec<u16> = database.query_characters(&query).iter().map(|character| character.number).collect();
+            in_memory.sort();
+            in_sql.sort();
+
+            assert_eq!(in_sql, in_memory, "{text}");
+        }
+
+        drop(database);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -1,8 +1,8 @@
-use crate::{database::Database, utils::{Character, ELEMENT_LIST, Element, ElementFlags, GAME_LIST, Game, GameFlags, POSITION_LIST, Position, PositionFlags, Progress}};
+use crate::{database::Database, utils::{Character, ELEMENT_LIST, Element, ElementFlags, GAME_LIST, Game, GameFlags, POSITION_LIST, Position, PositionFlags, Progress, StatFilters}};
 
 mod fetcher;
 
-use fetcher::{get_character_list, populate_character_stats};
+use fetcher::{fetch_portraits, get_character_list, get_character_stats, get_japanese_names, populate_character_stats};
 
 #[derive(Debug, Clone)]
 pub struct Request {
@@ -12,6 +12,9 @@ pub struct Request {
     elements: ElementFlags,
     positions: PositionFlags,
     games: GameFlags,
+
+    /// Not supported by the website, these are applied client-side.
+    pub stat_filters: StatFilters,
 }
 
 impl Request {
@@ -23,9 +26,36 @@ impl Request {
             elements: ElementFlags::empty(), 
             positions: PositionFlags::empty(), 
             games: GameFlags::empty(),
+
+            stat_filters: StatFilters::default(),
         }
     }
 
+    pub fn with_filters(name: String, elements: ElementFlags, positions: PositionFlags, games: GameFlags, stat_filters: StatFilters) -> Request {
+        Request {
+            client: reqwest::Client::new(),
+
+            name,
+            elements,
+            positions,
+            games,
+
+            stat_filters,
+        }
+    }
+
+    pub fn elements(&self) -> &ElementFlags {
+        &self.elements
+    }
+
+    pub fn positions(&self) -> &PositionFlags {
+        &self.positions
+    }
+
+    pub fn games(&self) -> &GameFlags {
+        &self.games
+    }
+
     pub fn has_element(&self, element: &Element) -> bool {
         self.elements.contains(element.flag())
     }
@@ -50,6 +80,14 @@ impl Request {
         self.games.toggle(game.flag());
     }
 
+    /// Whether a fetched character passes the stat filters of the request.
+    pub fn matches_stats(&self, character: &Character) -> bool {
+        character
+            .stats
+            .as_ref()
+            .is_some_and(|stats| self.stat_filters.matches(stats))
+    }
+
     pub async fn send(&self, cache: &mut Database, max_parallelism: usize, progress: Progress) -> Vec<Character> {
         let mut params = vec![("rc", "0"), ("per_page", "200")];
 
@@ -76,7 +114,106 @@ impl Request {
         }
 
         let mut characters = get_character_list(&self.client, &progress, &params, max_parallelism).await.unwrap();
+        cache.store_my_team_keys(&characters);
         characters = populate_character_stats(cache, &self.client, &progress, characters, max_parallelism).await;
+
+        self.fetch_japanese_names(cache, &params, &mut characters, max_parallelism).await;
+
+        // New characters may be versions of cached ones
+        let numbers: Vec<u16> = characters.iter().map(|character| character.number).collect();
+        cache.resolve_identities(&numbers);
+
+        // With a single game selected, every result is known to appear in it
+        let games: Vec<&Game> = GAME_LIST.iter().filter(|game| self.has_game(game)).collect();
+        if let [game] = games.as_slice() {
+            let ids: Vec<u16> = characters.iter().map(|character| character.number).collect();
+            cache.record_character_games(&ids, **game);
+        }
+
         characters
     }
+
+    /// Downloads the portraits of the results that aren't stored yet. Kept out
+    /// of `send` so that the results show while the portraits download.
+    pub async fn fetch_portraits(&self, cache: &mut Database, characters: &[Character], max_parallelism: usize) {
+        fetch_portraits(cache, &self.client, characters, max_parallelism).await;
+    }
+
+    /// Looks up the Japanese names of the results that lack one. Secret
+    /// characters, which have no name, and characters the Japanese listing
+    /// didn't name before are skipped. The name search only matches names in
+    /// the language of the page, so it is left out of the Japanese search and
+    /// the listing is read only until every wanted character is found.
+    async fn fetch_japanese_names(&self, cache: &mut Database, params: &[(&str, &str)], characters: &mut [Character], max_parallelism: usize) {
+        let misses = cache.japanese_name_misses();
+        let wanted: Vec<u16> = characters
+            .iter()
+            .filter(|character| !character.name.is_empty() && character.japanese_name.is_empty())
+            .filter(|character| !misses.contains(&character.number))
+            .map(|character| character.number)
+            .collect();
+
+        if wanted.is_empty() {
+            return;
+        }
+
+        let japanese_params: Vec<(&str, &str)> = params
+            .iter()
+            .copied()
+            .filter(|(key, _)| *key != "name_search")
+            .collect();
+
+        let names = match get_japanese_names(&self.client, &japanese_params, &wanted, max_parallelism).await {
+            Ok(names) => names,
+            Err(error) => {
+                eprintln!("Error while fetching Japanese names : {error}");
+                return;
+            }
+        };
+
+        let mut missed = Vec::new();
+        for character in characters.iter_mut().filter(|character| wanted.contains(&character.number)) {
+            match names.get(&character.number) {
+                Some((name, nickname)) => {
+                    character.japanese_name = name.clone();
+                    character.japanese_nickname = nickname.clone();
+                }
+                None => missed.push(character.number),
+            }
+        }
+
+        cache.store_japanese_names(characters);
+        cache.record_japanese_name_misses(&missed);
+    }
+}
+
+/// Fetches the stats and profile of a character from the website again,
+/// bypassing the cache, and stores them. The previous stats are kept if the fetch fails.
+pub async fn refresh_character(cache: &mut Database, mut character: Character) -> Character {
+    let client = reqwest::Client::new();
+
+    // Taken out so that only freshly parsed stats get stored with a new
+    // fetch time
+    let previous_stats = character.stats.take();
+
+    match get_character_stats(client.clone(), &mut character).await {
+        Ok(profile) => {
+            if character.stats.is_some() {
+                cache.store_character(&character);
+            }
+            if let Some(profile) = profile {
+                cache.store_character_profile(character.number, &profile);
+                character.school = profile.affiliation;
+            }
+        }
+        Err(error) => eprintln!("Error while fetching {0} : {1}", character.page_url, error),
+    }
+
+    if character.stats.is_none() {
+        character.stats = previous_stats;
+    }
+
+    fetch_portraits(cache, &client, std::slice::from_ref(&character), 1).await;
+
+    character
 }
\ No newline at end of file
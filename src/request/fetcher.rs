@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use futures::stream::{self, StreamExt};
 
 use reqwest::Client;
@@ -5,15 +7,82 @@ use reqwest::Client;
 mod search_parser;
 mod character_parser;
 
-use character_parser::get_character_stats;
-use search_parser::parse_search_result;
+pub use character_parser::get_character_stats;
+use search_parser::{Names, parse_name_list, parse_search_result};
 
 use crate::{
-    database::Database, utils::{BASE_URL, Character, Progress, SEARCH_URL}
+    database::Database, utils::{BASE_URL, Character, Progress, SEARCH_URL, SEARCH_URL_JA}
 };
 
 pub async fn get_character_list(client: &Client, progress: &Progress, params: &Vec<(&str, &str)>, max_parallelism: usize) -> Result<Vec<Character>, Box<dyn std::error::Error>> {
-    let response = client.post(BASE_URL.to_owned() + SEARCH_URL)
+    search(client, SEARCH_URL, Some(progress), params, max_parallelism).await
+}
+
+/// Japanese name and nickname of the characters matching the search, by
+/// number. Pages are fetched a batch at a time and the crawl stops once every
+/// wanted character is named, so that a broad search only reads the pages it
+/// needs. Every page is read with a parser that fails instead of panicking,
+/// so a layout differing from the English pages only loses the Japanese names.
+pub async fn get_japanese_names(client: &Client, params: &Vec<(&str, &str)>, wanted: &[u16], max_parallelism: usize) -> Result<Names, String> {
+    let response = client.post(BASE_URL.to_owned() + SEARCH_URL_JA)
+        .form(&params)
+        .send()
+        .await
+        .map_err(|error| error.to_string())?;
+
+    let q = response.url()
+        .query_pairs()
+        .find(|(key, _)| key == "q")
+        .map(|(_, value)| value.to_string())
+        .unwrap_or_default();
+
+    let text_data = response.text().await.map_err(|error| error.to_string())?;
+    let (mut names, nb_pages) = parse_name_list(&text_data).map_err(|error| error.to_string())?;
+
+    let mut page_indices = 2..=nb_pages;
+
+    while !wanted.iter().all(|number| names.contains_key(number)) {
+        let batch: Vec<u8> = page_indices.by_ref().take(max_parallelism.max(1)).collect();
+        if batch.is_empty() {
+            break;
+        }
+
+        let pages = stream::iter(batch)
+            .map(|page_index| {
+                let client = client.clone();
+                let q = q.clone();
+
+                async move {
+                    let page_string = page_index.to_string();
+                    let new_params = [("q", q.as_str()), ("per_page", "200"), ("page", page_string.as_str())];
+
+                    let response = client.post(BASE_URL.to_owned() + SEARCH_URL_JA)
+                        .form(&new_params)
+                        .send()
+                        .await
+                        .map_err(|error| error.to_string())?;
+                    let text_data = response.text().await.map_err(|error| error.to_string())?;
+
+                    parse_name_list(&text_data)
+                        .map(|(names, _)| names)
+                        .map_err(|error| error.to_string())
+                }
+            })
+            .buffer_unordered(max_parallelism)
+            .collect::<Vec<_>>()
+            .await;
+
+        for page in pages {
+            names.extend(page?);
+        }
+    }
+
+    Ok(names)
+}
+
+/// Fetches every page of a search, reporting them to the progress if any.
+async fn search(client: &Client, search_url: &'static str, progress: Option<&Progress>, params: &Vec<(&str, &str)>, max_parallelism: usize) -> Result<Vec<Character>, Box<dyn std::error::Error>> {
+    let response = client.post(BASE_URL.to_owned() + search_url)
         .form(&params)
         .send()
         .await?;
@@ -32,12 +101,14 @@ pub async fn get_character_list(client: &Client, progress: &Progress, params: &V
 
     let (mut character_summaries, nb_pages) = parse_search_result(&text_data)?;
 
-    progress.set_page_total(nb_pages);
-    progress.inc_page();
+    if let Some(progress) = progress {
+        progress.set_page_total(nb_pages);
+        progress.inc_page();
+    }
     
     let new_client = client.clone();
     let new_q = q.clone();
-    let new_progress = progress.clone();
+    let new_progress = progress.cloned();
 
     let characters_futures = stream::iter(2..=nb_pages)
         .map(move |page_index| {
@@ -49,14 +120,16 @@ pub async fn get_character_list(client: &Client, progress: &Progress, params: &V
             async move {
                 let new_params = [("q", q.as_str()), ("per_page", "200"), ("page", page_string.as_str())];
 
-                let new_response = client.post(BASE_URL.to_owned() + SEARCH_URL)
+                let new_response = client.post(BASE_URL.to_owned() + search_url)
                 .form(&new_params)
                 .send()
                 .await
                 .unwrap();
 
                 let new_text_data = new_response.text().await.unwrap();
-                progress_clone.inc_page();
+                if let Some(progress) = &progress_clone {
+                    progress.inc_page();
+                }
                 parse_search_result(&new_text_data).unwrap().0
             }
         })
@@ -92,11 +165,15 @@ pub async fn populate_character_stats(database: &mut Database, client: &Client,
             async move {
                 if !database.populate_character_data(&mut character) {
                     // If the character is not in the database
-                    get_character_stats(client, &mut character).await.unwrap();
+                    let profile = get_character_stats(client, &mut character).await.unwrap();
                     
                     if character.stats.is_some() {
                         database.store_character(&character);
                     }
+                    if let Some(profile) = profile {
+                        database.store_character_profile(character.number, &profile);
+                        character.school = profile.affiliation;
+                    }
                 }
                 
                 progress_clone.inc_char();
@@ -107,4 +184,57 @@ pub async fn populate_character_stats(database: &mut Database, client: &Client,
         .collect::<Vec<_>>();
 
     character_futures.await
-}
\ No newline at end of file
+}
+/// Downloads the portraits of the characters that aren't stored yet, at most
+/// `max_parallelism` at a time. Failed downloads are skipped and retried on
+/// the next search.
+///
+/// The portrait selectors are guesses: an image shared by several characters
+/// is a logo or a placeholder rather than a portrait, so it is not downloaded
+/// and any copy already stored is dropped.
+pub async fn fetch_portraits(database: &mut Database, client: &Client, characters: &[Character], max_parallelism: usize) {
+    let mut shared = database.shared_portrait_urls();
+    let mut seen = HashSet::new();
+    for character in characters.iter().filter(|character| !character.portrait_url.is_empty()) {
+        if !seen.insert(character.portrait_url.as_str()) {
+            shared.insert(character.portrait_url.clone());
+        }
+    }
+    database.delete_portraits_at(&shared);
+
+    let missing: Vec<(u16, String)> = characters
+        .iter()
+        .filter(|character| !character.portrait_url.is_empty())
+        .filter(|character| !shared.contains(&character.portrait_url))
+        .filter(|character| !database.has_portrait(character.number, &character.portrait_url))
+        .map(|character| (character.number, character.portrait_url.clone()))
+        .collect();
+
+    let new_client = client.clone();
+    let new_database = database.clone();
+
+    let portrait_futures = stream::iter(missing)
+        .map(move |(number, url)| {
+            let client = new_client.clone();
+            let database = new_database.clone();
+
+            async move {
+                let response = match client.get(&url).send().await.and_then(|response| response.error_for_status()) {
+                    Ok(response) => response,
+                    Err(error) => {
+                        eprintln!("Error while fetching portrait {url} : {error}");
+                        return;
+                    }
+                };
+
+                match response.bytes().await {
+                    Ok(data) => database.store_portrait(number, &url, &data),
+                    Err(error) => eprintln!("Error while fetching portrait {url} : {error}"),
+                }
+            }
+        })
+        .buffer_unordered(max_parallelism)
+        .collect::<Vec<_>>();
+
+    portrait_futures.await;
+}
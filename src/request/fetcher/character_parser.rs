@@ -1,19 +1,28 @@
 use std::error::Error;
 
 use reqwest::Client;
-use scraper::{Html, Selector};
+use scraper::{ElementRef, Html, Selector};
 
-use crate::utils::{Character, Stats};
+use crate::utils::{Character, CharacterProfile, Stats, absolute_url};
 
-pub async fn get_character_stats(client: Client, character: &mut Character) -> Result<(), Box<dyn Error>> {
+/// Fetches the page of a character, filling in its stats and returning the
+/// rest of its profile.
+pub async fn get_character_stats(client: Client, character: &mut Character) -> Result<Option<CharacterProfile>, Box<dyn Error>> {
     if character.name == "" { // Secret character
-        return Ok(())
+        return Ok(None)
     }
 
     let doc = client.get(&character.page_url).send().await?;
 
     let document = Html::parse_document(&doc.text().await?);
 
+    parse_character_page(&document, character)
+}
+
+/// Reads the stats, portrait and profile of a character from its page. A page
+/// without stats, e.g. an error page, gives no portrait and no profile so
+/// that the stored ones are kept.
+fn parse_character_page(document: &Html, character: &mut Character) -> Result<Option<CharacterProfile>, Box<dyn Error>> {
     let stats_selector = Selector::parse("ul.param")?;
 
     let stats_block = match document
@@ -22,10 +31,18 @@ pub async fn get_character_stats(client: Client, character: &mut Character) -> R
             Some(block) => block,
             None => { 
                 eprintln!("No stats found for {0} : {1}", character.name, character.page_url);
-                return Ok(())
+                return Ok(None)
             }
         };
 
+    let block = profile_block(stats_block)?;
+
+    if character.portrait_url.is_empty() {
+        character.portrait_url = portrait(document, block)?.map(absolute_url).unwrap_or_default();
+    }
+
+    let profile = block.map(parse_profile).transpose()?;
+
     let li_selector = Selector::parse("tr > td")?;
     let li_iterator = stats_block.select(&li_selector);
 
@@ -37,11 +54,16 @@ pub async fn get_character_stats(client: Client, character: &mut Character) -> R
             Err(_) => {
                 stats.push(0);
                 eprintln!("Error while parsing stats for {0} : {1}", character.name, character.page_url);
-                return Ok(())
+                return Ok(profile)
             }
         }
     }
 
+    if stats.len() < 7 {
+        eprintln!("Error while parsing stats for {0} : {1}", character.name, character.page_url);
+        return Ok(profile)
+    }
+
     let stats = Stats {
         kick: stats[0],
         control: stats[1],
@@ -54,5 +76,224 @@ pub async fn get_character_stats(client: Client, character: &mut Character) -> R
 
     character.stats = Some(stats);
 
-    return Ok(())
-}
\ No newline at end of file
+    return Ok(profile)
+}
+
+/// The element holding the profile of the character: the closest ancestor
+/// of the stats with labelled entries, which leaves out the lists and tables
+/// of the rest of the site.
+fn profile_block(stats_block: ElementRef) -> Result<Option<ElementRef>, Box<dyn Error>> {
+    let entry_selector = Selector::parse("dl, th")?;
+
+    Ok(stats_block
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .take_while(|element| !matches!(element.value().name(), "body" | "html"))
+        .find(|element| element.select(&entry_selector).next().is_some()))
+}
+
+/// Link to the portrait of the character: the first image of the profile
+/// block, lazy-loaded ones having their source in data-src, or else the
+/// image shared by the page, which may only be a banner of the site.
+fn portrait<'a>(document: &'a Html, block: Option<ElementRef<'a>>) -> Result<Option<&'a str>, Box<dyn Error>> {
+    let img_selector = Selector::parse("img")?;
+    let image_selector = Selector::parse(r#"meta[property="og:image"]"#)?;
+
+    let in_block = block.and_then(|block| {
+        block
+            .select(&img_selector)
+            .filter_map(|img| img.value().attr("data-src").or(img.value().attr("src")))
+            .find(|src| !src.is_empty())
+    });
+
+    Ok(in_block.or_else(|| {
+        document
+            .select(&image_selector)
+            .filter_map(|meta| meta.value().attr("content"))
+            .next()
+    }))
+}
+
+/// Reads the labelled entries of the profile block, from definition lists and
+/// from table rows with a header cell. The stats table has no header cells so
+/// it is left out.
+fn parse_profile(block: ElementRef) -> Result<CharacterProfile, Box<dyn Error>> {
+    let mut profile = CharacterProfile::default();
+
+    // Text nodes of an entry are kept apart so that techniques listed one per
+    // line or item stay separate
+    let text = |element: ElementRef| element.text().map(str::trim).filter(|part| !part.is_empty()).map(str::to_string).collect::<Vec<_>>();
+
+    let mut entries = Vec::new();
+
+    let dl_selector = Selector::parse("dl")?;
+    let dt_dd_selector = Selector::parse("dt, dd")?;
+    for list in block.select(&dl_selector) {
+        let mut label = None;
+
+        for element in list.select(&dt_dd_selector) {
+            match (element.value().name(), label.take()) {
+                ("dt", _) => label = Some(text(element).join(" ")),
+                ("dd", Some(label)) => entries.push((label, text(element))),
+                _ => (),
+            }
+        }
+    }
+
+    let row_selector = Selector::parse("tr")?;
+    let th_selector = Selector::parse("th")?;
+    let td_selector = Selector::parse("td")?;
+    for row in block.select(&row_selector) {
+        if let (Some(header), Some(cell)) = (row.select(&th_selector).next(), row.select(&td_selector).next()) {
+            entries.push((text(header).join(" "), text(cell)));
+        }
+    }
+
+    for (label, parts) in entries {
+        if label.is_empty() || parts.is_empty() {
+            continue;
+        }
+
+        if CharacterProfile::is_techniques_label(&label) {
+            profile.techniques.extend(
+                parts
+                    .iter()
+                    .flat_map(|part| part.split(['/', ',', '、']))
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string),
+            );
+            continue;
+        }
+
+        let value = parts.join(" ");
+
+        match profile.field_mut(&label) {
+            Some(field) if field.is_empty() => *field = value,
+            Some(_) => (),
+            None => profile.other.push((label, value)),
+        }
+    }
+
+    Ok(profile)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{Element, Position};
+
+    const PAGE: &str = r#"
+        <html><head><meta property="og:image" content="https://zukan.inazuma.jp/images/ogp.png"></head><body>
+            <header><dl><dt>Language</dt><dd>English</dd></dl><img src="/images/logo.png"></header>
+            <div class="charaDetail">
+                <img src="" data-src="/images/chara/mark.png">
+                <table>
+                    <tr><th>School</th><td>Raimon</td></tr>
+                    <tr><th>School year</th><td>2nd year</td></tr>
+                    <tr><th>Gender</th><td>Male</td></tr>
+                    <tr><th>Team technique</th><td>Inazuma Break</td></tr>
+                    <tr><th>Techniques</th><td><ul><li>God Hand</li><li>Majin The Hand / Fire Tornado</li></ul></td></tr>
+                </table>
+                <dl><dt>Image</dt><dd>Headband</dd><dt>How to recruit:</dt><dd>Story</dd></dl>
+                <ul class="param"><li><table>
+                    <tr><td>80</td><td>70</td><td>60</td><td>50</td><td>40</td><td>30</td><td>20</td></tr>
+                </table></li></ul>
+            </div>
+            <footer><table><tr><th>Team</th><td>Level-5</td></tr></table></footer>
+        </body></html>
+    "#;
+
+    fn profile(html: &str) -> CharacterProfile {
+        let document = Html::parse_document(html);
+        let stats_selector = Selector::parse("ul.param").unwrap();
+        let stats_block = document.select(&stats_selector).next().unwrap();
+
+        parse_profile(profile_block(stats_block).unwrap().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn profile_matches_whole_labels() {
+        let profile = profile(PAGE);
+
+        assert_eq!(profile.affiliation, "Raimon");
+        assert_eq!(profile.grade, "2nd year");
+        assert_eq!(profile.gender, "Male");
+        assert_eq!(profile.recruitment, "Story");
+        assert_eq!(profile.techniques, vec!["God Hand", "Majin The Hand", "Fire Tornado"]);
+        assert_eq!(
+            profile.other,
+            vec![
+                ("Image".to_string(), "Headband".to_string()),
+                ("Team technique".to_string(), "Inazuma Break".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn page_without_stats_gives_no_profile() {
+        let document = Html::parse_document("<html><body><h1>Too many requests</h1><dl><dt>School</dt><dd>?</dd></dl></body></html>");
+        let mut character = Character {
+            number: 1,
+            name: "Mark Evans".to_string(),
+            nickname: String::new(),
+            japanese_name: String::new(),
+            japanese_nickname: String::new(),
+            school: "Raimon".to_string(),
+            element: Element::MOUNTAIN,
+            position: Position::GK,
+            stats: None,
+            page_url: String::new(),
+            portrait_url: String::new(),
+            my_team_key: String::new(),
+        };
+
+        assert_eq!(parse_character_page(&document, &mut character).unwrap(), None);
+        assert!(character.stats.is_none());
+
+        let document = Html::parse_document(PAGE);
+        let profile = parse_character_page(&document, &mut character).unwrap().unwrap();
+
+        assert_eq!(profile.affiliation, "Raimon");
+        assert_eq!(character.stats.as_ref().map(|stats| stats.kick), Some(80));
+    }
+
+    #[test]
+    fn profile_ignores_the_rest_of_the_site() {
+        let profile = profile(PAGE);
+
+        assert!(profile.other.iter().all(|(label, _)| label != "Language"));
+        assert_ne!(profile.affiliation, "Level-5");
+    }
+
+    #[test]
+    fn portrait_comes_from_the_profile_or_the_shared_image() {
+        let mut character = Character {
+            number: 1,
+            name: "Mark Evans".to_string(),
+            nickname: String::new(),
+            japanese_name: String::new(),
+            japanese_nickname: String::new(),
+            school: String::new(),
+            element: Element::MOUNTAIN,
+            position: Position::GK,
+            stats: None,
+            page_url: String::new(),
+            portrait_url: String::new(),
+            my_team_key: String::new(),
+        };
+
+        parse_character_page(&Html::parse_document(PAGE), &mut character).unwrap();
+        assert_eq!(character.portrait_url, "https://zukan.inazuma.jp/images/chara/mark.png");
+
+        // Without an image next to the stats, the shared one is used
+        let page = PAGE.replace(r#"<img src="" data-src="/images/chara/mark.png">"#, "");
+        character.portrait_url.clear();
+        parse_character_page(&Html::parse_document(&page), &mut character).unwrap();
+        assert_eq!(character.portrait_url, "https://zukan.inazuma.jp/images/ogp.png");
+
+        // A known portrait is kept
+        character.portrait_url = "https://zukan.inazuma.jp/list.png".to_string();
+        parse_character_page(&Html::parse_document(PAGE), &mut character).unwrap();
+        assert_eq!(character.portrait_url, "https://zukan.inazuma.jp/list.png");
+    }
+}
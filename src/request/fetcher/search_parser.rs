@@ -1,8 +1,8 @@
-use std::error::Error;
+use std::{collections::HashMap, error::Error};
 
 use scraper::{Html, Selector};
 
-use crate::utils::{BASE_URL, Character, Element, Position};
+use crate::utils::{BASE_URL, Character, Element, Position, absolute_url};
 
 pub fn parse_search_result(text_data: &String) -> Result<(Vec<Character>, u8), Box<dyn Error>> {
     let document = Html::parse_document(text_data);
@@ -16,6 +16,7 @@ pub fn parse_search_result(text_data: &String) -> Result<(Vec<Character>, u8), B
     let tbody_sel = Selector::parse("table > tbody")?;
     let input_sel = Selector::parse("input.my-team-checkbox")?;
     let namebox_link_sel = Selector::parse(".nameBox p > a")?;
+    let img_sel = Selector::parse("img")?;
 
     let mut results = Vec::new();
 
@@ -25,6 +26,7 @@ pub fn parse_search_result(text_data: &String) -> Result<(Vec<Character>, u8), B
 
         let mut name = "".to_owned();
         let mut nickname = "".to_owned();
+        let mut my_team_key = String::new();
 
         if let Some(checkbox_td) = td_iterator.next() { // First TD
             let input = checkbox_td.select(&input_sel).next().unwrap();
@@ -40,6 +42,12 @@ pub fn parse_search_result(text_data: &String) -> Result<(Vec<Character>, u8), B
                 .attr("data-nickname")
                 .map(|s| s.trim().to_string())
                 .unwrap_or_default();
+
+            my_team_key = input
+                .value()
+                .attr("value")
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default();
         }
 
         let number = {
@@ -88,14 +96,26 @@ pub fn parse_search_result(text_data: &String) -> Result<(Vec<Character>, u8), B
             BASE_URL.to_owned() + page_href
         };
 
+        // Images may be lazy-loaded, the actual source being in data-src
+        let portrait_src = tbody
+            .select(&img_sel)
+            .filter_map(|img| img.value().attr("data-src").or(img.value().attr("src")))
+            .next()
+            .unwrap_or_default();
+
         results.push(Character {
             number,
             name,
             nickname,
+            japanese_name: String::new(),
+            japanese_nickname: String::new(),
+            school: String::new(),
             element,
             position,
             stats: None,
             page_url,
+            portrait_url: absolute_url(portrait_src),
+            my_team_key,
         });
     }
 
@@ -109,4 +129,90 @@ pub fn parse_search_result(text_data: &String) -> Result<(Vec<Character>, u8), B
     let last_page_nb = last_page_str.trim().parse().unwrap();
 
     Ok((results, last_page_nb))
-}
\ No newline at end of file
+}
+/// Name and nickname of characters, by number.
+pub type Names = HashMap<u16, (String, String)>;
+
+/// Reads the number, name and nickname of the characters of a search page,
+/// along with the number of pages. Unlike `parse_search_result`, missing
+/// elements are reported as errors, as the layout of the Japanese pages is
+/// only assumed to match the English one.
+pub fn parse_name_list(text_data: &str) -> Result<(Names, u8), Box<dyn Error>> {
+    let document = Html::parse_document(text_data);
+
+    let div_sel = Selector::parse("div.charaListResult")?;
+    let tbody_sel = Selector::parse("table > tbody")?;
+    let td_sel = Selector::parse("tr > td")?;
+    let input_sel = Selector::parse("input.my-team-checkbox")?;
+    let page_sel = Selector::parse("ul.pagination > li")?;
+
+    let container = document.select(&div_sel).next().ok_or("No character list in the page")?;
+
+    let mut names = HashMap::new();
+
+    for tbody in container.select(&tbody_sel) {
+        let mut tds = tbody.select(&td_sel);
+
+        let input = tds
+            .next()
+            .and_then(|td| td.select(&input_sel).next())
+            .ok_or("No character checkbox in a row")?;
+        let number: u16 = tds
+            .next()
+            .ok_or("No character number in a row")?
+            .text()
+            .collect::<String>()
+            .trim()
+            .parse()?;
+
+        let attr = |name| input.value().attr(name).map(|value| value.trim().to_string()).unwrap_or_default();
+        let name = attr("data-chara-name");
+
+        if !name.is_empty() {
+            names.insert(number, (name, attr("data-nickname")));
+        }
+    }
+
+    // A single page of results has no pagination
+    let page_buttons: Vec<_> = document.select(&page_sel).collect();
+    let pages = match page_buttons.len() {
+        0 => 1,
+        len if len >= 2 => page_buttons[len - 2].text().collect::<String>().trim().parse()?,
+        _ => return Err("Unexpected pagination".into()),
+    };
+
+    Ok((names, pages))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_list_reads_rows() {
+        let html = r#"
+            <div class="charaListResult"><table>
+                <tbody><tr>
+                    <td><input class="my-team-checkbox" data-chara-name=" 円堂守 " data-nickname="キャプテン"></td>
+                    <td>12</td>
+                </tr></tbody>
+                <tbody><tr>
+                    <td><input class="my-team-checkbox" data-chara-name=""></td>
+                    <td>13</td>
+                </tr></tbody>
+            </table></div>
+        "#;
+
+        let (names, pages) = parse_name_list(html).unwrap();
+
+        assert_eq!(pages, 1);
+        assert_eq!(names.len(), 1);
+        assert_eq!(names[&12], ("円堂守".to_string(), "キャプテン".to_string()));
+    }
+
+    #[test]
+    fn name_list_fails_on_unknown_layout() {
+        assert!(parse_name_list("<html><body><p>Maintenance</p></body></html>").is_err());
+        assert!(parse_name_list(r#"<div class="charaListResult"><table><tbody><tr><td>?</td></tr></tbody></table></div>"#).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+/// A character recruited in the save file of the current profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RosterEntry {
+    pub character_id: u16,
+    /// Date of recruitment as entered, e.g. `2025-11-14`. Empty when unknown.
+    pub recruited_on: String,
+    pub level: Option<u16>,
+    pub notes: String,
+}
+
+impl RosterEntry {
+    pub fn new(character_id: u16) -> RosterEntry {
+        RosterEntry {
+            character_id,
+            recruited_on: String::new(),
+            level: None,
+            notes: String::new(),
+        }
+    }
+}
+
+/// Which characters to keep according to the roster.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ownership {
+    Any,
+    Owned,
+    NotOwned,
+}
+
+impl Ownership {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Ownership::Any => "Any",
+            Ownership::Owned => "Only owned",
+            Ownership::NotOwned => "Not yet owned",
+        }
+    }
+
+    pub fn matches(self, owned: bool) -> bool {
+        match self {
+            Ownership::Any => true,
+            Ownership::Owned => owned,
+            Ownership::NotOwned => !owned,
+        }
+    }
+}
+
+/// A line of a roster CSV, the character being given by number or by name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvRow {
+    pub character: String,
+    pub recruited_on: String,
+    pub level: Option<u16>,
+    pub notes: String,
+}
+
+/// Reads a roster CSV with the columns `character,recruited_on,level,notes`,
+/// only the first being required. A header line starting with `id`,
+/// `character` or `name` is skipped, as is a leading byte order mark. Returns
+/// the rows and the numbers of the lines that couldn't be read.
+pub fn parse_csv(text: &str) -> (Vec<CsvRow>, Vec<usize>) {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    let mut first = true;
+
+    for (line, fields) in split_csv_records(text) {
+        if fields.iter().all(String::is_empty) {
+            continue;
+        }
+
+        if std::mem::take(&mut first) && ["id", "character", "name"].contains(&fields[0].to_lowercase().as_str()) {
+            continue;
+        }
+
+        let field = |index: usize| fields.get(index).map_or("", |field| field.as_str());
+
+        let level = match field(2) {
+            "" => None,
+            level => match level.parse() {
+                Ok(level) => Some(level),
+                Err(_) => {
+                    errors.push(line);
+                    continue;
+                }
+            },
+        };
+
+        if field(0).is_empty() {
+            errors.push(line);
+            continue;
+        }
+
+        rows.push(CsvRow {
+            character: field(0).to_string(),
+            recruited_on: field(1).to_string(),
+            level,
+            notes: field(3).to_string(),
+        });
+    }
+
+    (rows, errors)
+}
+
+/// Writes the roster in the format read by `parse_csv`.
+pub fn export_csv(entries: &[RosterEntry]) -> String {
+    let mut csv = "id,recruited_on,level,notes\n".to_string();
+
+    for entry in entries {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            entry.character_id,
+            quote(&entry.recruited_on),
+            entry.level.map_or(String::new(), |level| level.to_string()),
+            quote(&entry.notes),
+        ));
+    }
+
+    csv
+}
+
+/// Splits the text into records of comma-separated fields, honoring
+/// double-quoted fields where `""` stands for a quote and line breaks are
+/// kept. Each record comes with the number of the line it starts on. Fields
+/// are trimmed.
+fn split_csv_records(text: &str) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().unwrap();
+
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            '\r' if chars.peek() == Some(&'\n') => (),
+            '\n' if quoted => {
+                field.push('\n');
+                line += 1;
+            }
+            '\n' => {
+                records.push((start, std::mem::replace(&mut fields, vec![String::new()])));
+                line += 1;
+                start = line;
+            }
+            c => field.push(c),
+        }
+    }
+    records.push((start, fields));
+
+    records
+        .into_iter()
+        .map(|(line, fields)| (line, fields.into_iter().map(|field| field.trim().to_string()).collect()))
+        .collect()
+}
+
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_round_trips() {
+        let entries = vec![
+            RosterEntry {
+                character_id: 12,
+                recruited_on: "2025-11-14".to_string(),
+                level: Some(40),
+                notes: "Scouted, \"lucky\"\nTrain kick next".to_string(),
+            },
+            RosterEntry::new(7),
+        ];
+
+        let (rows, errors) = parse_csv(&export_csv(&entries));
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            rows,
+            vec![
+                CsvRow {
+                    character: "12".to_string(),
+                    recruited_on: "2025-11-14".to_string(),
+                    level: Some(40),
+                    notes: "Scouted, \"lucky\"\nTrain kick next".to_string(),
+                },
+                CsvRow {
+                    character: "7".to_string(),
+                    recruited_on: String::new(),
+                    level: None,
+                    notes: String::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn header_is_skipped_after_bom_and_blank_lines() {
+        let (rows, errors) = parse_csv("\u{feff}\r\n\r\nname,recruited_on,level,notes\r\nEndou,,5,\r\n");
+
+        assert!(errors.is_empty());
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].character, "Endou");
+        assert_eq!(rows[0].level, Some(5));
+    }
+
+    #[test]
+    fn errors_give_the_starting_line() {
+        let (rows, errors) = parse_csv("id,recruited_on,level,notes\n1,,,\"two\nlines\"\n2,,high\n,,3\n");
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(errors, vec![4, 5]);
+    }
+}
@@ -0,0 +1,273 @@
+use crate::utils::{Character, ELEMENT_LIST, Element, Position, STAT_LIST};
+
+mod optimizer;
+
+pub use optimizer::{Lineup, OptimizerConfig, OptimizerResult, StatWeights, eligible_characters, optimize, optimize_cached};
+
+/// Number of players on the pitch.
+pub const TEAM_SIZE: usize = 11;
+/// Maximum number of substitutes.
+pub const BENCH_SIZE: usize = 5;
+
+/// A formation, described by the number of players on each line besides the
+/// goalkeeper.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Formation {
+    pub name: &'static str,
+    pub defenders: usize,
+    pub midfielders: usize,
+    pub forwards: usize,
+}
+
+pub const FORMATION_LIST: [Formation; 7] = [
+    Formation { name: "4-4-2", defenders: 4, midfielders: 4, forwards: 2 },
+    Formation { name: "4-3-3", defenders: 4, midfielders: 3, forwards: 3 },
+    Formation { name: "4-5-1", defenders: 4, midfielders: 5, forwards: 1 },
+    Formation { name: "3-5-2", defenders: 3, midfielders: 5, forwards: 2 },
+    Formation { name: "3-4-3", defenders: 3, midfielders: 4, forwards: 3 },
+    Formation { name: "5-3-2", defenders: 5, midfielders: 3, forwards: 2 },
+    Formation { name: "5-4-1", defenders: 5, midfielders: 4, forwards: 1 },
+];
+
+impl Formation {
+    pub fn from_name(name: &str) -> Option<Formation> {
+        FORMATION_LIST.into_iter().find(|formation| formation.name == name)
+    }
+
+    /// Position expected in each of the eleven slots: the goalkeeper first,
+    /// then defenders, midfielders and forwards.
+    pub fn slots(&self) -> Vec<Position> {
+        let mut slots = vec![Position::GK];
+        slots.extend(std::iter::repeat_n(Position::DF, self.defenders));
+        slots.extend(std::iter::repeat_n(Position::MF, self.midfielders));
+        slots.extend(std::iter::repeat_n(Position::FW, self.forwards));
+        slots
+    }
+}
+
+/// Eleven players in a formation, plus substitutes. Players are referenced by
+/// character number.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Team {
+    /// Database id, `None` until the team is saved.
+    pub id: Option<i64>,
+    pub name: String,
+    pub formation: Formation,
+    /// One entry per slot of the formation.
+    pub players: [Option<u16>; TEAM_SIZE],
+    pub bench: Vec<u16>,
+}
+
+impl Team {
+    pub fn new(name: &str) -> Team {
+        Team {
+            id: None,
+            name: name.to_string(),
+            formation: FORMATION_LIST[0],
+            players: [None; TEAM_SIZE],
+            bench: Vec::new(),
+        }
+    }
+
+    pub fn contains(&self, number: u16) -> bool {
+        self.players.contains(&Some(number)) || self.bench.contains(&number)
+    }
+
+    /// Removes a character from the pitch and the bench.
+    pub fn remove(&mut self, number: u16) {
+        for slot in self.players.iter_mut() {
+            if *slot == Some(number) {
+                *slot = None;
+            }
+        }
+        self.bench.retain(|other| *other != number);
+    }
+
+    /// Puts a character in a slot, moving it if it was elsewhere in the team.
+    /// The player previously in the slot takes the character's old place.
+    pub fn assign(&mut self, slot: usize, number: u16) {
+        let previous = self.players[slot];
+        let old_slot = self.players.iter().position(|other| *other == Some(number));
+        let old_bench = self.bench.iter().position(|other| *other == number);
+
+        match (old_slot, old_bench, previous) {
+            (Some(old_slot), _, _) => self.players[old_slot] = previous,
+            (None, Some(index), Some(previous)) => self.bench[index] = previous,
+            (None, Some(index), None) => {
+                self.bench.remove(index);
+            }
+            _ => (),
+        }
+
+        self.players[slot] = Some(number);
+    }
+
+    /// Adds a character to the bench, returning false if it is full.
+    pub fn add_to_bench(&mut self, number: u16) -> bool {
+        if self.bench.contains(&number) {
+            return true;
+        }
+        if self.bench.len() >= BENCH_SIZE {
+            return false;
+        }
+
+        self.remove(number);
+        self.bench.push(number);
+        true
+    }
+
+    /// First empty slot expecting the given position, or else the first empty
+    /// slot.
+    pub fn free_slot(&self, position: Position) -> Option<usize> {
+        let slots = self.formation.slots();
+        let free = |slot: &usize| self.players[*slot].is_none();
+
+        (0..TEAM_SIZE)
+            .filter(free)
+            .find(|slot| slots[*slot] == position)
+            .or_else(|| (0..TEAM_SIZE).find(free))
+    }
+
+    /// Members of the team, starters first.
+    pub fn members(&self) -> Vec<u16> {
+        self.players
+            .iter()
+            .flatten()
+            .chain(self.bench.iter())
+            .copied()
+            .collect()
+    }
+
+    /// Slots whose player doesn't play the position the formation expects.
+    pub fn misfits(&self, characters: &[Character]) -> Vec<usize> {
+        self.formation
+            .slots()
+            .iter()
+            .enumerate()
+            .filter(|(slot, position)| {
+                self.players[*slot]
+                    .and_then(|number| find(characters, number))
+                    .is_some_and(|character| character.position != **position)
+            })
+            .map(|(slot, _)| slot)
+            .collect()
+    }
+
+    /// Serializes the team as `key=value` lines, players being written as
+    /// character numbers.
+    pub fn export(&self) -> String {
+        let slots = self.formation.slots();
+        let mut lines = vec![
+            "# Inazugle Scraper team".to_string(),
+            format!("name={}", self.name),
+            format!("formation={}", self.formation.name),
+        ];
+
+        for (position, player) in slots.iter().zip(self.players) {
+            let player = player.map(|number| number.to_string()).unwrap_or_default();
+            lines.push(format!("{}={}", position.to_str(), player));
+        }
+
+        let bench: Vec<String> = self.bench.iter().map(|number| number.to_string()).collect();
+        lines.push(format!("bench={}", bench.join(",")));
+
+        lines.join("\n")
+    }
+
+    /// Parses a team written by `export`.
+    pub fn import(text: &str) -> Result<Team, String> {
+        let mut team = Team::new("Imported team");
+        let mut slot = 0;
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("line {}: expected key=value", index + 1));
+            };
+            let value = value.trim();
+
+            match key.trim() {
+                "name" => team.name = value.to_string(),
+                "formation" => {
+                    team.formation = Formation::from_name(value)
+                        .ok_or_else(|| format!("line {}: unknown formation \"{value}\"", index + 1))?;
+                }
+                "bench" => {
+                    team.bench = value
+                        .split(',')
+                        .filter(|number| !number.trim().is_empty())
+                        .map(|number| number.trim().parse().map_err(|_| format!("line {}: invalid number \"{number}\"", index + 1)))
+                        .collect::<Result<_, _>>()?;
+                    team.bench.truncate(BENCH_SIZE);
+                }
+                _ => {
+                    if slot >= TEAM_SIZE {
+                        return Err(format!("line {}: more than {TEAM_SIZE} players", index + 1));
+                    }
+
+                    if !value.is_empty() {
+                        let number = value.parse().map_err(|_| format!("line {}: invalid number \"{value}\"", index + 1))?;
+                        team.players[slot] = Some(number);
+                    }
+                    slot += 1;
+                }
+            }
+        }
+
+        Ok(team)
+    }
+}
+
+/// Aggregated stats of the starting eleven.
+#[derive(Debug, Clone, Default)]
+pub struct TeamSummary {
+    pub player_count: usize,
+    /// Sum of each stat, in `STAT_LIST` order.
+    pub totals: [u32; 7],
+    /// Number of players of each element, in `ELEMENT_LIST` order.
+    pub elements: [usize; 4],
+}
+
+impl TeamSummary {
+    pub fn new(team: &Team, characters: &[Character]) -> TeamSummary {
+        let mut summary = TeamSummary::default();
+
+        for character in team.players.iter().flatten().filter_map(|number| find(characters, *number)) {
+            summary.player_count += 1;
+
+            if let Some(stats) = &character.stats {
+                for (total, stat) in summary.totals.iter_mut().zip(STAT_LIST) {
+                    *total += stats.get(stat) as u32;
+                }
+            }
+
+            if let Some(index) = ELEMENT_LIST.iter().position(|element| *element == character.element) {
+                summary.elements[index] += 1;
+            }
+        }
+
+        summary
+    }
+
+    pub fn average(&self, stat_index: usize) -> f64 {
+        if self.player_count == 0 {
+            return 0.0;
+        }
+        self.totals[stat_index] as f64 / self.player_count as f64
+    }
+
+    pub fn element_count(&self, element: Element) -> usize {
+        ELEMENT_LIST
+            .iter()
+            .position(|other| *other == element)
+            .map_or(0, |index| self.elements[index])
+    }
+}
+
+pub fn find(characters: &[Character], number: u16) -> Option<&Character> {
+    characters.iter().find(|character| character.number == number)
+}
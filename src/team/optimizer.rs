@@ -0,0 +1,577 @@
+use crate::{
+    database::Database,
+    query::Query,
+    team::{FORMATION_LIST, Formation, TEAM_SIZE, Team},
+    utils::{Character, ELEMENT_LIST, GameFlags, POSITION_LIST, Position, STAT_LIST},
+};
+
+/// Weight of each stat, in `STAT_LIST` order.
+pub type StatWeights = [f64; 7];
+
+/// Upper bound on the number of lineups explored, keeping the search
+/// responsive when constraints make the bounds loose.
+const SEARCH_BUDGET: usize = 5_000_000;
+
+/// Constraints and objective of the team optimizer.
+#[derive(Debug, Clone)]
+pub struct OptimizerConfig {
+    pub formation: Formation,
+    /// Stat weights of the slots of each position, in `POSITION_LIST` order.
+    /// The score of a player in a slot is the weighted sum of their stats.
+    pub weights: [StatWeights; 4],
+    /// Weights replacing those of the position for single slots, in the slot
+    /// order of the formation.
+    pub slot_weights: [Option<StatWeights>; TEAM_SIZE],
+    /// Minimum number of starters of each element, in `ELEMENT_LIST` order.
+    pub min_elements: [usize; 4],
+    /// Characters that can't be picked, by number.
+    pub banned: Vec<u16>,
+    /// Only pick characters known to appear in one of these games. Empty
+    /// flags allow every character.
+    pub games: GameFlags,
+    /// Multiplier of the score of a player in a slot of another position, or
+    /// `None` to only put players in their own position.
+    pub off_position_factor: Option<f64>,
+    /// Number of lineups to return.
+    pub alternatives: usize,
+}
+
+impl Default for OptimizerConfig {
+    fn default() -> Self {
+        OptimizerConfig {
+            formation: FORMATION_LIST[0],
+            weights: [
+                // kick, control, technique, pressure, physical, agility, intelligence
+                // FW
+                [1.0, 1.0, 1.0, 0.0, 0.25, 1.0, 0.25],
+                // MF
+                [0.5, 1.0, 1.0, 0.5, 0.25, 0.5, 1.0],
+                // DF
+                [0.25, 0.25, 0.5, 1.0, 1.0, 0.5, 1.0],
+                // GK
+                [0.0, 0.0, 0.5, 1.0, 1.0, 0.5, 0.5],
+            ],
+            slot_weights: [None; TEAM_SIZE],
+            min_elements: [0; 4],
+            banned: Vec::new(),
+            games: GameFlags::empty(),
+            off_position_factor: None,
+            alternatives: 5,
+        }
+    }
+}
+
+impl OptimizerConfig {
+    pub fn weights_for(&self, position: Position) -> Option<&StatWeights> {
+        POSITION_LIST
+            .iter()
+            .position(|other| *other == position)
+            .map(|index| &self.weights[index])
+    }
+
+    /// Weights of a slot of the formation: its own ones if set, otherwise
+    /// those of its position.
+    pub fn slot_weights_for(&self, slot: usize) -> Option<&StatWeights> {
+        match self.slot_weights.get(slot)? {
+            Some(weights) => Some(weights),
+            None => self.weights_for(*self.formation.slots().get(slot)?),
+        }
+    }
+
+    /// Score of a character in a slot of a position with the weights of the
+    /// position, or `None` if they can't play there.
+    pub fn score(&self, character: &Character, slot: Position) -> Option<f64> {
+        self.score_with(character, slot, self.weights_for(slot)?)
+    }
+
+    /// Score of a character in a slot of the formation, by index.
+    pub fn slot_score(&self, character: &Character, slot: usize) -> Option<f64> {
+        let position = *self.formation.slots().get(slot)?;
+        self.score_with(character, position, self.slot_weights_for(slot)?)
+    }
+
+    fn score_with(&self, character: &Character, slot: Position, weights: &StatWeights) -> Option<f64> {
+        let stats = character.stats.as_ref()?;
+
+        let score: f64 = STAT_LIST
+            .iter()
+            .zip(weights)
+            .map(|(stat, weight)| stats.get(*stat) as f64 * weight)
+            .sum();
+
+        if character.position == slot {
+            Some(score)
+        } else {
+            self.off_position_factor.map(|factor| score * factor)
+        }
+    }
+}
+
+/// Eleven players in the slot order of the formation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lineup {
+    pub players: [u16; TEAM_SIZE],
+    pub score: f64,
+}
+
+impl Lineup {
+    pub fn to_team(&self, name: &str, formation: Formation) -> Team {
+        let mut team = Team::new(name);
+        team.formation = formation;
+        team.players = self.players.map(Some);
+        team
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OptimizerResult {
+    /// Best lineups found, best first.
+    pub lineups: Vec<Lineup>,
+    /// False when the search was cut short, in which case better lineups may
+    /// exist.
+    pub complete: bool,
+}
+
+/// Picks the best elevens among the cached characters.
+pub fn optimize_cached(database: &Database, config: &OptimizerConfig) -> OptimizerResult {
+    optimize(&eligible_characters(database, &config.games), config)
+}
+
+/// Cached characters known to appear in one of the games, or every cached
+/// character when no game is given.
+pub fn eligible_characters(database: &Database, games: &GameFlags) -> Vec<Character> {
+    let mut characters = database.query_characters(&Query::All);
+
+    if !games.is_empty() {
+        let in_games = database.characters_in_games(games);
+        characters.retain(|character| in_games.contains(&character.number));
+    }
+
+    characters
+}
+
+/// Picks the elevens maximizing the sum of the slot scores under the
+/// constraints, by a branch and bound search over the slots.
+pub fn optimize(characters: &[Character], config: &OptimizerConfig) -> OptimizerResult {
+    let slots = config.formation.slots();
+
+    // Slots sharing a position and weights are interchangeable, so they share
+    // their candidates
+    let mut groups: Vec<(Position, StatWeights)> = Vec::new();
+    let slot_groups: Vec<usize> = slots
+        .iter()
+        .enumerate()
+        .map(|(slot, position)| {
+            let weights = *config.slot_weights_for(slot).unwrap_or(&[0.0; 7]);
+
+            groups
+                .iter()
+                .position(|group| *group == (*position, weights))
+                .unwrap_or_else(|| {
+                    groups.push((*position, weights));
+                    groups.len() - 1
+                })
+        })
+        .collect();
+
+    let candidates: Vec<Vec<Candidate>> = groups
+        .iter()
+        .map(|(position, weights)| candidates(characters, config, *position, weights))
+        .collect();
+
+    // Slots are searched group by group so that interchangeable ones follow
+    // each other
+    let mut order: Vec<usize> = (0..slots.len()).collect();
+    order.sort_by_key(|slot| slot_groups[*slot]);
+
+    let mut search = Search {
+        config,
+        slots: order.iter().map(|slot| slot_groups[*slot]).collect(),
+        order,
+        candidates: &candidates,
+        best: Vec::new(),
+        picks: Vec::with_capacity(TEAM_SIZE),
+        element_counts: [0; 4],
+        nodes: 0,
+    };
+
+    if config.alternatives > 0 {
+        search.explore(0, 0.0);
+    }
+
+    OptimizerResult {
+        complete: search.nodes < SEARCH_BUDGET,
+        lineups: search.best,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    number: u16,
+    /// Index in `ELEMENT_LIST`, if the character has an element.
+    element: Option<usize>,
+    score: f64,
+}
+
+/// Characters able to play a slot of a position with the given weights, best
+/// first. Only the best of each element are kept: any lineup using another one
+/// can swap them for an unused better character of the same element.
+fn candidates(characters: &[Character], config: &OptimizerConfig, position: Position, weights: &StatWeights) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = characters
+        .iter()
+        .filter(|character| !config.banned.contains(&character.number))
+        .filter_map(|character| {
+            Some(Candidate {
+                number: character.number,
+                element: ELEMENT_LIST.iter().position(|element| *element == character.element),
+                score: config.score_with(character, position, weights)?,
+            })
+        })
+        .collect();
+
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.number.cmp(&b.number)));
+
+    let keep = TEAM_SIZE + config.alternatives;
+    let mut kept = [0; 5];
+    candidates.retain(|candidate| {
+        let group = candidate.element.unwrap_or(ELEMENT_LIST.len());
+        kept[group] += 1;
+        kept[group] <= keep
+    });
+
+    candidates
+}
+
+struct Search<'a> {
+    config: &'a OptimizerConfig,
+    /// Group of each searched slot, indexing `candidates`.
+    slots: Vec<usize>,
+    /// Formation slot of each searched slot.
+    order: Vec<usize>,
+    candidates: &'a [Vec<Candidate>],
+    best: Vec<Lineup>,
+    /// Candidate index picked for each filled slot.
+    picks: Vec<usize>,
+    element_counts: [usize; 4],
+    nodes: usize,
+}
+
+impl Search<'_> {
+    fn explore(&mut self, slot: usize, score: f64) {
+        self.nodes += 1;
+        if self.nodes >= SEARCH_BUDGET {
+            return;
+        }
+
+        if slot == TEAM_SIZE {
+            self.record(score);
+            return;
+        }
+
+        let group = self.slots[slot];
+        // Slots of a group are interchangeable, so their candidates are picked
+        // in list order to explore each set of players once.
+        let start = match slot.checked_sub(1) {
+            Some(previous) if self.slots[previous] == group => self.picks[previous] + 1,
+            _ => 0,
+        };
+
+        let candidates = &self.candidates[group];
+
+        for (index, candidate) in candidates.iter().enumerate().skip(start) {
+
+            if score + self.bound(slot, index) <= self.threshold() {
+                break;
+            }
+
+            if self.is_picked(candidate.number) {
+                continue;
+            }
+
+            if let Some(element) = candidate.element {
+                self.element_counts[element] += 1;
+            }
+
+            if self.is_feasible(slot + 1) {
+                self.picks.push(index);
+                self.explore(slot + 1, score + candidate.score);
+                self.picks.pop();
+            }
+
+            if let Some(element) = candidate.element {
+                self.element_counts[element] -= 1;
+            }
+
+            if self.nodes >= SEARCH_BUDGET {
+                return;
+            }
+        }
+    }
+
+    /// Highest score the remaining slots can add when the candidate `index`
+    /// fills `slot`, ignoring that a character can only be picked once.
+    fn bound(&self, slot: usize, index: usize) -> f64 {
+        let group = self.slots[slot];
+        let group_left = self.slots[slot..].iter().filter(|other| **other == group).count();
+
+        let mut bound: f64 = self.candidates[group]
+            .iter()
+            .skip(index)
+            .take(group_left)
+            .map(|candidate| candidate.score)
+            .sum();
+
+        // Groups after the current one start from their best candidate
+        for other in 0..self.candidates.len() {
+            if other == group || !self.slots[slot..].contains(&other) {
+                continue;
+            }
+
+            let count = self.slots.iter().filter(|slot| **slot == other).count();
+            bound += self.candidates[other].iter().take(count).map(|candidate| candidate.score).sum::<f64>();
+        }
+
+        bound
+    }
+
+    /// Score to beat to enter the ranking.
+    fn threshold(&self) -> f64 {
+        if self.best.len() < self.config.alternatives {
+            f64::NEG_INFINITY
+        } else {
+            self.best.last().map_or(f64::NEG_INFINITY, |lineup| lineup.score)
+        }
+    }
+
+    fn is_picked(&self, number: u16) -> bool {
+        self.picks
+            .iter()
+            .enumerate()
+            .any(|(slot, index)| self.candidates[self.slots[slot]][*index].number == number)
+    }
+
+    /// Whether the slots from `slot` on can still satisfy the element minimums.
+    fn is_feasible(&self, slot: usize) -> bool {
+        let missing: usize = self.config.min_elements
+            .iter()
+            .zip(self.element_counts)
+            .map(|(min, count)| min.saturating_sub(count))
+            .sum();
+
+        missing <= TEAM_SIZE - slot
+    }
+
+    fn record(&mut self, score: f64) {
+        let mut players = [0; TEAM_SIZE];
+        for (slot, index) in self.picks.iter().enumerate() {
+            players[self.order[slot]] = self.candidates[self.slots[slot]][*index].number;
+        }
+
+        // With players out of position, the same players can be arranged in
+        // several ways: only the best arrangement is kept.
+        let mut members = players;
+        members.sort_unstable();
+        if let Some(index) = self.best.iter().position(|lineup| {
+            let mut other = lineup.players;
+            other.sort_unstable();
+            other == members
+        }) {
+            if self.best[index].score >= score {
+                return;
+            }
+            self.best.remove(index);
+        }
+
+        let position = self.best.partition_point(|lineup| lineup.score >= score);
+        self.best.insert(position, Lineup { players, score });
+        self.best.truncate(self.config.alternatives);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{Element, Stat, Stats};
+
+    fn forward(number: u16, kick: u8, pressure: u8) -> Character {
+        Character {
+            number,
+            name: format!("Player {number}"),
+            nickname: String::new(),
+            japanese_name: String::new(),
+            japanese_nickname: String::new(),
+            school: String::new(),
+            element: Element::FIRE,
+            position: Position::FW,
+            stats: Some(Stats {
+                kick,
+                control: 50,
+                technique: 50,
+                pressure,
+                physical: 50,
+                agility: 50,
+                intelligence: 50,
+            }),
+            page_url: String::new(),
+            portrait_url: String::new(),
+            my_team_key: String::new(),
+        }
+    }
+
+    #[test]
+    fn default_weights_favor_kick_for_forwards() {
+        let config = OptimizerConfig::default();
+        let striker = forward(1, 99, 10);
+        let stopper = forward(2, 10, 99);
+
+        assert!(config.score(&striker, Position::FW) > config.score(&stopper, Position::FW));
+    }
+
+    #[test]
+    fn default_weights_favor_pressure_for_goalkeepers() {
+        let config = OptimizerConfig::default();
+        let weights = config.weights_for(Position::GK).unwrap();
+
+        assert!(weights[Stat::Pressure.index()] > weights[Stat::Kick.index()]);
+    }
+
+    /// A character with every stat at `value`.
+    fn player(number: u16, position: Position, element: Element, value: u8) -> Character {
+        let mut character = forward(number, value, value);
+        character.position = position;
+        character.element = element;
+        character.stats = character.stats.map(|stats| Stats {
+            control: value,
+            technique: value,
+            physical: value,
+            agility: value,
+            intelligence: value,
+            ..stats
+        });
+        character
+    }
+
+    /// Two goalkeepers and five players of every other position, numbered by
+    /// position with the best first: GK 1-2, DF 10-14, MF 20-24, FW 30-34.
+    fn pool() -> Vec<Character> {
+        let mut pool = vec![player(1, Position::GK, Element::FIRE, 90), player(2, Position::GK, Element::FIRE, 80)];
+        for (base, position) in [(10, Position::DF), (20, Position::MF), (30, Position::FW)] {
+            for rank in 0..5 {
+                pool.push(player(base + rank, position, Element::FIRE, 90 - rank as u8 * 10));
+            }
+        }
+        pool
+    }
+
+    fn members(lineup: &Lineup) -> Vec<u16> {
+        let mut members = lineup.players.to_vec();
+        members.sort_unstable();
+        members
+    }
+
+    fn find_position(pool: &[Character], number: u16) -> Position {
+        pool.iter().find(|character| character.number == number).unwrap().position
+    }
+
+    fn element_index(element: Element) -> usize {
+        ELEMENT_LIST.iter().position(|other| *other == element).unwrap()
+    }
+
+    #[test]
+    fn picks_the_best_of_each_line() {
+        let config = OptimizerConfig { alternatives: 1, ..OptimizerConfig::default() };
+        let pool = pool();
+        let result = optimize(&pool, &config);
+
+        assert!(result.complete);
+        let lineup = &result.lineups[0];
+        // 4-4-2
+        assert_eq!(members(lineup), vec![1, 10, 11, 12, 13, 20, 21, 22, 23, 30, 31]);
+
+        let slots = config.formation.slots();
+        for (position, number) in slots.iter().zip(lineup.players) {
+            assert_eq!(find_position(&pool, number), *position);
+        }
+
+        let expected: f64 = lineup.players.iter().map(|number| {
+            let character = pool.iter().find(|character| character.number == *number).unwrap();
+            config.score(character, character.position).unwrap()
+        }).sum();
+        assert!((lineup.score - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn element_minimums_are_met_or_give_nothing() {
+        let mut pool = pool();
+        pool.push(player(40, Position::MF, Element::WIND, 10));
+
+        let mut config = OptimizerConfig { alternatives: 1, ..OptimizerConfig::default() };
+        config.min_elements[element_index(Element::WIND)] = 1;
+        assert!(members(&optimize(&pool, &config).lineups[0]).contains(&40));
+
+        config.min_elements[element_index(Element::WIND)] = 2;
+        assert!(optimize(&pool, &config).lineups.is_empty());
+    }
+
+    #[test]
+    fn banned_characters_are_left_out() {
+        let config = OptimizerConfig { alternatives: 1, banned: vec![1, 30], ..OptimizerConfig::default() };
+        let lineup = &optimize(&pool(), &config).lineups[0];
+
+        assert!(!lineup.players.contains(&1));
+        assert!(!lineup.players.contains(&30));
+        assert!(lineup.players.contains(&2));
+        assert!(lineup.players.contains(&32));
+    }
+
+    #[test]
+    fn off_position_players_only_fill_in_with_a_factor() {
+        let mut pool = pool();
+        pool.retain(|character| character.position != Position::GK);
+
+        let mut config = OptimizerConfig { alternatives: 1, ..OptimizerConfig::default() };
+        assert!(optimize(&pool, &config).lineups.is_empty());
+
+        config.off_position_factor = Some(0.5);
+        let lineup = &optimize(&pool, &config).lineups[0];
+        let keeper = pool.iter().find(|character| character.number == lineup.players[0]).unwrap();
+
+        assert_ne!(keeper.position, Position::GK);
+        let value = keeper.stats.as_ref().unwrap().kick as f64;
+        let full = config.weights_for(Position::GK).unwrap().iter().map(|weight| weight * value).sum::<f64>();
+        assert_eq!(config.score(keeper, Position::GK), Some(full * 0.5));
+    }
+
+    #[test]
+    fn alternatives_are_distinct_and_ranked() {
+        let config = OptimizerConfig { alternatives: 4, ..OptimizerConfig::default() };
+        let lineups = optimize(&pool(), &config).lineups;
+
+        assert_eq!(lineups.len(), 4);
+        for (index, lineup) in lineups.iter().enumerate() {
+            for other in &lineups[index + 1..] {
+                assert_ne!(members(lineup), members(other));
+                assert!(lineup.score >= other.score);
+            }
+        }
+    }
+
+    #[test]
+    fn slot_weights_override_those_of_the_position() {
+        let mut pool = pool();
+        // The worst forward at kicking but the best at pressure
+        pool.push(forward(35, 0, 255));
+
+        let mut config = OptimizerConfig { alternatives: 1, ..OptimizerConfig::default() };
+        assert!(!optimize(&pool, &config).lineups[0].players.contains(&35));
+
+        let mut pressure_only = [0.0; 7];
+        pressure_only[Stat::Pressure.index()] = 1.0;
+        // The last slot is the second forward of the 4-4-2
+        config.slot_weights[TEAM_SIZE - 1] = Some(pressure_only);
+
+        let lineup = &optimize(&pool, &config).lineups[0];
+        assert_eq!(lineup.players[TEAM_SIZE - 1], 35);
+        assert_eq!(config.slot_score(&pool[pool.len() - 1], TEAM_SIZE - 1), Some(255.0));
+    }
+}
@@ -4,12 +4,25 @@ use std::{
         Arc,
         atomic::{AtomicBool, AtomicU8, AtomicU16, Ordering},
     },
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use bitflags::bitflags;
 
 pub const BASE_URL: &str = "https://zukan.inazuma.jp";
 pub const SEARCH_URL: &str = "/en/chara_list/process_form";
+/// Search form of the Japanese version of the website, which is served
+/// without a language prefix.
+pub const SEARCH_URL_JA: &str = "/chara_list/process_form";
+
+/// Resolves a link found on the website, which may be relative to it.
+pub fn absolute_url(href: &str) -> String {
+    if href.is_empty() || href.starts_with("http") {
+        href.to_string()
+    } else {
+        BASE_URL.to_owned() + href
+    }
+}
 
 pub const ELEMENT_LIST: [Element; 4] = [
     Element::FIRE,
@@ -67,6 +80,16 @@ impl Element {
         }
     }
 
+    pub fn from_db_str(text: &str) -> Element {
+        match text {
+            "Mountain" => Self::MOUNTAIN,
+            "Fire" => Self::FIRE,
+            "Forest" => Self::FOREST,
+            "Wind" => Self::WIND,
+            _ => Self::NONE,
+        }
+    }
+
     pub fn flag(self) -> ElementFlags {
         match self {
             Element::FOREST => ElementFlags::FOREST,
@@ -109,6 +132,16 @@ impl Position {
         }
     }
 
+    pub fn from_db_str(text: &str) -> Position {
+        match text {
+            "GK" => Self::GK,
+            "DF" => Self::DF,
+            "MF" => Self::MF,
+            "FW" => Self::FW,
+            _ => Self::NONE,
+        }
+    }
+
     pub fn flag(&self) -> PositionFlags {
         match self {
             Position::GK    => PositionFlags::GK,
@@ -131,6 +164,7 @@ bitflags! {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Game {
     IE1,
     IE2,
@@ -204,18 +238,119 @@ bitflags! {
 
 /// This structure stores the basic information about a character that is displayed
 /// on the search results.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Character {
     pub number: u16,
     pub name: String,
     pub nickname: String,
+    /// Name on the Japanese version of the website, empty when unknown.
+    pub japanese_name: String,
+    pub japanese_nickname: String,
+    /// Team or school the character belongs to, empty when unknown.
+    pub school: String,
     pub element: Element,
     pub position: Position,
     pub stats: Option<Stats>,
     pub page_url: String,
+    /// Portrait image, empty when unknown.
+    pub portrait_url: String,
+    /// Value of the My Team checkbox of the search results, which is what the
+    /// website keeps in its My Team. Only known for search results.
+    pub my_team_key: String,
+}
+
+impl Character {
+    /// Name in the chosen language, falling back to the English one when the
+    /// Japanese name is unknown.
+    pub fn display_name(&self, japanese: bool) -> &str {
+        if japanese && !self.japanese_name.is_empty() { &self.japanese_name } else { &self.name }
+    }
+
+    pub fn display_nickname(&self, japanese: bool) -> &str {
+        if japanese && !self.japanese_nickname.is_empty() { &self.japanese_nickname } else { &self.nickname }
+    }
+}
+
+/// Everything the page of a character says about them besides the stats.
+/// Fields the page doesn't show are empty.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CharacterProfile {
+    /// Team or school the character belongs to.
+    pub affiliation: String,
+    pub gender: String,
+    /// School grade or age.
+    pub grade: String,
+    pub height: String,
+    /// How to recruit the character in the games.
+    pub recruitment: String,
+    pub rarity: String,
+    pub description: String,
+    /// Names of the special moves the character learns, in page order.
+    pub techniques: Vec<String>,
+    /// Labelled entries of the page that match none of the fields above.
+    pub other: Vec<(String, String)>,
+}
+
+impl CharacterProfile {
+    /// The field an entry of the page goes to, recognized from its whole
+    /// label in English or Japanese. Labels merely containing a known one,
+    /// like "Team technique", go nowhere.
+    pub fn field_mut(&mut self, label: &str) -> Option<&mut String> {
+        let label = normalize_label(label);
+        let is = |labels: &[&str]| labels.contains(&label.as_str());
+
+        if is(&["affiliation", "team", "school", "所属", "チーム", "学校"]) {
+            Some(&mut self.affiliation)
+        } else if is(&["gender", "sex", "性別"]) {
+            Some(&mut self.gender)
+        } else if is(&["grade", "school year", "year", "age", "学年", "年齢"]) {
+            Some(&mut self.grade)
+        } else if is(&["height", "身長"]) {
+            Some(&mut self.height)
+        } else if is(&["recruitment", "how to recruit", "scout", "how to obtain", "obtain", "スカウト", "入手方法", "入手"]) {
+            Some(&mut self.recruitment)
+        } else if is(&["rarity", "rank", "レアリティ", "ランク"]) {
+            Some(&mut self.rarity)
+        } else if is(&["description", "profile", "紹介", "プロフィール"]) {
+            Some(&mut self.description)
+        } else {
+            None
+        }
+    }
+
+    /// Whether an entry of the page lists the special moves of the
+    /// character. "Team technique" and the like don't.
+    pub fn is_techniques_label(label: &str) -> bool {
+        [
+            "technique", "techniques", "special move", "special moves", "hissatsu", "moves",
+            "必殺技", "ひっさつ技", "技",
+        ]
+        .contains(&normalize_label(label).as_str())
+    }
+
+    /// The labelled fields that are known, in display order.
+    pub fn fields(&self) -> Vec<(&str, &str)> {
+        [
+            ("Affiliation", self.affiliation.as_str()),
+            ("Gender", self.gender.as_str()),
+            ("Grade", self.grade.as_str()),
+            ("Height", self.height.as_str()),
+            ("Recruitment", self.recruitment.as_str()),
+            ("Rarity", self.rarity.as_str()),
+        ]
+        .into_iter()
+        .chain(self.other.iter().map(|(label, value)| (label.as_str(), value.as_str())))
+        .filter(|(_, value)| !value.is_empty())
+        .collect()
+    }
 }
 
-#[derive(Debug)]
+/// A label of the page without its trailing colon, lowercased.
+fn normalize_label(label: &str) -> String {
+    label.trim().trim_end_matches([':', '：']).trim_end().to_lowercase()
+}
+
+#[derive(Debug, Clone)]
 pub struct Stats {
     pub kick: u8,
     pub control: u8,
@@ -226,6 +361,204 @@ pub struct Stats {
     pub intelligence: u8,
 }
 
+impl Stats {
+    pub fn get(&self, stat: Stat) -> u8 {
+        match stat {
+            Stat::Kick => self.kick,
+            Stat::Control => self.control,
+            Stat::Technique => self.technique,
+            Stat::Pressure => self.pressure,
+            Stat::Physical => self.physical,
+            Stat::Agility => self.agility,
+            Stat::Intelligence => self.intelligence,
+        }
+    }
+
+    pub fn set(&mut self, stat: Stat, value: u8) {
+        match stat {
+            Stat::Kick => self.kick = value,
+            Stat::Control => self.control = value,
+            Stat::Technique => self.technique = value,
+            Stat::Pressure => self.pressure = value,
+            Stat::Physical => self.physical = value,
+            Stat::Agility => self.agility = value,
+            Stat::Intelligence => self.intelligence = value,
+        }
+    }
+}
+
+pub const STAT_LIST: [Stat; 7] = [
+    Stat::Kick,
+    Stat::Control,
+    Stat::Technique,
+    Stat::Pressure,
+    Stat::Physical,
+    Stat::Agility,
+    Stat::Intelligence,
+];
+
+/// One of the seven fields of `Stats`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stat {
+    Kick,
+    Control,
+    Technique,
+    Pressure,
+    Physical,
+    Agility,
+    Intelligence,
+}
+
+impl Stat {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Stat::Kick => "Kick",
+            Stat::Control => "Control",
+            Stat::Technique => "Technique",
+            Stat::Pressure => "Pressure",
+            Stat::Physical => "Physical",
+            Stat::Agility => "Agility",
+            Stat::Intelligence => "Intelligence",
+        }
+    }
+
+    /// Name of the column storing this stat in the `characters` table.
+    pub fn db_column(self) -> &'static str {
+        match self {
+            Stat::Kick => "kick",
+            Stat::Control => "control",
+            Stat::Technique => "technique",
+            Stat::Pressure => "pressure",
+            Stat::Physical => "physical",
+            Stat::Agility => "agility",
+            Stat::Intelligence => "intelligence",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Stat> {
+        STAT_LIST
+            .into_iter()
+            .find(|stat| stat.to_str().eq_ignore_ascii_case(name))
+    }
+
+    /// Position of the stat in `STAT_LIST`.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Inclusive min/max bounds on each stat. A stat whose bounds cover the whole
+/// `u8` range doesn't filter anything.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatFilters {
+    min: [u8; 7],
+    max: [u8; 7],
+}
+
+impl Default for StatFilters {
+    fn default() -> Self {
+        StatFilters {
+            min: [u8::MIN; 7],
+            max: [u8::MAX; 7],
+        }
+    }
+}
+
+impl StatFilters {
+    pub fn min_mut(&mut self, stat: Stat) -> &mut u8 {
+        &mut self.min[stat.index()]
+    }
+
+    pub fn max_mut(&mut self, stat: Stat) -> &mut u8 {
+        &mut self.max[stat.index()]
+    }
+
+    pub fn range(&self, stat: Stat) -> (u8, u8) {
+        (self.min[stat.index()], self.max[stat.index()])
+    }
+
+    pub fn is_active(&self, stat: Stat) -> bool {
+        self.range(stat) != (u8::MIN, u8::MAX)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !STAT_LIST.iter().any(|stat| self.is_active(*stat))
+    }
+
+    pub fn reset(&mut self) {
+        *self = StatFilters::default();
+    }
+
+    pub fn matches(&self, stats: &Stats) -> bool {
+        STAT_LIST.iter().all(|stat| {
+            let (min, max) = self.range(*stat);
+            (min..=max).contains(&stats.get(*stat))
+        })
+    }
+
+    /// Serializes the active bounds as `Kick:80-255,Agility:70-255`.
+    pub fn to_spec(&self) -> String {
+        STAT_LIST
+            .iter()
+            .filter(|stat| self.is_active(**stat))
+            .map(|stat| {
+                let (min, max) = self.range(*stat);
+                format!("{}:{}-{}", stat.to_str(), min, max)
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Parses bounds produced by `to_spec`, ignoring malformed entries.
+    pub fn from_spec(spec: &str) -> StatFilters {
+        let mut filters = StatFilters::default();
+
+        for entry in spec.split(',') {
+            let Some((name, range)) = entry.split_once(':') else { continue };
+            let Some((min, max)) = range.split_once('-') else { continue };
+            let Some(stat) = Stat::from_name(name) else { continue };
+
+            if let (Ok(min), Ok(max)) = (min.parse(), max.parse()) {
+                filters.min[stat.index()] = min;
+                filters.max[stat.index()] = max;
+            }
+        }
+
+        filters
+    }
+}
+
+/// Returns the current time as seconds since the Unix epoch.
+pub fn now_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Formats a Unix timestamp as `YYYY-MM-DD HH:MM` (UTC).
+pub fn format_timestamp(timestamp: i64) -> String {
+    let days = timestamp.div_euclid(86_400);
+    let seconds = timestamp.rem_euclid(86_400);
+
+    // Civil date from days since epoch (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+        seconds / 3_600,
+        (seconds % 3_600) / 60
+    )
+}
+
 pub struct Progress {
     internal: Arc<InternalProgress>,
 }